@@ -0,0 +1,462 @@
+//! `/artist`: every song of an artist in maimai, CHUNITHM and O.N.G.E.K.I.
+//!
+//! Credits are split into individual artists ("A feat. B", "A vs. B", "A × B", "A (CV: B)"),
+//! and spellings are matched ignoring case, width and spaces. `data/aliases/artists.tsv` lists
+//! other names of an artist, one `name<TAB>alias<TAB>...` line per artist; credits that are
+//! themselves a name in that file are never split.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use eyre::WrapErr;
+use ordered_float::OrderedFloat;
+use poise::serenity_prelude::{ButtonStyle, CreateActionRow, CreateButton, CreateComponents};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    catalog::*,
+    components::{Action, ComponentId},
+    cooldowns::cooldown_check,
+    crossover::Charts,
+    document::Document,
+    settings::query_settings,
+    utils::*,
+};
+
+/// Relative to `data_dir`.
+pub const ARTIST_ALIASES_FILE: &str = "aliases/artists.tsv";
+/// Game slot of artist page custom ids.
+const ALL_GAMES: &str = "all";
+/// Songs per page of `/artist`.
+const PAGE_LEN: usize = 15;
+/// How close a name has to be to be offered when no artist matches exactly.
+const MIN_SIMILARITY: f64 = 0.8;
+
+/// Where credits are split, lowercase; longer ones first so " feat. " wins over "feat.".
+const SEPARATORS: [&str; 19] = [
+    " fused with ",
+    " featuring ",
+    " feat. ",
+    " feat.",
+    "feat.",
+    " ft. ",
+    " with ",
+    " vs. ",
+    " vs ",
+    " × ",
+    "×",
+    " x ",
+    " & ",
+    "＆",
+    " / ",
+    "／",
+    ", ",
+    "、",
+    " + ",
+];
+
+/// Spelling-insensitive form of a name: NFKC, lowercase, no whitespace.
+pub fn artist_key(name: &str) -> String {
+    name.nfkc()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Other names of artists, from `data/aliases/artists.tsv`.
+#[derive(Debug, Default)]
+pub struct ArtistAliases {
+    /// Key of every name and alias → the name.
+    names: HashMap<String, String>,
+}
+
+impl ArtistAliases {
+    /// Empty if the file doesn't exist.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {:?}", path))?;
+        Ok(Self::parse(&text))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut names = HashMap::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.split('\t').map(str::trim);
+            let Some(name) = fields.next() else {
+                continue;
+            };
+            names.insert(artist_key(name), name.to_string());
+            for alias in fields.filter(|alias| !alias.is_empty()) {
+                names.insert(artist_key(alias), name.to_string());
+            }
+        }
+        Self { names }
+    }
+
+    /// The name `name` is an alias of, if any.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        self.names.get(&artist_key(name)).map(String::as_str)
+    }
+}
+
+/// "A (CV: B)" → ["A", "B"]; other parentheses are part of the name.
+fn split_voice_credits(credit: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut rest = credit;
+    while let Some(open) = rest.find(['(', '（']) {
+        let inner_start = open + rest[open..].chars().next().map_or(1, char::len_utf8);
+        let Some(close) = rest[inner_start..].find([')', '）']) else {
+            break;
+        };
+        let inner = &rest[inner_start..inner_start + close];
+        let Some(voice) = inner
+            .trim()
+            .strip_prefix("CV")
+            .or_else(|| inner.trim().strip_prefix("cv"))
+        else {
+            break;
+        };
+        parts.push(rest[..open].to_string());
+        parts.push(voice.trim_start_matches([':', '：', '.', ' ']).to_string());
+        rest = &rest[inner_start + close..];
+        rest = &rest[rest.chars().next().map_or(0, char::len_utf8)..];
+    }
+    parts.push(rest.to_string());
+    parts
+}
+
+/// The individual artists of a credit, in order.
+pub fn split_artists(credit: &str, aliases: &ArtistAliases) -> Vec<String> {
+    if aliases.resolve(credit).is_some() {
+        return vec![credit.trim().to_string()];
+    }
+    let mut artists = vec![];
+    for part in split_voice_credits(credit) {
+        let mut rest = part.as_str();
+        loop {
+            // ASCII lowercasing keeps byte offsets the same.
+            let lower = rest.to_ascii_lowercase();
+            let next = SEPARATORS
+                .iter()
+                .filter_map(|separator| Some((lower.find(separator)?, separator.len())))
+                .min_by_key(|&(at, len)| (at, std::cmp::Reverse(len)));
+            let Some((at, len)) = next else {
+                artists.push(rest.to_string());
+                break;
+            };
+            artists.push(rest[..at].to_string());
+            rest = &rest[at + len..];
+        }
+    }
+    artists
+        .into_iter()
+        .map(|artist| artist.trim().to_string())
+        .filter(|artist| !artist.is_empty())
+        .collect()
+}
+
+/// An artist and the keys of their songs in each game, sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Artist {
+    pub name: String,
+    pub maimai: Vec<String>,
+    pub chuni: Vec<String>,
+    pub ongeki: Vec<String>,
+}
+
+impl Artist {
+    pub fn songs(&self, game: Game) -> &[String] {
+        match game {
+            Game::Maimai => &self.maimai,
+            Game::Chunithm => &self.chuni,
+            Game::Ongeki => &self.ongeki,
+        }
+    }
+
+    fn songs_mut(&mut self, game: Game) -> &mut Vec<String> {
+        match game {
+            Game::Maimai => &mut self.maimai,
+            Game::Chunithm => &mut self.chuni,
+            Game::Ongeki => &mut self.ongeki,
+        }
+    }
+}
+
+/// Every artist of the three games.
+#[derive(Debug, Default)]
+pub struct ArtistIndex {
+    /// By `artist_key` of the name.
+    artists: BTreeMap<String, Artist>,
+    aliases: ArtistAliases,
+}
+
+impl ArtistIndex {
+    /// Artists without an alias entry are named after their most common spelling.
+    pub fn build(charts: Charts, aliases: ArtistAliases) -> Self {
+        let credits = [
+            (
+                Game::Maimai,
+                charts
+                    .maimai
+                    .iter()
+                    .map(|(title, song)| (title, song.artist.as_str()))
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                Game::Chunithm,
+                charts
+                    .chuni
+                    .iter()
+                    .map(|(title, song)| (title, song.artist.as_str()))
+                    .collect(),
+            ),
+            (
+                Game::Ongeki,
+                charts
+                    .ongeki
+                    .iter()
+                    .map(|(title, song)| (title, song.artist.as_str()))
+                    .collect(),
+            ),
+        ];
+        let mut artists = BTreeMap::<String, Artist>::new();
+        let mut spellings = HashMap::<String, HashMap<String, usize>>::new();
+        for (game, songs) in credits {
+            for (title, credit) in songs {
+                for artist in split_artists(credit, &aliases) {
+                    let name = aliases.resolve(&artist).unwrap_or(&artist).to_string();
+                    let key = artist_key(&name);
+                    *spellings
+                        .entry(key.clone())
+                        .or_default()
+                        .entry(name)
+                        .or_default() += 1;
+                    let songs = artists.entry(key).or_default().songs_mut(game);
+                    if !songs.contains(title) {
+                        songs.push(title.clone());
+                    }
+                }
+            }
+        }
+        for (key, artist) in &mut artists {
+            artist.name = spellings[key]
+                .iter()
+                .max_by_key(|&(name, count)| (count, std::cmp::Reverse(name)))
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default();
+            for game in [Game::Maimai, Game::Chunithm, Game::Ongeki] {
+                artist.songs_mut(game).sort();
+            }
+        }
+        Self { artists, aliases }
+    }
+
+    pub fn artists(&self) -> impl Iterator<Item = &Artist> {
+        self.artists.values()
+    }
+
+    /// The artist named `name` or one of its aliases.
+    pub fn find(&self, name: &str) -> Option<&Artist> {
+        let name = self.aliases.resolve(name).unwrap_or(name);
+        self.artists.get(&artist_key(name))
+    }
+
+    /// The artist whose name is most like `name`, if any is close enough.
+    pub fn closest(&self, name: &str) -> Option<&Artist> {
+        let key = artist_key(name);
+        let candidates = self.artists.iter().chain(
+            self.aliases
+                .names
+                .iter()
+                .filter_map(|(alias, name)| Some((alias, self.find(name)?))),
+        );
+        candidates
+            .map(|(candidate, artist)| (strsim::jaro_winkler(candidate, &key), artist))
+            .filter(|&(similarity, _)| similarity >= MIN_SIMILARITY)
+            .max_by_key(|&(similarity, _)| OrderedFloat(similarity))
+            .map(|(_, artist)| artist)
+    }
+}
+
+/// "DX 5 / 7 / 10 / 12+ · ST 4 / 6 / 9 / 11" from the JP levels, or intl if the song isn't in JP.
+fn song_levels(song: &dyn SongInfo) -> String {
+    let entries = song.chart_entries();
+    let region = if entries.iter().any(|entry| entry.region == Region::Jp) {
+        Region::Jp
+    } else {
+        Region::Intl
+    };
+    let mut groups: Vec<(Option<&str>, Vec<&ChartEntry>)> = vec![];
+    for entry in entries.iter().filter(|entry| entry.region == region) {
+        match groups.iter_mut().find(|(t, _)| *t == entry.chart_type) {
+            Some((_, charts)) => charts.push(entry),
+            None => groups.push((entry.chart_type, vec![entry])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(chart_type, mut charts)| {
+            charts.sort_by_key(|entry| entry.difficulty);
+            let levels = charts
+                .iter()
+                .map(|entry| entry.level.as_str())
+                .collect::<Vec<_>>()
+                .join(" / ");
+            match chart_type {
+                Some(chart_type) => format!("{} {}", chart_type, levels),
+                None => levels,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// One page of `/artist`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtistPage {
+    pub document: Document,
+    /// Index of this page; clamped to the last page.
+    pub page: usize,
+    pub pages: usize,
+    /// Name the buttons point at.
+    name: String,
+}
+
+impl ArtistPage {
+    /// Previous and next buttons, if there is more than one page.
+    pub fn components<'a>(&self, c: &'a mut CreateComponents) -> &'a mut CreateComponents {
+        if self.pages < 2 {
+            return c;
+        }
+        let id = |page| ComponentId::new(Action::Artist(page), ALL_GAMES, &self.name).to_string();
+        let mut prev = CreateButton::default();
+        prev.custom_id(id(self.page.saturating_sub(1)))
+            .label("◀")
+            .style(ButtonStyle::Secondary)
+            .disabled(self.page == 0);
+        let mut next = CreateButton::default();
+        next.custom_id(id((self.page + 1).min(self.pages - 1)))
+            .label("▶")
+            .style(ButtonStyle::Secondary)
+            .disabled(self.page + 1 == self.pages);
+        let mut ar = CreateActionRow::default();
+        ar.add_button(prev);
+        ar.add_button(next);
+        c.set_action_row(ar)
+    }
+}
+
+/// Page `page` of the songs of `artist`, maimai first, each line a song and its levels.
+pub fn artist_page(
+    catalogs: [(Game, &dyn GameCatalog); 3],
+    artist: &Artist,
+    page: usize,
+) -> ArtistPage {
+    let mut chunks = vec![];
+    for (game, catalog) in catalogs {
+        let songs = artist.songs(game);
+        let lines = songs
+            .iter()
+            .filter_map(|title| {
+                let song = catalog.song(title)?;
+                Some(format!(
+                    "**{}** {}",
+                    catalog.display_title(title).replace('*', "\\*"),
+                    song_levels(song)
+                ))
+            })
+            .collect::<Vec<_>>();
+        let count = lines.len();
+        for (idx, chunk) in lines.chunks(PAGE_LEN).enumerate() {
+            let first = idx * PAGE_LEN + 1;
+            chunks.push((
+                format!(
+                    "**{}** ({}–{} of {})",
+                    catalog.name(),
+                    first,
+                    first + chunk.len() - 1,
+                    count
+                ),
+                chunk.join("\n"),
+                catalog,
+            ));
+        }
+    }
+    let pages = chunks.len().max(1);
+    let page = page.min(pages - 1);
+    let (description, color) = match chunks.get(page) {
+        Some((heading, lines, catalog)) => (
+            format!("{}\n{}\n\nPage {}/{}", heading, lines, page + 1, pages),
+            catalog.color(),
+        ),
+        None => ("No songs.".to_string(), (255, 255, 255)),
+    };
+    ArtistPage {
+        document: Document {
+            title: artist.name.clone(),
+            description,
+            fields: vec![],
+            thumbnail: None,
+            color,
+        },
+        page,
+        pages,
+        name: artist.name.clone(),
+    }
+}
+
+/// The catalogs `/artist` lists songs from, in order.
+pub fn artist_catalogs(data: &Data) -> [(Game, &dyn GameCatalog); 3] {
+    [Game::Maimai, Game::Chunithm, Game::Ongeki].map(|game| (game, data.catalog(game)))
+}
+
+/// List the songs of an artist in every game
+#[poise::command(slash_command, prefix_command, check = "cooldown_check")]
+pub async fn artist(
+    ctx: Context<'_>,
+    #[description = "Artist name e.g. \"t+pazolite\"; collaborations and feat. credits count"]
+    #[rest]
+    name: String,
+) -> Result<(), Error> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let data = ctx.data();
+    let (artist, content) = match data.artists.find(&name) {
+        Some(artist) => (artist, None),
+        None => match data.artists.closest(&name) {
+            Some(artist) => (
+                artist,
+                Some(format!(
+                    "I couldn't find **{}**; showing **{}**.",
+                    name, artist.name
+                )),
+            ),
+            None => {
+                ctx.send(|f| {
+                    f.ephemeral(true)
+                        .content(format!("I couldn't find an artist named **{}**.", name))
+                })
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+    let page = artist_page(artist_catalogs(data), artist, 0);
+
+    ctx.send(|f| {
+        if let Some(content) = content {
+            f.content(content);
+        }
+        f.ephemeral(settings.ephemeral)
+            .embed(|f| page.document.embed(f))
+            .components(|c| page.components(c))
+    })
+    .await?;
+    Ok(())
+}
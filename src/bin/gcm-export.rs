@@ -0,0 +1,91 @@
+//! Writes the merged chart database to files, for diffing releases and feeding other tools.
+//!
+//! `gcm-export --out export` writes `{game}.json`, `{game}-charts.csv` and `gcm.sqlite`
+//! for maimai, chuni, ongeki and every custom game.
+//! Run from the repository root so that `data/` is found.
+
+use std::{fs, path::PathBuf};
+
+use clap::{Parser, ValueEnum};
+use gcm_bot::{
+    catalog::*, chuni::*, config::data_path, custom::*, export::*, maimai::*, ongeki::*, utils::*,
+};
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+    Sqlite,
+}
+
+#[derive(Parser)]
+#[command(name = "gcm-export", about = "Export the GCM-bot chart database")]
+struct Cli {
+    /// Output directory
+    #[arg(long, default_value = "export")]
+    out: PathBuf,
+    /// Formats to write (default: all)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    format: Vec<Format>,
+    /// Games to export (default: all)
+    games: Vec<String>,
+}
+
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    let formats = if cli.format.is_empty() {
+        vec![Format::Json, Format::Csv, Format::Sqlite]
+    } else {
+        cli.format
+    };
+
+    let mut configs = load_custom_configs(&data_path(CUSTOM_GAME_DIR))?;
+    let games = if cli.games.is_empty() {
+        ["maimai", "chuni", "ongeki"]
+            .into_iter()
+            .map(String::from)
+            .chain(configs.iter().map(|c| c.id.clone()))
+            .collect()
+    } else {
+        cli.games
+    };
+
+    let mut catalogs: Vec<(String, Box<dyn GameCatalog>)> = vec![];
+    for game in games {
+        let catalog: Box<dyn GameCatalog> = match game.as_str() {
+            "maimai" => Box::new(Catalog::load(set_mai_charts()?, "maimai")?),
+            "chuni" => Box::new(Catalog::load(set_chuni_charts()?, "chuni")?),
+            "ongeki" => Box::new(Catalog::load(set_ongeki_charts()?, "ongeki")?),
+            id => {
+                let idx = configs
+                    .iter()
+                    .position(|c| c.id == id)
+                    .ok_or_else(|| format!("Unknown game {id}"))?;
+                Box::new(set_custom_catalog(configs.remove(idx))?)
+            }
+        };
+        catalogs.push((game, catalog));
+    }
+
+    fs::create_dir_all(&cli.out)?;
+    for (game, catalog) in &catalogs {
+        if formats.contains(&Format::Json) {
+            write_json(catalog.as_ref(), &cli.out.join(format!("{game}.json")))?;
+        }
+        if formats.contains(&Format::Csv) {
+            write_csv(
+                catalog.as_ref(),
+                &cli.out.join(format!("{game}-charts.csv")),
+            )?;
+        }
+    }
+    if formats.contains(&Format::Sqlite) {
+        let catalogs = catalogs
+            .iter()
+            .map(|(game, catalog)| (game.as_str(), catalog.as_ref()))
+            .collect::<Vec<_>>();
+        write_sqlite(&catalogs, &cli.out.join("gcm.sqlite"))?;
+    }
+    eprintln!("Wrote {:?}", cli.out);
+    Ok(())
+}
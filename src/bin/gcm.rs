@@ -0,0 +1,439 @@
+//! Command-line counterpart of the bot commands, for checking data changes without Discord.
+//!
+//! `gcm info maimai bbb`, `gcm search chuni --level 14+ --json`, `gcm alias resolve ongeki w4`,
+//! `gcm changes old-snapshot.json`, ...
+//! Run from the repository root so that `data/` is found.
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use gcm_bot::{
+    catalog::*, changes::*, chart_view::*, chuni::*, config::data_path, crossover::*, custom::*,
+    document::Document, images::*, jackets::*, maimai::*, ongeki::*, rating::*, utils::*,
+};
+use poise::serenity_prelude::GuildId;
+use rand::seq::SliceRandom;
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(name = "gcm", about = "Query the GCM-bot chart database")]
+struct Cli {
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    /// Print Discord markdown instead of plain text
+    #[arg(long, global = true, conflicts_with = "json")]
+    markdown: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Song info, like `/[game]-info`
+    Info(SongArgs),
+    /// One chart of a song in detail, like `/detailed-mai-info`
+    Detail(DetailArgs),
+    /// List songs matching the filters
+    Search(SearchArgs),
+    /// Info for a random song matching the filters
+    Random(SearchArgs),
+    /// Alias operations
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommand,
+    },
+    /// Jacket URL of a song
+    JacketUrl(SongArgs),
+    /// Save the merged chart data for comparing with `changes` later
+    Snapshot {
+        /// Output file
+        path: PathBuf,
+        /// Games to include (default: maimai, chuni and ongeki)
+        games: Vec<String>,
+    },
+    /// What changed between two snapshots, like `/changes`
+    Changes {
+        /// Older snapshot
+        old: PathBuf,
+        /// Newer snapshot (default: the current data)
+        new: Option<PathBuf>,
+    },
+    /// Draw a best-rating image from a score list, like `/rating` (maimai and chuni)
+    Rating {
+        game: String,
+        /// One `title<TAB>difficulty<TAB>score[<TAB>DX|ST]` line per chart
+        scores: PathBuf,
+        /// Output PNG
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long, value_enum, default_value = "jp")]
+        region: RegionArg,
+    },
+    /// Songs in several of maimai, CHUNITHM and O.N.G.E.K.I., like `/crossover`
+    Crossover {
+        /// Games the songs have to be in (default: all three)
+        #[arg(value_enum)]
+        games: Vec<GameArg>,
+        /// Only originals of this game
+        #[arg(long, value_enum)]
+        origin: Option<GameArg>,
+        /// Print a namu-wiki table
+        #[arg(long)]
+        wiki: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AliasCommand {
+    /// Show which song a query resolves to
+    Resolve {
+        #[command(flatten)]
+        song: SongArgs,
+        /// Server id, for community aliases added with `/add-alias`
+        #[arg(long, default_value_t = 0)]
+        server: u64,
+    },
+}
+
+#[derive(Args)]
+struct SongArgs {
+    /// maimai, chuni, ongeki or the id of a custom game
+    game: String,
+    /// Song title or alias
+    #[arg(required = true, num_args = 1..)]
+    query: Vec<String>,
+}
+
+#[derive(Args)]
+struct DetailArgs {
+    #[command(flatten)]
+    song: SongArgs,
+    /// DX or ST (maimai only; default: the song's first chart type)
+    #[arg(long)]
+    chart_type: Option<String>,
+    /// Difficulty name, e.g. EXPERT (default: MASTER, or the hardest chart)
+    #[arg(long)]
+    difficulty: Option<String>,
+}
+
+impl DetailArgs {
+    fn choice(&self, catalog: &dyn GameCatalog) -> Result<ChartChoice, Error> {
+        let chart_type = match self.chart_type.as_deref().map(str::to_uppercase).as_deref() {
+            None => None,
+            Some("DX") => Some("DX"),
+            Some("ST") => Some("ST"),
+            Some(other) => return Err(format!("Unknown chart type {other}").into()),
+        };
+        let difficulty = match &self.difficulty {
+            Some(name) => Some(difficulty_index(catalog, name)?),
+            None => None,
+        };
+        Ok(ChartChoice {
+            chart_type,
+            difficulty,
+        })
+    }
+}
+
+/// Position of the difficulty called `name` in the catalog's difficulty list.
+fn difficulty_index(catalog: &dyn GameCatalog, name: &str) -> Result<usize, Error> {
+    Ok(catalog
+        .difficulty_names()
+        .iter()
+        .position(|d| d.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("Unknown difficulty {name} for {}", catalog.name()))?)
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RegionArg {
+    Jp,
+    Intl,
+}
+
+impl From<RegionArg> for Region {
+    fn from(region: RegionArg) -> Self {
+        match region {
+            RegionArg::Jp => Region::Jp,
+            RegionArg::Intl => Region::Intl,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GameArg {
+    Maimai,
+    Chuni,
+    Ongeki,
+}
+
+impl From<GameArg> for Game {
+    fn from(game: GameArg) -> Self {
+        match game {
+            GameArg::Maimai => Game::Maimai,
+            GameArg::Chuni => Game::Chunithm,
+            GameArg::Ongeki => Game::Ongeki,
+        }
+    }
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// maimai, chuni, ongeki or the id of a custom game
+    game: String,
+    /// Exact level, e.g. 13+
+    #[arg(long)]
+    level: Option<String>,
+    #[arg(long)]
+    min_constant: Option<f32>,
+    #[arg(long)]
+    max_constant: Option<f32>,
+    /// Difficulty name, e.g. MASTER
+    #[arg(long)]
+    difficulty: Option<String>,
+    /// DX or ST (maimai only)
+    #[arg(long)]
+    chart_type: Option<String>,
+    #[arg(long, value_enum)]
+    region: Option<RegionArg>,
+    /// Part of the artist name
+    #[arg(long)]
+    artist: Option<String>,
+    /// Part of the version name
+    #[arg(long)]
+    version: Option<String>,
+    /// Only charts added in the current version
+    #[arg(long)]
+    new: bool,
+    #[arg(long)]
+    include_deleted: bool,
+}
+
+impl SearchArgs {
+    fn filter(&self, catalog: &dyn GameCatalog) -> Result<SearchFilter, Error> {
+        let difficulty = match &self.difficulty {
+            Some(name) => Some(difficulty_index(catalog, name)?),
+            None => None,
+        };
+        Ok(SearchFilter {
+            level: self.level.clone(),
+            min_constant: self.min_constant,
+            max_constant: self.max_constant,
+            difficulty,
+            chart_type: self.chart_type.clone(),
+            region: self.region.map(Region::from),
+            artist: self.artist.clone(),
+            version: self.version.clone(),
+            new_only: self.new,
+            include_deleted: self.include_deleted,
+        })
+    }
+}
+
+fn load(game: &str) -> Result<Box<dyn GameCatalog>, Error> {
+    Ok(match game {
+        "maimai" => Box::new(Catalog::load(set_mai_charts()?, "maimai")?),
+        "chuni" => Box::new(Catalog::load(set_chuni_charts()?, "chuni")?),
+        "ongeki" => Box::new(Catalog::load(set_ongeki_charts()?, "ongeki")?),
+        id => {
+            let config = load_custom_configs(&data_path(CUSTOM_GAME_DIR))?
+                .into_iter()
+                .find(|c| c.id == id)
+                .ok_or_else(|| format!("Unknown game {id}"))?;
+            Box::new(set_custom_catalog(config)?)
+        }
+    })
+}
+
+fn snapshot(games: &[String]) -> Result<Snapshot, Error> {
+    let loaded = games
+        .iter()
+        .map(|game| load(game))
+        .collect::<Result<Vec<_>, _>>()?;
+    let catalogs = games
+        .iter()
+        .zip(&loaded)
+        .map(|(game, loaded)| (game.as_str(), loaded.as_ref()))
+        .collect::<Vec<_>>();
+    Ok(Snapshot::capture(&catalogs))
+}
+
+/// Resolves like the bot does, failing with the closest match as a hint.
+fn resolve(catalog: &dyn GameCatalog, query: &str, server: GuildId) -> Result<String, Error> {
+    if let Some(title) = catalog.lookup(query, server) {
+        return Ok(title);
+    }
+    let closest = get_closest_title(query, catalog.aliases(), server);
+    Err(format!(
+        "No song found for \"{}\"; did you mean \"{}\" (for \"{}\")?",
+        query, closest.0, closest.1
+    )
+    .into())
+}
+
+fn print_document(cli: &Cli, document: &Document) -> Result<(), Error> {
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(document)?);
+    } else if cli.markdown {
+        print!("{}", document.to_markdown());
+    } else {
+        print!("{}", document.to_text());
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Command::Info(args) => {
+            let loaded = load(&args.game)?;
+            let catalog = loaded.as_ref();
+            let title = resolve(catalog, &args.query.join(" "), GuildId(0))?;
+            print_document(&cli, &catalog.document(&title)?)?;
+        }
+        Command::Detail(args) => {
+            let loaded = load(&args.song.game)?;
+            let catalog = loaded.as_ref();
+            let title = resolve(catalog, &args.song.query.join(" "), GuildId(0))?;
+            let choice = args.choice(catalog)?;
+            let view = chart_view(catalog, &title, choice, &RenderOptions::default())?;
+            print_document(&cli, &view.document)?;
+        }
+        Command::Search(args) => {
+            let loaded = load(&args.game)?;
+            let catalog = loaded.as_ref();
+            let titles = catalog.search(&args.filter(catalog)?);
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&titles)?);
+            } else {
+                for title in titles {
+                    println!("{}", catalog.display_title(&title));
+                }
+            }
+        }
+        Command::Random(args) => {
+            let loaded = load(&args.game)?;
+            let catalog = loaded.as_ref();
+            let titles = catalog.search(&args.filter(catalog)?);
+            let title = titles
+                .choose(&mut rand::thread_rng())
+                .ok_or("No song matches the filters")?;
+            print_document(&cli, &catalog.document(title)?)?;
+        }
+        Command::Alias {
+            command: AliasCommand::Resolve { song, server },
+        } => {
+            let loaded = load(&song.game)?;
+            let catalog = loaded.as_ref();
+            let query = song.query.join(" ");
+            let server = GuildId(*server);
+            let title = catalog.lookup(&query, server);
+            let closest = get_closest_title(&query, catalog.aliases(), server);
+            if cli.json {
+                let value = json!({
+                    "query": query,
+                    "title": title,
+                    "closest": { "alias": closest.0, "title": closest.1 },
+                });
+                println!("{}", serde_json::to_string_pretty(&value)?);
+            } else if let Some(title) = title {
+                println!("{}", title);
+            } else {
+                println!(
+                    "No match; closest is \"{}\" (for \"{}\")",
+                    closest.0, closest.1
+                );
+            }
+        }
+        Command::JacketUrl(args) => {
+            let loaded = load(&args.game)?;
+            let catalog = loaded.as_ref();
+            let title = resolve(catalog, &args.query.join(" "), GuildId(0))?;
+            let url = catalog.jacket_url(&title);
+            if cli.json {
+                println!("{}", json!({ "title": title, "url": url }));
+            } else {
+                println!("{}", url.ok_or("Song has no jacket")?);
+            }
+        }
+        Command::Snapshot { path, games } => {
+            let games = if games.is_empty() {
+                vec![
+                    "maimai".to_string(),
+                    "chuni".to_string(),
+                    "ongeki".to_string(),
+                ]
+            } else {
+                games.clone()
+            };
+            snapshot(&games)?.save(path)?;
+        }
+        Command::Changes { old, new } => {
+            let old = Snapshot::load(old)?;
+            let new = match new {
+                Some(path) => Snapshot::load(path)?,
+                None => snapshot(&old.games.keys().cloned().collect::<Vec<_>>())?,
+            };
+            let changes = diff(&old, &new);
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                print!("{}", changes_markdown(&changes));
+            }
+        }
+        Command::Crossover {
+            games,
+            origin,
+            wiki,
+        } => {
+            let (maimai, chuni, ongeki) =
+                (set_mai_charts()?, set_chuni_charts()?, set_ongeki_charts()?);
+            let charts = Charts {
+                maimai: &maimai,
+                chuni: &chuni,
+                ongeki: &ongeki,
+            };
+            let data = CrossoverData::load(&data_path(CROSSOVER_FILE))?;
+            let mut filter = CrossoverFilter {
+                origin: origin.map(Game::from),
+                ..Default::default()
+            };
+            if !games.is_empty() {
+                filter.games = games.iter().map(|&game| game.into()).collect();
+            }
+            let titles = crossover_songs(charts, &data, &filter);
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&titles)?);
+            } else if *wiki {
+                print!("{}", wiki_table(&titles, &data));
+            } else {
+                for title in titles {
+                    println!("{}", title);
+                }
+            }
+        }
+        Command::Rating {
+            game,
+            scores,
+            out,
+            region,
+        } => {
+            let loaded = load(game)?;
+            let scores = std::fs::read_to_string(scores)?;
+            let sheet = RatingSheet::build(loaded.as_ref(), (*region).into(), &scores, GuildId(0))?;
+            for line in &sheet.skipped {
+                eprintln!("Skipped {}", line);
+            }
+            let fonts = Fonts::load(&data_path(FONT_DIR))?;
+            let jackets = JacketCache::new(data_path(JACKET_DIR));
+            let png = sheet.render(&fonts, |rated| {
+                let (jacket, url) = rated.jacket.as_ref().zip(rated.jacket_url.as_ref())?;
+                jackets.get(game, jacket, url).ok()
+            })?;
+            std::fs::write(out, png)?;
+            println!("{}", sheet.rules.format_rating(sheet.total()));
+        }
+    }
+    Ok(())
+}
@@ -1,47 +1,41 @@
-use std::collections::BTreeSet;
+//! Every artist with an original in O.N.G.E.K.I., CHUNITHM or maimai, and which of the three
+//! they have originals in.
 
 use gcm_bot::{
+    artists::*,
     chuni::set_chuni_charts,
+    config::data_path,
+    crossover::Charts,
     maimai::set_mai_charts,
     ongeki::set_ongeki_charts,
-    utils::{ChuniCategory, MaiCategory, OngekiCategory},
+    utils::{Error, Game},
 };
-fn main() {
-    let g = set_ongeki_charts().unwrap();
-    let c = set_chuni_charts().unwrap();
-    let m = set_mai_charts().unwrap();
 
-    let gl: BTreeSet<_> = g
-        .iter()
-        .filter(|(_, info)| info.category == OngekiCategory::Ongeki)
-        .map(|(_, info)| &info.artist)
-        .collect();
-    let cl: BTreeSet<_> = c
-        .iter()
-        .filter(|(_, info)| {
-            info.category == ChuniCategory::Original || info.category == ChuniCategory::Irodori
-        })
-        .map(|(_, info)| &info.artist)
-        .collect();
-    let ml: BTreeSet<_> = m
-        .iter()
-        .filter(|(_, info)| info.category == MaiCategory::Maimai)
-        .map(|(_, info)| &info.artist)
-        .collect();
+fn main() -> Result<(), Error> {
+    let g = set_ongeki_charts()?;
+    let c = set_chuni_charts()?;
+    let m = set_mai_charts()?;
+    let charts = Charts {
+        maimai: &m,
+        chuni: &c,
+        ongeki: &g,
+    };
+    let aliases = ArtistAliases::load(&data_path(ARTIST_ALIASES_FILE))?;
+    let index = ArtistIndex::build(charts, aliases);
 
-    let mut l = gl.clone();
-    l.append(&mut cl.clone());
-    l.append(&mut ml.clone());
-
-    let v = l.iter().map(|artist| {
-        (
-            artist,
-            (gl.contains(artist) as usize),
-            (cl.contains(artist) as usize),
-            (ml.contains(artist) as usize),
-        )
-    });
-    for i in v {
-        println!("{}\t{}\t{}\t{}", i.0, i.1, i.2, i.3);
+    for artist in index.artists() {
+        let originals = [Game::Ongeki, Game::Chunithm, Game::Maimai].map(|game| {
+            artist
+                .songs(game)
+                .iter()
+                .any(|title| charts.is_original(game, title)) as usize
+        });
+        if originals.iter().any(|&original| original > 0) {
+            println!(
+                "{}\t{}\t{}\t{}",
+                artist.name, originals[0], originals[1], originals[2]
+            );
+        }
     }
+    Ok(())
 }
@@ -6,7 +6,7 @@ fn main() {
         .iter()
         .map(|i| (i.0, i.1.order.unwrap_or(999)))
         .collect::<Vec<_>>();
-    m.sort_by(|a, b| a.1.cmp(&b.1));
+    m.sort_by_key(|a| a.1);
     for i in m {
         println!("{}\t{}", i.0, i.1);
     }
@@ -0,0 +1,77 @@
+//! Downloads every jacket into the jacket cache, so the bot never waits on the jacket host.
+//!
+//! `prefetch-jackets` covers maimai, chuni and ongeki; list game ids to pick others.
+//! Already cached jackets are skipped. Run from the repository root so that `data/` is found.
+
+use clap::Parser;
+use gcm_bot::{
+    catalog::*, chuni::*, config::data_path, custom::*, jackets::*, maimai::*, ongeki::*, utils::*,
+};
+
+#[derive(Parser)]
+#[command(name = "prefetch-jackets", about = "Fill the GCM-bot jacket cache")]
+struct Cli {
+    /// Games to fetch jackets for (default: maimai, chuni and ongeki)
+    games: Vec<String>,
+}
+
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    let games = if cli.games.is_empty() {
+        vec![
+            "maimai".to_string(),
+            "chuni".to_string(),
+            "ongeki".to_string(),
+        ]
+    } else {
+        cli.games
+    };
+    let jackets = JacketCache::new(data_path(JACKET_DIR));
+
+    let mut failed = 0;
+    for game in games {
+        let catalog: Box<dyn GameCatalog> = match game.as_str() {
+            "maimai" => Box::new(Catalog::load(set_mai_charts()?, "maimai")?),
+            "chuni" => Box::new(Catalog::load(set_chuni_charts()?, "chuni")?),
+            "ongeki" => Box::new(Catalog::load(set_ongeki_charts()?, "ongeki")?),
+            id => {
+                let config = load_custom_configs(&data_path(CUSTOM_GAME_DIR))?
+                    .into_iter()
+                    .find(|c| c.id == id)
+                    .ok_or_else(|| format!("Unknown game {id}"))?;
+                Box::new(set_custom_catalog(config)?)
+            }
+        };
+
+        let mut titles = catalog.songs().map(|(t, _)| t.clone()).collect::<Vec<_>>();
+        titles.sort();
+        let (mut cached, mut downloaded) = (0, 0);
+        for title in titles {
+            let Some(jacket) = catalog.song(&title).and_then(|s| s.jp_jacket()) else {
+                continue;
+            };
+            if jackets.is_cached(&game, jacket) {
+                cached += 1;
+                continue;
+            }
+            match jackets.jacket(catalog.as_ref(), &title) {
+                Some(Ok(_)) => downloaded += 1,
+                Some(Err(e)) => {
+                    eprintln!("{}: {:?}", title, e);
+                    failed += 1;
+                }
+                None => {}
+            }
+        }
+        eprintln!(
+            "{}: {} downloaded, {} already cached",
+            catalog.name(),
+            downloaded,
+            cached
+        );
+    }
+    if failed > 0 {
+        return Err(format!("{} jackets could not be fetched", failed).into());
+    }
+    Ok(())
+}
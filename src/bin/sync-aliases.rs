@@ -5,7 +5,7 @@ use std::{
     io::{BufRead, BufReader},
 };
 
-use gcm_bot::ongeki::set_ongeki_charts;
+use gcm_bot::{config::data_path, ongeki::set_ongeki_charts};
 
 fn main() {
     let m = set_ongeki_charts().unwrap();
@@ -14,7 +14,7 @@ fn main() {
     let mut chuni = HashMap::new();
     let mut mai = HashMap::new();
 
-    let file = File::open("data/aliases/en/chuni.tsv").unwrap();
+    let file = File::open(data_path("aliases/en/chuni.tsv")).unwrap();
     let reader = BufReader::new(file);
     for line in reader.lines() {
         let split = line.unwrap();
@@ -25,7 +25,7 @@ fn main() {
         let nickname_slice = split[1..].iter().map(|c| c.to_string()).collect::<Vec<_>>();
         chuni.insert(title.to_string(), nickname_slice);
     }
-    let file = File::open("data/aliases/en/maimai.tsv").unwrap();
+    let file = File::open(data_path("aliases/en/maimai.tsv")).unwrap();
     let reader = BufReader::new(file);
     for line in reader.lines() {
         let split = line.unwrap();
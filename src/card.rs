@@ -0,0 +1,232 @@
+//! Chart cards: one PNG per song with the jacket, title, artist and a coloured box per chart,
+//! one row per region and chart type.
+
+use image::{imageops, RgbaImage};
+
+use crate::{
+    catalog::*,
+    images::{difficulty_color, encode_png, fill_rect, text_color, Color, Fonts},
+    utils::Game,
+};
+
+const WIDTH: u32 = 900;
+const PADDING: u32 = 24;
+const BAR: u32 = 8;
+const JACKET: u32 = 200;
+const ROW_HEIGHT: u32 = 72;
+const ROW_GAP: u32 = 8;
+/// Width of the region badge and chart type label in front of each row.
+const LABEL_WIDTH: u32 = 120;
+const BOX_GAP: u32 = 8;
+/// Boxes per line; charts of games with more difficulties wrap onto further lines.
+const MAX_COLUMNS: u32 = 6;
+
+const BACKGROUND: Color = (250, 250, 250);
+const TEXT: Color = (20, 20, 20);
+const SUBTLE: Color = (110, 110, 110);
+
+/// Charts of one (region, chart type) pair.
+type Row<'a> = ((Region, Option<&'static str>), Vec<&'a ChartEntry>);
+
+/// Everything a card shows, copied out of the catalog so it can be drawn off the async runtime.
+#[derive(Debug, Clone)]
+pub struct ChartCard {
+    pub title: String,
+    pub artist: String,
+    pub version: Option<String>,
+    pub deleted: bool,
+    pub color: Color,
+    pub difficulty_names: Vec<String>,
+    /// Whether to label rows with their region; off for custom games, which have none.
+    pub regional: bool,
+    pub charts: Vec<ChartEntry>,
+}
+
+impl ChartCard {
+    pub fn from_catalog(catalog: &dyn GameCatalog, title: &str) -> Option<Self> {
+        let song = catalog.song(title)?;
+        let builtin = [Game::Maimai, Game::Chunithm, Game::Ongeki]
+            .iter()
+            .any(|game| game.id() == catalog.id());
+        Some(Self {
+            title: catalog.display_title(title),
+            artist: song.artist().to_string(),
+            version: song.version(),
+            deleted: song.deleted(),
+            color: catalog.color(),
+            difficulty_names: catalog
+                .difficulty_names()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            regional: builtin,
+            charts: song.chart_entries(),
+        })
+    }
+
+    /// Leaves out the other region's charts and the constants if `options` ask for that.
+    pub fn apply(&mut self, options: &RenderOptions) {
+        if let (Some(region), true) = (options.region, self.regional) {
+            self.charts.retain(|chart| chart.region == region);
+        }
+        if !options.constants {
+            self.charts
+                .iter_mut()
+                .for_each(|chart| chart.constant = None);
+        }
+    }
+
+    /// Charts grouped into rows by (region, chart type), JP first.
+    fn rows(&self) -> Vec<Row<'_>> {
+        let mut rows: Vec<Row> = vec![];
+        for chart in &self.charts {
+            let key = (chart.region, chart.chart_type);
+            match rows.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, charts)) => charts.push(chart),
+                None => rows.push((key, vec![chart])),
+            }
+        }
+        rows.sort_by_key(|((region, _), _)| *region == Region::Intl);
+        rows
+    }
+
+    /// Boxes per line, at least one.
+    fn columns(&self) -> u32 {
+        (self.difficulty_names.len() as u32).clamp(1, MAX_COLUMNS)
+    }
+
+    /// Lines of boxes the charts of a row take up.
+    fn lines(&self, charts: &[&ChartEntry]) -> u32 {
+        let last = charts
+            .iter()
+            .map(|chart| chart.difficulty)
+            .max()
+            .unwrap_or(0);
+        last as u32 / self.columns() + 1
+    }
+
+    /// The card as PNG bytes. `jacket` is the jacket image in any format `image` reads;
+    /// a placeholder is drawn if it is missing or unreadable.
+    pub fn render(&self, fonts: &Fonts, jacket: Option<&[u8]>) -> eyre::Result<Vec<u8>> {
+        let rows = self.rows();
+        let rows_top = BAR + PADDING * 2 + JACKET;
+        let lines = rows
+            .iter()
+            .map(|(_, charts)| self.lines(charts))
+            .sum::<u32>();
+        let height = rows_top + lines * (ROW_HEIGHT + ROW_GAP) + PADDING - ROW_GAP;
+        let mut image = RgbaImage::new(WIDTH, height);
+        fill_rect(&mut image, (0, 0), (WIDTH, height), BACKGROUND);
+        fill_rect(&mut image, (0, 0), (WIDTH, BAR), self.color);
+
+        let top = BAR + PADDING;
+        match jacket.and_then(|bytes| image::load_from_memory(bytes).ok()) {
+            Some(jacket) => {
+                let jacket = jacket
+                    .resize_to_fill(JACKET, JACKET, imageops::FilterType::Triangle)
+                    .to_rgba8();
+                imageops::overlay(&mut image, &jacket, PADDING as i64, top as i64);
+            }
+            None => {
+                fill_rect(
+                    &mut image,
+                    (PADDING, top),
+                    (JACKET, JACKET),
+                    (200, 200, 200),
+                );
+                fonts.draw_centered(
+                    &mut image,
+                    "No jacket",
+                    20.,
+                    (
+                        (PADDING + JACKET / 2) as f32,
+                        (top + JACKET / 2 - 12) as f32,
+                    ),
+                    SUBTLE,
+                );
+            }
+        }
+
+        let x = (PADDING * 2 + JACKET) as f32;
+        let width = (WIDTH - PADDING) as f32 - x;
+        let mut y = top as f32;
+        let title = fonts.fit(&self.title, 34., width);
+        fonts.draw_text(&mut image, &title, 34., (x, y), TEXT);
+        y += 48.;
+        let artist = fonts.fit(&self.artist, 22., width);
+        fonts.draw_text(&mut image, &artist, 22., (x, y), SUBTLE);
+        y += 36.;
+        if let Some(version) = &self.version {
+            let version = fonts.fit(version, 18., width);
+            fonts.draw_text(&mut image, &version, 18., (x, y), SUBTLE);
+            y += 30.;
+        }
+        if self.deleted {
+            fonts.draw_text(&mut image, "DELETED", 18., (x, y), (220, 40, 40));
+        }
+
+        let columns = self.columns();
+        let box_width = (WIDTH - PADDING * 2 - LABEL_WIDTH - BOX_GAP * (columns - 1)) / columns;
+        let mut y = rows_top;
+        for ((region, chart_type), charts) in &rows {
+            if self.regional {
+                let (label, color) = match region {
+                    Region::Jp => ("JP", (200, 40, 60)),
+                    Region::Intl => ("INTL", (40, 90, 200)),
+                };
+                fill_rect(&mut image, (PADDING, y + 8), (64, 26), color);
+                fonts.draw_centered(
+                    &mut image,
+                    label,
+                    16.,
+                    ((PADDING + 32) as f32, (y + 11) as f32),
+                    (255, 255, 255),
+                );
+            }
+            if let Some(chart_type) = chart_type {
+                fonts.draw_text(
+                    &mut image,
+                    chart_type,
+                    20.,
+                    (PADDING as f32, (y + 40) as f32),
+                    TEXT,
+                );
+            }
+            for chart in charts {
+                let difficulty = chart.difficulty as u32;
+                let x = PADDING + LABEL_WIDTH + difficulty % columns * (box_width + BOX_GAP);
+                let y = y + difficulty / columns * (ROW_HEIGHT + ROW_GAP);
+                self.draw_chart(&mut image, fonts, chart, (x, y), box_width);
+            }
+            y += self.lines(charts) * (ROW_HEIGHT + ROW_GAP);
+        }
+
+        encode_png(&image)
+    }
+
+    fn draw_chart(
+        &self,
+        image: &mut RgbaImage,
+        fonts: &Fonts,
+        chart: &ChartEntry,
+        (x, y): (u32, u32),
+        width: u32,
+    ) {
+        let name = self
+            .difficulty_names
+            .get(chart.difficulty)
+            .map(String::as_str)
+            .unwrap_or_default();
+        let background = difficulty_color(name).unwrap_or(self.color);
+        let color = text_color(background);
+        fill_rect(image, (x, y), (width, ROW_HEIGHT), background);
+        let center = x as f32 + width as f32 / 2.;
+        let name = fonts.fit(name, 13., width as f32 - 8.);
+        fonts.draw_centered(image, &name, 13., (center, (y + 4) as f32), color);
+        fonts.draw_centered(image, &chart.level, 26., (center, (y + 20) as f32), color);
+        if let Some(constant) = chart.constant {
+            let constant = format!("{:.1}", constant);
+            fonts.draw_centered(image, &constant, 14., (center, (y + 52) as f32), color);
+        }
+    }
+}
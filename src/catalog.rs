@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use ordered_float::OrderedFloat;
+use poise::serenity_prelude::GuildId;
+use serde::Serialize;
+
+use crate::{
+    config::config,
+    document::{Document, Field, DESCRIPTION_LIMIT, FIELD_LIMIT},
+    utils::*,
+    versions::Version,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    Jp,
+    Intl,
+}
+
+/// A single chart of a song, flattened out of the per-game difficulty structs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartEntry {
+    pub region: Region,
+    /// "DX" or "ST" for maimai, `None` for games without chart types.
+    pub chart_type: Option<&'static str>,
+    /// BASIC = 0, ADVANCED = 1, ... (same as `diff_to_idx`)
+    pub difficulty: usize,
+    pub level: String,
+    pub constant: Option<OrderedFloat<f32>>,
+    /// The version the chart was added in, if the game has a version list.
+    pub version: Option<&'static Version>,
+}
+
+/// What some games know about a chart besides its level.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChartDetails {
+    pub designer: Option<String>,
+    /// (kind, count) pairs like ("Tap", 512); empty if the counts aren't known.
+    pub notes: Vec<(&'static str, usize)>,
+}
+
+/// How info embeds are rendered, from guild settings and user preferences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Only show levels of this region; `None` for both.
+    pub region: Option<Region>,
+    /// Show chart constants next to levels.
+    pub constants: bool,
+    /// Link difficulties to YouTube searches.
+    pub links: bool,
+    /// Only show levels, without artist, version, BPM and the like.
+    pub compact: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            region: None,
+            constants: true,
+            links: true,
+            compact: false,
+        }
+    }
+}
+
+/// Level lines of one chart group: a single line if both regions agree, one per region
+/// otherwise, or only the line of `options.region`.
+pub fn region_lines(jp: &str, intl: &str, options: &RenderOptions) -> String {
+    match options.region {
+        Some(Region::Jp) => format!(":flag_jp: {}", jp),
+        Some(Region::Intl) => format!(":globe_with_meridians: {}", intl),
+        None if jp == intl => format!(":flag_jp::globe_with_meridians: {}", jp),
+        None => format!(":flag_jp: {}\n:globe_with_meridians: {}", jp, intl),
+    }
+}
+
+/// Fields every game's song info has in some form.
+pub trait SongInfo {
+    fn title(&self) -> &str;
+    fn artist(&self) -> &str;
+    fn deleted(&self) -> bool;
+    fn jp_jacket(&self) -> Option<&str>;
+    fn version(&self) -> Option<String>;
+    fn chart_entries(&self) -> Vec<ChartEntry>;
+
+    /// Designer and note counts of one chart, for games whose data has them.
+    fn chart_details(&self, _chart_type: Option<&str>, _difficulty: usize) -> Option<ChartDetails> {
+        None
+    }
+}
+
+/// Game-specific parts of a song table.
+/// Implementing this for a game's info type makes `Catalog<T>` a `GameCatalog`.
+pub trait GameSong: SongInfo + Serialize + Send + Sync + Sized + 'static {
+    const NAME: &'static str;
+    const COLOR: (u8, u8, u8);
+    const DIFFICULTY_NAMES: &'static [&'static str];
+
+    /// Turns internal titles used to dodge duplicates (e.g. "Link (maimai)") back into display titles.
+    fn display_title(title: &str) -> String {
+        title.to_string()
+    }
+
+    /// Embed description for the song stored under `title`, and its levels as fields.
+    fn render(&self, title: &str, options: &RenderOptions) -> eyre::Result<(String, Vec<Field>)>;
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SearchFilter {
+    /// Exact level string, e.g. "13+".
+    pub level: Option<String>,
+    pub min_constant: Option<f32>,
+    pub max_constant: Option<f32>,
+    pub difficulty: Option<usize>,
+    pub chart_type: Option<String>,
+    pub region: Option<Region>,
+    /// Case-insensitive substring of the artist.
+    pub artist: Option<String>,
+    /// Case-insensitive substring of the version.
+    pub version: Option<String>,
+    /// Only charts that are new songs for rating in their region:
+    /// added in the current version, or an earlier act of it.
+    pub new_only: bool,
+    pub include_deleted: bool,
+}
+
+impl SearchFilter {
+    fn chart_filter_is_empty(&self) -> bool {
+        self.level.is_none()
+            && self.min_constant.is_none()
+            && self.max_constant.is_none()
+            && self.difficulty.is_none()
+            && self.chart_type.is_none()
+            && self.region.is_none()
+            && !self.new_only
+    }
+
+    pub fn matches_chart(&self, chart: &ChartEntry) -> bool {
+        if let Some(level) = &self.level {
+            if &chart.level != level {
+                return false;
+            }
+        }
+        if self.min_constant.is_some() || self.max_constant.is_some() {
+            let Some(cc) = chart.constant else {
+                return false;
+            };
+            if self.min_constant.is_some_and(|min| cc.0 < min)
+                || self.max_constant.is_some_and(|max| cc.0 > max)
+            {
+                return false;
+            }
+        }
+        if self.difficulty.is_some_and(|d| d != chart.difficulty) {
+            return false;
+        }
+        if let Some(chart_type) = &self.chart_type {
+            if !chart
+                .chart_type
+                .is_some_and(|t| t.eq_ignore_ascii_case(chart_type))
+            {
+                return false;
+            }
+        }
+        if self.region.is_some_and(|r| r != chart.region) {
+            return false;
+        }
+        if self.new_only && !chart.version.is_some_and(|v| v.is_new(chart.region)) {
+            return false;
+        }
+        true
+    }
+
+    pub fn matches(&self, song: &dyn SongInfo) -> bool {
+        if song.deleted() && !self.include_deleted {
+            return false;
+        }
+        if let Some(artist) = &self.artist {
+            if !song
+                .artist()
+                .to_lowercase()
+                .contains(&artist.to_lowercase())
+            {
+                return false;
+            }
+        }
+        if let Some(version) = &self.version {
+            let Some(song_version) = song.version() else {
+                return false;
+            };
+            if !song_version
+                .to_lowercase()
+                .contains(&version.to_lowercase())
+            {
+                return false;
+            }
+        }
+        self.chart_filter_is_empty()
+            || song
+                .chart_entries()
+                .iter()
+                .any(|chart| self.matches_chart(chart))
+    }
+}
+
+/// Everything needed to look up and present songs of one game.
+pub trait GameCatalog: Send + Sync {
+    /// "maimai", "chuni", "ongeki" or a custom game's id.
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    fn color(&self) -> (u8, u8, u8);
+    fn difficulty_names(&self) -> Vec<&str>;
+    fn aliases(&self) -> &Aliases;
+    fn jacket_prefix(&self) -> &str;
+    fn song(&self, title: &str) -> Option<&dyn SongInfo>;
+    /// (key, song) pairs; the key is what `get_title` resolves to.
+    fn songs(&self) -> Box<dyn Iterator<Item = (&String, &dyn SongInfo)> + '_>;
+    fn display_title(&self, title: &str) -> String;
+    /// The full song info as JSON.
+    fn song_json(&self, title: &str) -> Option<serde_json::Value>;
+    /// (description, level fields, jacket)
+    fn render_fields(
+        &self,
+        title: &str,
+        options: &RenderOptions,
+    ) -> eyre::Result<(String, Vec<Field>, Option<String>)>;
+
+    /// (description with the level fields appended, jacket)
+    fn render(
+        &self,
+        title: &str,
+        options: &RenderOptions,
+    ) -> eyre::Result<(String, Option<String>)> {
+        let (mut description, fields, jacket) = self.render_fields(title, options)?;
+        for field in fields {
+            description = format!("{}\n\n**{}**\n{}", description, field.name, field.value);
+        }
+        Ok((description.trim_start().to_string(), jacket))
+    }
+
+    /// The info embed as a renderer-neutral document.
+    fn document(&self, title: &str) -> eyre::Result<Document> {
+        self.document_with(title, &RenderOptions::default())
+    }
+
+    fn document_with(&self, title: &str, options: &RenderOptions) -> eyre::Result<Document> {
+        let (description, fields, _) = self.render_fields(title, options)?;
+        Ok(Document {
+            title: self.display_title(title),
+            description,
+            fields,
+            thumbnail: self.jacket_url(title),
+            color: self.color(),
+        })
+    }
+
+    fn lookup(&self, query: &str, server_id: GuildId) -> Option<String> {
+        get_title(query, self.aliases(), server_id)
+    }
+
+    fn jacket_url(&self, title: &str) -> Option<String> {
+        let jacket = self.song(title)?.jp_jacket()?;
+        Some(format!("{}{}", self.jacket_prefix(), jacket))
+    }
+
+    /// Titles of songs matching `filter`, sorted.
+    fn search(&self, filter: &SearchFilter) -> Vec<String> {
+        let mut titles = self
+            .songs()
+            .filter(|(_, song)| filter.matches(*song))
+            .map(|(title, _)| title.clone())
+            .collect::<Vec<_>>();
+        titles.sort();
+        titles
+    }
+}
+
+pub struct Catalog<T> {
+    pub game: String,
+    pub charts: HashMap<String, T>,
+    pub aliases: Aliases,
+    pub jacket_prefix: String,
+}
+
+impl<T> Catalog<T> {
+    /// Builds aliases for `charts` and gets the jacket prefix from the config.
+    /// `game` is the data directory name: "maimai", "chuni" or "ongeki".
+    pub fn load(charts: HashMap<String, T>, game: &str) -> Result<Self, Error> {
+        let aliases = set_aliases(charts.keys(), game)?;
+        let jacket_prefix = config()
+            .jacket_prefix(game)
+            .ok_or_else(|| format!("No jacket prefix setting for {}", game))??;
+        Ok(Catalog {
+            game: game.to_string(),
+            charts,
+            aliases,
+            jacket_prefix,
+        })
+    }
+}
+
+impl<T: GameSong> GameCatalog for Catalog<T> {
+    fn id(&self) -> &str {
+        &self.game
+    }
+
+    fn name(&self) -> &str {
+        T::NAME
+    }
+
+    fn color(&self) -> (u8, u8, u8) {
+        T::COLOR
+    }
+
+    fn difficulty_names(&self) -> Vec<&str> {
+        T::DIFFICULTY_NAMES.to_vec()
+    }
+
+    fn aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    fn jacket_prefix(&self) -> &str {
+        &self.jacket_prefix
+    }
+
+    fn song(&self, title: &str) -> Option<&dyn SongInfo> {
+        self.charts.get(title).map(|song| song as &dyn SongInfo)
+    }
+
+    fn songs(&self) -> Box<dyn Iterator<Item = (&String, &dyn SongInfo)> + '_> {
+        Box::new(
+            self.charts
+                .iter()
+                .map(|(title, song)| (title, song as &dyn SongInfo)),
+        )
+    }
+
+    fn display_title(&self, title: &str) -> String {
+        T::display_title(title)
+    }
+
+    fn song_json(&self, title: &str) -> Option<serde_json::Value> {
+        to_json_value(self.charts.get(title)?)
+    }
+
+    /// Falls back to unlinked levels when the links would push the embed past Discord's limits.
+    fn render_fields(
+        &self,
+        title: &str,
+        options: &RenderOptions,
+    ) -> eyre::Result<(String, Vec<Field>, Option<String>)> {
+        let Some(song) = self.charts.get(title) else {
+            eyre::bail!("No data for {title}");
+        };
+        let (mut description, mut fields) = song.render(title, options)?;
+        let too_long = description.len() > DESCRIPTION_LIMIT
+            || fields.iter().any(|field| field.value.len() > FIELD_LIMIT);
+        if too_long && options.links {
+            let options = RenderOptions {
+                links: false,
+                ..*options
+            };
+            (description, fields) = song.render(title, &options)?;
+        }
+        Ok((description, fields, song.jp_jacket().map(|s| s.to_string())))
+    }
+}
+
+/// Like `serde_json::to_value`, but keeps `f32` constants short (12.3 rather than 12.300000190734863).
+pub fn to_json_value<T: Serialize>(value: &T) -> Option<serde_json::Value> {
+    serde_json::from_str(&serde_json::to_string(value).ok()?).ok()
+}
+
+/// Flattens a five-difficulty `Difficulty` into chart entries, skipping missing levels.
+pub fn difficulty_entries(
+    lv: &Difficulty,
+    region: Region,
+    chart_type: Option<&'static str>,
+    version: Option<&'static Version>,
+) -> Vec<ChartEntry> {
+    (0..5)
+        .filter(|&idx| idx < 4 || lv.extra.is_some())
+        .map(|idx| ChartEntry {
+            region,
+            chart_type,
+            difficulty: idx,
+            level: lv.lv(idx),
+            constant: lv.get_constant(idx),
+            version,
+        })
+        .filter(|chart| !chart.level.is_empty())
+        .collect()
+}
@@ -0,0 +1,366 @@
+//! "What changed this update": snapshots of the merged chart data and diffs between them.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    catalog::*, cooldowns::cooldown_check, export::chart_rows, settings::query_settings, utils::*,
+};
+
+/// Where the bot keeps `latest.json` (data at the last startup that changed anything)
+/// and `previous.json` (the data before that), relative to `data_dir`.
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartSnapshot {
+    /// "DX"/"ST" for maimai, empty otherwise.
+    pub chart_type: String,
+    pub difficulty: String,
+    pub region: String,
+    pub level: String,
+    pub constant: Option<f32>,
+}
+
+impl ChartSnapshot {
+    fn same_chart(&self, other: &ChartSnapshot) -> bool {
+        self.chart_type == other.chart_type
+            && self.difficulty == other.difficulty
+            && self.region == other.region
+    }
+
+    /// e.g. "DX MASTER (JP)"
+    fn name(&self) -> String {
+        if self.chart_type.is_empty() {
+            format!("{} ({})", self.difficulty, self.region)
+        } else {
+            format!("{} {} ({})", self.chart_type, self.difficulty, self.region)
+        }
+    }
+
+    /// e.g. "13+ (13.7)"
+    fn level_string(&self) -> String {
+        match self.constant {
+            Some(cc) => format!("{} ({:.1})", self.level, cc),
+            None => self.level.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SongSnapshot {
+    pub deleted: bool,
+    pub version: Option<String>,
+    pub charts: Vec<ChartSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub name: String,
+    pub songs: BTreeMap<String, SongSnapshot>,
+}
+
+/// The merged chart data of some games at one point in time, keyed by game id.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub games: BTreeMap<String, GameSnapshot>,
+}
+
+impl Snapshot {
+    pub fn capture(catalogs: &[(&str, &dyn GameCatalog)]) -> Self {
+        let games = catalogs
+            .iter()
+            .map(|(game, catalog)| {
+                let mut songs = catalog
+                    .songs()
+                    .map(|(title, song)| {
+                        let snapshot = SongSnapshot {
+                            deleted: song.deleted(),
+                            version: song.version(),
+                            charts: vec![],
+                        };
+                        (title.clone(), snapshot)
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                for row in chart_rows(*catalog) {
+                    songs
+                        .get_mut(&row.title)
+                        .unwrap()
+                        .charts
+                        .push(ChartSnapshot {
+                            chart_type: row.chart_type,
+                            difficulty: row.difficulty,
+                            region: row.region,
+                            level: row.level,
+                            constant: row.constant,
+                        });
+                }
+                let snapshot = GameSnapshot {
+                    name: catalog.name().to_string(),
+                    songs,
+                };
+                (game.to_string(), snapshot)
+            })
+            .collect();
+        Snapshot { games }
+    }
+
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChartChange {
+    pub title: String,
+    pub old: ChartSnapshot,
+    pub new: ChartSnapshot,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameChanges {
+    pub game: String,
+    pub name: String,
+    pub new_songs: Vec<String>,
+    pub deleted_songs: Vec<String>,
+    /// (title, region) for existing songs that got their first charts in a region.
+    pub new_in_region: Vec<(String, String)>,
+    /// (title, region) for songs still listed that lost all their charts in a region.
+    pub removed_from_region: Vec<(String, String)>,
+    /// Charts added to songs that already had charts in that region, e.g. a new Re:MASTER.
+    pub new_charts: Vec<(String, ChartSnapshot)>,
+    /// Charts gone from songs that still have charts in that region.
+    pub removed_charts: Vec<(String, ChartSnapshot)>,
+    /// Level or constant changes.
+    pub changed_charts: Vec<ChartChange>,
+}
+
+impl GameChanges {
+    pub fn is_empty(&self) -> bool {
+        self.new_songs.is_empty()
+            && self.deleted_songs.is_empty()
+            && self.new_in_region.is_empty()
+            && self.removed_from_region.is_empty()
+            && self.new_charts.is_empty()
+            && self.removed_charts.is_empty()
+            && self.changed_charts.is_empty()
+    }
+
+    /// Announcement-style Discord markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut s = format!("**{}**\n", self.name);
+        if self.is_empty() {
+            s.push_str("No changes.\n");
+            return s;
+        }
+        let mut section = |heading: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                s.push_str(&format!("__{} ({})__\n", heading, lines.len()));
+                for line in lines {
+                    s.push_str(&format!("- {}\n", line));
+                }
+            }
+        };
+        section("New songs", self.new_songs.clone());
+        section("Deleted songs", self.deleted_songs.clone());
+        section(
+            "New in region",
+            self.new_in_region
+                .iter()
+                .map(|(title, region)| format!("{} ({})", title, region))
+                .collect(),
+        );
+        section(
+            "Removed from region",
+            self.removed_from_region
+                .iter()
+                .map(|(title, region)| format!("{} ({})", title, region))
+                .collect(),
+        );
+        let chart_lines = |charts: &[(String, ChartSnapshot)]| {
+            charts
+                .iter()
+                .map(|(title, chart)| {
+                    format!("{} {}: {}", title, chart.name(), chart.level_string())
+                })
+                .collect()
+        };
+        section("New charts", chart_lines(&self.new_charts));
+        section("Removed charts", chart_lines(&self.removed_charts));
+        section(
+            "Level changes",
+            self.changed_charts
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{} {}: {} → {}",
+                        change.title,
+                        change.new.name(),
+                        change.old.level_string(),
+                        change.new.level_string()
+                    )
+                })
+                .collect(),
+        );
+        s
+    }
+}
+
+/// Compares every game present in both snapshots.
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Vec<GameChanges> {
+    new.games
+        .iter()
+        .filter_map(|(game, new_game)| Some(diff_game(game, old.games.get(game)?, new_game)))
+        .collect()
+}
+
+fn diff_game(game: &str, old: &GameSnapshot, new: &GameSnapshot) -> GameChanges {
+    let mut changes = GameChanges {
+        game: game.to_string(),
+        name: new.name.clone(),
+        new_songs: vec![],
+        deleted_songs: vec![],
+        new_in_region: vec![],
+        removed_from_region: vec![],
+        new_charts: vec![],
+        removed_charts: vec![],
+        changed_charts: vec![],
+    };
+
+    for (title, old_song) in &old.songs {
+        if !old_song.deleted && new.songs.get(title).is_none_or(|s| s.deleted) {
+            changes.deleted_songs.push(title.clone());
+        }
+    }
+
+    for (title, new_song) in &new.songs {
+        if new_song.deleted {
+            continue;
+        }
+        let Some(old_song) = old.songs.get(title) else {
+            changes.new_songs.push(title.clone());
+            continue;
+        };
+
+        let old_regions = old_song
+            .charts
+            .iter()
+            .map(|c| c.region.as_str())
+            .collect::<BTreeSet<_>>();
+        let kept_regions = new_song
+            .charts
+            .iter()
+            .map(|c| c.region.as_str())
+            .collect::<BTreeSet<_>>();
+        let mut new_regions = BTreeSet::new();
+        for chart in &new_song.charts {
+            if !old_regions.contains(chart.region.as_str()) {
+                new_regions.insert(chart.region.clone());
+                continue;
+            }
+            match old_song.charts.iter().find(|c| c.same_chart(chart)) {
+                None => changes.new_charts.push((title.clone(), chart.clone())),
+                Some(old_chart) if old_chart != chart => changes.changed_charts.push(ChartChange {
+                    title: title.clone(),
+                    old: old_chart.clone(),
+                    new: chart.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        changes.new_in_region.extend(
+            new_regions
+                .into_iter()
+                .map(|region| (title.clone(), region)),
+        );
+
+        let mut removed_regions = BTreeSet::new();
+        for chart in &old_song.charts {
+            if !kept_regions.contains(chart.region.as_str()) {
+                removed_regions.insert(chart.region.clone());
+            } else if !new_song.charts.iter().any(|c| c.same_chart(chart)) {
+                changes.removed_charts.push((title.clone(), chart.clone()));
+            }
+        }
+        changes.removed_from_region.extend(
+            removed_regions
+                .into_iter()
+                .map(|region| (title.clone(), region)),
+        );
+    }
+    changes
+}
+
+/// Whole report for several games.
+pub fn changes_markdown(changes: &[GameChanges]) -> String {
+    changes
+        .iter()
+        .map(GameChanges::to_markdown)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Keeps `latest.json` in `dir` up to date with `live`, moving the old one to `previous.json`
+/// if anything changed. Returns the snapshot `/changes` compares against, if there is one.
+pub fn rotate_snapshots(dir: &Path, live: &Snapshot) -> eyre::Result<Option<Snapshot>> {
+    let latest = dir.join("latest.json");
+    let previous = dir.join("previous.json");
+    if latest.exists() {
+        if &Snapshot::load(&latest)? != live {
+            fs::rename(&latest, &previous)?;
+            live.save(&latest)?;
+        }
+    } else {
+        live.save(&latest)?;
+    }
+    if previous.exists() {
+        Ok(Some(Snapshot::load(&previous)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Show what changed in the last data update
+#[poise::command(slash_command, prefix_command, check = "cooldown_check")]
+pub async fn changes(
+    ctx: Context<'_>,
+    #[description = "Game to show changes for; all games if empty"]
+    #[autocomplete = "autocomplete_game"]
+    game: Option<String>,
+) -> Result<(), Error> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let data = ctx.data();
+    let Some(previous) = &data.previous_snapshot else {
+        ctx.say("No earlier data to compare with yet.").await?;
+        return Ok(());
+    };
+    let catalogs = match game {
+        Some(game) => match data.find_game(&game) {
+            Some(found) => vec![found],
+            None => {
+                ctx.say(format!("There is no game called {}.", game))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => data.catalogs(),
+    };
+    let report = changes_markdown(&diff(previous, &Snapshot::capture(&catalogs)));
+
+    say_or_attach(ctx, report, "changes.md", settings.ephemeral).await?;
+    Ok(())
+}
@@ -0,0 +1,340 @@
+//! The detailed chart view: one chart of a song at a time, with its level in each region,
+//! designer, note counts and links, and buttons that switch the message to another chart type
+//! or difficulty. The buttons are answered by `components`, so they keep working after restarts.
+
+use std::{fmt::Write as _, io::Write as _};
+
+use poise::serenity_prelude::{ButtonStyle, CreateActionRow, CreateButton, CreateComponents};
+
+use crate::{
+    catalog::*,
+    components::{Action, ComponentId},
+    config::config,
+    document::Document,
+    preferences::render_options,
+    settings::query_settings,
+    utils::*,
+};
+
+/// Buttons per action row, as Discord allows.
+const ROW_LEN: usize = 5;
+
+/// The chart a switcher button asks for. Parts left out are filled in by `resolve`,
+/// so the default is "whatever chart the song is best shown with".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChartChoice {
+    /// "DX" or "ST" for maimai.
+    pub chart_type: Option<&'static str>,
+    /// BASIC = 0, ADVANCED = 1, ...
+    pub difficulty: Option<usize>,
+}
+
+impl ChartChoice {
+    /// `type.difficulty` with missing parts left empty, e.g. "DX.3", ".4" or "ST.".
+    pub fn encode(self) -> String {
+        format!(
+            "{}.{}",
+            self.chart_type.unwrap_or(""),
+            self.difficulty.map_or(String::new(), |d| d.to_string())
+        )
+    }
+
+    /// `None` for anything `encode` doesn't make.
+    pub fn decode(s: &str) -> Option<Self> {
+        let (chart_type, difficulty) = s.split_once('.')?;
+        let chart_type = match chart_type {
+            "" => None,
+            "DX" => Some("DX"),
+            "ST" => Some("ST"),
+            _ => return None,
+        };
+        let difficulty = match difficulty {
+            "" => None,
+            d => Some(d.parse().ok()?),
+        };
+        Some(Self {
+            chart_type,
+            difficulty,
+        })
+    }
+}
+
+/// A chart of a song: its type (`None` for games without chart types) and difficulty.
+pub type ChartKey = (Option<&'static str>, usize);
+
+/// The charts of `song` in either region, grouped by type in the order of `chart_entries`
+/// and sorted by difficulty within a type.
+pub fn song_charts(song: &dyn SongInfo) -> Vec<ChartKey> {
+    let entries = song.chart_entries();
+    let mut types = vec![];
+    for entry in &entries {
+        if !types.contains(&entry.chart_type) {
+            types.push(entry.chart_type);
+        }
+    }
+    let mut charts = vec![];
+    for chart_type in types {
+        let mut difficulties = entries
+            .iter()
+            .filter(|entry| entry.chart_type == chart_type)
+            .map(|entry| entry.difficulty)
+            .collect::<Vec<_>>();
+        difficulties.sort();
+        difficulties.dedup();
+        charts.extend(difficulties.into_iter().map(|d| (chart_type, d)));
+    }
+    charts
+}
+
+/// The chart `choice` points at. A type the song doesn't have falls back to its first type,
+/// and a missing difficulty to MASTER, or the hardest chart if there is no MASTER.
+pub fn resolve(charts: &[ChartKey], choice: ChartChoice) -> Option<ChartKey> {
+    let chart_type = match choice.chart_type {
+        Some(t) if charts.iter().any(|&(chart_type, _)| chart_type == Some(t)) => Some(t),
+        _ => charts.first()?.0,
+    };
+    let difficulties = charts
+        .iter()
+        .filter(|&&(t, _)| t == chart_type)
+        .map(|&(_, d)| d)
+        .collect::<Vec<_>>();
+    let difficulty = [choice.difficulty, Some(3)]
+        .into_iter()
+        .flatten()
+        .find(|d| difficulties.contains(d))
+        .or_else(|| difficulties.last().copied())?;
+    Some((chart_type, difficulty))
+}
+
+/// A switcher button.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewButton {
+    pub label: String,
+    pub custom_id: String,
+    /// The button of the chart being shown, which is highlighted and can't be clicked.
+    pub current: bool,
+}
+
+/// One chart of a song, ready to send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChartView {
+    pub document: Document,
+    /// A row of chart types if the song has more than one, then rows of difficulties.
+    pub rows: Vec<Vec<ViewButton>>,
+}
+
+impl ChartView {
+    /// Fills Discord message components with the switcher buttons.
+    pub fn components<'a>(&self, c: &'a mut CreateComponents) -> &'a mut CreateComponents {
+        for row in &self.rows {
+            let mut ar = CreateActionRow::default();
+            for view_button in row {
+                let mut button = CreateButton::default();
+                button
+                    .custom_id(&view_button.custom_id)
+                    .label(&view_button.label)
+                    .disabled(view_button.current)
+                    .style(if view_button.current {
+                        ButtonStyle::Primary
+                    } else {
+                        ButtonStyle::Secondary
+                    });
+                ar.add_button(button);
+            }
+            c.add_action_row(ar);
+        }
+        c
+    }
+}
+
+fn difficulty_name(catalog: &dyn GameCatalog, difficulty: usize) -> String {
+    catalog
+        .difficulty_names()
+        .get(difficulty)
+        .map_or_else(|| format!("Lv.{}", difficulty + 1), |name| name.to_string())
+}
+
+/// The chart of the song stored under `title` that `choice` points at.
+pub fn chart_view(
+    catalog: &dyn GameCatalog,
+    title: &str,
+    choice: ChartChoice,
+    options: &RenderOptions,
+) -> eyre::Result<ChartView> {
+    let Some(song) = catalog.song(title) else {
+        eyre::bail!("No data for {title}");
+    };
+    let charts = song_charts(song);
+    let Some((chart_type, difficulty)) = resolve(&charts, choice) else {
+        eyre::bail!("No charts for {title}");
+    };
+    let display_title = catalog.display_title(title);
+    let name = difficulty_name(catalog, difficulty);
+
+    let entries = song.chart_entries();
+    let entry = |region| {
+        entries.iter().find(|entry| {
+            entry.region == region
+                && entry.chart_type == chart_type
+                && entry.difficulty == difficulty
+        })
+    };
+    let level = |region| match entry(region) {
+        Some(entry) => format!(
+            "Lv.**{}**{}",
+            entry.level,
+            if options.constants {
+                constant_to_string(entry.constant)
+            } else {
+                "".to_string()
+            }
+        ),
+        None => "**Unreleased**".to_string(),
+    };
+    let in_region = |region| entries.iter().any(|entry| entry.region == region);
+
+    let mut description = if song.deleted() {
+        "**THIS SONG IS DELETED**\n\n".to_string()
+    } else {
+        String::new()
+    };
+    match chart_type {
+        Some(chart_type) => writeln!(description, "**{} {}**", chart_type, name)?,
+        None => writeln!(description, "**{}**", name)?,
+    }
+    let levels = match (
+        options.region,
+        in_region(Region::Jp),
+        in_region(Region::Intl),
+    ) {
+        (None, true, false) => format!(":flag_jp: {}", level(Region::Jp)),
+        (None, false, true) => format!(":globe_with_meridians: {}", level(Region::Intl)),
+        _ => region_lines(&level(Region::Jp), &level(Region::Intl), options),
+    };
+    description.push_str(&levels);
+    let version = entry(Region::Jp)
+        .or_else(|| entry(Region::Intl))
+        .and_then(|entry| entry.version);
+    if let Some(version) = version {
+        write!(description, "\n**Added in:** {}", version.name)?;
+    }
+    if let Some(details) = song.chart_details(chart_type, difficulty) {
+        if let Some(designer) = details.designer {
+            write!(description, "\n**Designer:** {}", designer)?;
+        }
+        if !details.notes.is_empty() {
+            let total = details.notes.iter().map(|(_, count)| count).sum::<usize>();
+            let notes = details
+                .notes
+                .iter()
+                .map(|(kind, count)| format!("{} {}", kind, count))
+                .collect::<Vec<_>>()
+                .join(" / ");
+            write!(description, "\n**Notes:** **{}** ({})", total, notes)?;
+        }
+    }
+
+    let chart = match chart_type {
+        Some(chart_type) => format!("{}+{}", chart_type, name),
+        None => name.clone(),
+    };
+    let mut links = vec![];
+    if options.links {
+        links.push(format!(
+            "[Play videos]({})",
+            youtube_search(catalog.name(), &display_title, &chart)
+        ));
+    }
+    if let Some(viewer) = config().chart_viewer(catalog.id()) {
+        let url = viewer
+            .replace("{title}", &urlencoding::encode(&display_title))
+            .replace("{type}", chart_type.unwrap_or(""))
+            .replace("{difficulty}", &name);
+        links.push(format!("[Chart viewer]({})", url));
+    }
+    if !links.is_empty() {
+        write!(description, "\n\n{}", links.join(" · "))?;
+    }
+
+    let button = |label: String, choice: ChartChoice, current: bool| ViewButton {
+        label,
+        custom_id: ComponentId::new(Action::Chart(choice), catalog.id(), title).to_string(),
+        current,
+    };
+    let mut rows = vec![];
+    let mut types = charts.iter().map(|&(t, _)| t).collect::<Vec<_>>();
+    types.dedup();
+    if types.len() > 1 {
+        // The current type's button leaves the difficulty out, so its id differs from the
+        // current difficulty's button below.
+        let row = types
+            .into_iter()
+            .map(|t| {
+                let current = t == chart_type;
+                let choice = ChartChoice {
+                    chart_type: t,
+                    difficulty: (!current).then_some(difficulty),
+                };
+                button(t.unwrap_or("-").to_string(), choice, current)
+            })
+            .collect();
+        rows.push(row);
+    }
+    let difficulties = charts
+        .iter()
+        .filter(|&&(t, _)| t == chart_type)
+        .map(|&(_, d)| {
+            let choice = ChartChoice {
+                chart_type,
+                difficulty: Some(d),
+            };
+            button(difficulty_name(catalog, d), choice, d == difficulty)
+        })
+        .collect::<Vec<_>>();
+    rows.extend(difficulties.chunks(ROW_LEN).map(<[_]>::to_vec));
+
+    Ok(ChartView {
+        document: Document {
+            title: display_title,
+            description,
+            fields: vec![],
+            thumbnail: catalog.jacket_url(title),
+            color: catalog.color(),
+        },
+        rows,
+    })
+}
+
+/// The `detailed-*-info` commands: the chart view of a song, or a suggestion if the title
+/// isn't known.
+pub async fn chart_template(
+    ctx: Context<'_>,
+    catalog: &dyn GameCatalog,
+    title: String,
+) -> eyre::Result<()> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let options = render_options(ctx, &settings).await;
+    let server_id = ctx
+        .guild_id()
+        .unwrap_or(poise::serenity_prelude::GuildId(0));
+    let Some(actual_title) = catalog.lookup(&title, server_id) else {
+        let mut log = ctx.data().alias_log.lock().await;
+        writeln!(log, "{}\t{}", title, catalog.name())?;
+        log.sync_all()?;
+        drop(log);
+        let closest = get_closest_title(&title, catalog.aliases(), server_id);
+        let action = Action::Chart(ChartChoice::default());
+        return suggest(ctx, catalog, &title, &closest, action).await;
+    };
+    let view = chart_view(catalog, &actual_title, ChartChoice::default(), &options)?;
+
+    ctx.send(|f| {
+        f.ephemeral(settings.ephemeral)
+            .embed(|f| view.document.embed(f))
+            .components(|c| view.components(c))
+    })
+    .await?;
+    Ok(())
+}
@@ -2,14 +2,16 @@ use std::{
     collections::HashMap,
     fs::{self, File},
     io::{BufRead, BufReader},
-    sync::Arc,
 };
 
-use eyre::{bail, ensure};
+use eyre::{bail, ensure, eyre};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 
-use crate::utils::*;
+use crate::{
+    catalog::*, chart_view::chart_template, config::*, cooldowns::cooldown_check, document::Field,
+    utils::*, versions::*,
+};
 
 lazy_static! {
     static ref CHUNI_INFO_REPLACEMENT: HashMap<String, String> = {
@@ -42,9 +44,59 @@ lazy_static! {
     };
 }
 
-fn get_chuni_embed(title: String, ctx: &Context<'_>) -> eyre::Result<(String, Option<String>)> {
-    let song = ctx.data().chuni_charts.get(&title).unwrap();
+impl SongInfo for ChuniInfo {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn jp_jacket(&self) -> Option<&str> {
+        self.jp_jacket.as_deref()
+    }
+
+    fn version(&self) -> Option<String> {
+        self.version.clone()
+    }
+
+    fn chart_entries(&self) -> Vec<ChartEntry> {
+        let version = self
+            .version
+            .as_ref()
+            .and_then(|v| versions().find("chuni", v));
+        let mut entries = vec![];
+        if let Some(lv) = &self.jp_lv {
+            entries.extend(difficulty_entries(lv, Region::Jp, None, version));
+        }
+        if let Some(lv) = &self.intl_lv {
+            entries.extend(difficulty_entries(lv, Region::Intl, None, version));
+        }
+        entries
+    }
+}
+
+impl GameSong for ChuniInfo {
+    const NAME: &'static str = "CHUNITHM";
+    const COLOR: (u8, u8, u8) = (255, 255, 0);
+    const DIFFICULTY_NAMES: &'static [&'static str] =
+        &["BASIC", "ADVANCED", "EXPERT", "MASTER", "ULTIMA"];
 
+    fn render(&self, title: &str, options: &RenderOptions) -> eyre::Result<(String, Vec<Field>)> {
+        get_chuni_embed(self, title, options)
+    }
+}
+
+fn get_chuni_embed(
+    song: &ChuniInfo,
+    title: &str,
+    options: &RenderOptions,
+) -> eyre::Result<(String, Vec<Field>)> {
     let mut description = if song.deleted {
         "**THIS SONG IS DELETED**\n\n"
     } else {
@@ -52,130 +104,112 @@ fn get_chuni_embed(title: String, ctx: &Context<'_>) -> eyre::Result<(String, Op
     }
     .to_string();
 
-    description = format!(
-        "{}**Artist:** {}",
-        description,
-        song.artist.replace('*', "\\*")
-    );
-    description = if let Some(version) = song.version.as_ref() {
-        format!("{}\n**Version:** {}", description, version)
-    } else {
-        description
-    };
-    description = if let Some(bpm) = song.bpm {
-        format!("{}\n**BPM:** {}", description, bpm)
-    } else {
-        description
-    };
+    if !options.compact {
+        description = format!(
+            "{}**Artist:** {}",
+            description,
+            song.artist.replace('*', "\\*")
+        );
+        if let Some(version) = song.version.as_ref() {
+            description = format!("{}\n**Version:** {}", description, version);
+        }
+        if let Some(bpm) = song.bpm {
+            description = format!("{}\n**BPM:** {}", description, bpm);
+        }
+    }
 
     let in_lv = &song.intl_lv;
     let jp_lv = &song.jp_lv;
 
-    if song.deleted {
-        description = format!(
-            "{}\n\n**Level:**\n{}",
-            description,
-            level_description(song.jp_lv.as_ref().unwrap(), &title)
-        )
+    let levels = if song.deleted {
+        let jp_lv = jp_lv
+            .as_ref()
+            .ok_or_else(|| eyre!("No JP levels for deleted song {title}"))?;
+        level_description(jp_lv, title, options)
     } else {
+        if jp_lv.is_none() && in_lv.is_none() {
+            bail!("No levels for {title}");
+        }
         let jp_txt = if let Some(jp_lv) = jp_lv {
-            level_description(jp_lv, &title)
+            level_description(jp_lv, title, options)
         } else {
             "**Unreleased**".to_string()
         };
         let in_txt = if let Some(in_lv) = in_lv {
-            level_description(in_lv, &title)
+            level_description(in_lv, title, options)
         } else {
             "**Unreleased**".to_string()
         };
-        if in_txt == jp_txt {
-            description = format!(
-                "{}
-
-**Level:**
-:flag_jp::globe_with_meridians: {}",
-                description, jp_txt
-            );
-        } else {
-            description = format!(
-                "{}
-
-**Level:**
-:flag_jp: {}
-:globe_with_meridians: {}",
-                description, jp_txt, in_txt
-            );
-        }
-    }
-
-    Ok((description, song.jp_jacket.clone()))
-}
+        region_lines(&jp_txt, &in_txt, options)
+    };
 
-fn chuni_duplicate_alias_to_title(title: &String) -> String {
-    title.to_string()
+    Ok((
+        description.trim().to_string(),
+        vec![Field::new("Level", levels)],
+    ))
 }
 
 /// Get CHUNITHM song info
-#[poise::command(slash_command, prefix_command, rename = "chuni-info")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "chuni-info",
+    check = "cooldown_check"
+)]
 pub async fn chuni_info(
     ctx: Context<'_>,
+    #[description = "Show a chart card image instead of text"]
+    #[flag]
+    card: bool,
     #[description = "Song title e.g. \"Xevel\", \"Ikazuchi\", etc. You don't have to be exact; try things out!"]
     #[rest]
     title: String,
 ) -> Result<(), Error> {
-    info_template(
-        ctx,
-        title,
-        Game::Chunithm,
-        Arc::new(get_chuni_embed),
-        (255, 255, 0),
-        Arc::new(chuni_duplicate_alias_to_title),
-    )
-    .await?;
+    info_template(ctx, &ctx.data().chuni, title, card).await?;
     Ok(())
 }
 
-fn level_description(lv: &Difficulty, title: &str) -> String {
-    let title = title.replace(" -", " ");
-    let title = title.strip_prefix('-').unwrap_or(&title);
-    let title = urlencoding::encode(title);
-    format!(
-        "[B](https://www.youtube.com/results?search_query=CHUNITHM+{}+BASIC) **{}**{} / [A](https://www.youtube.com/results?search_query=CHUNITHM+{}+ADVANCED) **{}**{} / [E](https://www.youtube.com/results?search_query=CHUNITHM+{}+EXPERT) **{}**{} / [M](https://www.youtube.com/results?search_query=CHUNITHM+{}+MASTER) **{}**{}{}",
-        title,
-        lv.bas,
-        constant_to_string(lv.bas_c),
-        title,
-        lv.adv,
-        constant_to_string(lv.adv_c),
-        title,
-        lv.exp,
-        constant_to_string(lv.exp_c),
-        title,
-        lv.mas,
-        constant_to_string(lv.mas_c),
-        if let Some(rem) = &lv.extra {
-            format!(" / [U](https://www.youtube.com/results?search_query=CHUNITHM+{}+ULTIMA) **{}**{}", title, rem, constant_to_string(lv.extra_c))
-        } else {
-            "".to_string()
-        }
-    )
+/// Get detailed info about each chart of a CHUNITHM song
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "detailed-chuni-info",
+    check = "cooldown_check"
+)]
+pub async fn chuni_chart(
+    ctx: Context<'_>,
+    #[description = "Song title e.g. \"Xevel\", \"Ikazuchi\", etc. You don't have to be exact; try things out!"]
+    #[rest]
+    title: String,
+) -> Result<(), Error> {
+    chart_template(ctx, &ctx.data().chuni, title).await?;
+    Ok(())
+}
+
+fn level_description(lv: &Difficulty, title: &str, options: &RenderOptions) -> String {
+    difficulty_line(lv, "CHUNITHM", title, "ULTIMA", options)
 }
 
 /// Get CHUNITHM song jacket
-#[poise::command(slash_command, prefix_command, rename = "chuni-jacket")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "chuni-jacket",
+    check = "cooldown_check"
+)]
 pub async fn chuni_jacket(
     ctx: Context<'_>,
     #[description = "Song title e.g. \"Xevel\", \"Ikazuchi\", etc. You don't have to be exact; try things out!"]
     #[rest]
     title: String,
 ) -> Result<(), Error> {
-    jacket_template(ctx, title, Game::Chunithm).await?;
+    jacket_template(ctx, &ctx.data().chuni, title).await?;
     Ok(())
 }
 
 fn set_jp_difficulty(charts: &mut HashMap<String, ChuniInfo>) -> eyre::Result<()> {
     // Get JP difficulty.
-    let url = fs::read_to_string("data/chuni/chuni-url.txt")?;
+    let url = config().source(&config().chuni.jp_url, "chuni/chuni-url.txt")?;
     let url = url.trim();
     let s = get_curl(url);
 
@@ -243,7 +277,7 @@ fn set_intl_difficulty(
         return Ok(());
     }
     // Get intl difficulty.
-    let url = fs::read_to_string("data/chuni/chuni-intl.txt")?;
+    let url = config().source(&config().chuni.intl_url, "chuni/chuni-intl.txt")?;
     let url = url.trim();
     let s = get_curl(url);
     let songs: serde_json::Value = serde_json::from_str(&s)?;
@@ -291,7 +325,7 @@ fn set_intl_difficulty(
 }
 
 fn remove_unreleased_intl_info(charts: &mut HashMap<String, ChuniInfo>) -> eyre::Result<()> {
-    let constants = fs::read_to_string("data/chuni/chuni-info.txt")?;
+    let constants = config().source(&config().chuni.info_url, "chuni/chuni-info.txt")?;
     let url = constants.trim();
     let s = get_curl(url);
     let songs: serde_json::Value = serde_json::from_str(&s).unwrap();
@@ -325,7 +359,7 @@ fn set_constants(
     jp_and_intl_version_is_different: bool,
 ) -> eyre::Result<()> {
     // Get constants
-    let constants = fs::read_to_string("data/chuni/chuni-info.txt")?;
+    let constants = config().source(&config().chuni.info_url, "chuni/chuni-info.txt")?;
     let url = constants.trim();
     let s = get_curl(url);
     let songs: serde_json::Value = serde_json::from_str(&s).unwrap();
@@ -394,12 +428,10 @@ fn set_constants(
             if c != 0.0 {
                 difficulty.set_constant(diff_c, c.to_string());
                 // Set intl cc too, if song isn't deleted.
-                if !jp_and_intl_version_is_different && chart.intl_lv.is_some() {
-                    chart
-                        .intl_lv
-                        .as_mut()
-                        .unwrap()
-                        .set_constant(diff_c, c.to_string());
+                if !jp_and_intl_version_is_different {
+                    if let Some(intl_lv) = chart.intl_lv.as_mut() {
+                        intl_lv.set_constant(diff_c, c.to_string());
+                    }
                 }
             }
 
@@ -437,7 +469,7 @@ fn set_intl_info(
     jp_and_intl_version_is_different: bool,
 ) -> eyre::Result<()> {
     // Get constants
-    let s = fs::read_to_string("data/chuni/chuni-info-verse.json")?;
+    let s = fs::read_to_string(data_path(&config().chuni.constants_file))?;
     let songs: serde_json::Value = serde_json::from_str(&s).unwrap();
     let songs = songs.as_object().unwrap()["songs"].as_array().unwrap();
     for song in songs {
@@ -501,7 +533,7 @@ fn _set_intl_info_csv(
 ) -> eyre::Result<()> {
     if jp_and_intl_version_is_different {
         // Add intl level info
-        let file = File::open("data/chuni/chuni-sun-lv.csv")?;
+        let file = File::open(data_path(&config().chuni.intl_levels_file))?;
         let lines = BufReader::new(file).lines();
         for line in lines.map_while(Result::ok) {
             let line = line.split('\t').collect_vec();
@@ -522,7 +554,7 @@ fn _set_intl_info_csv(
         }
 
         // Add intl constant info
-        let file = File::open("data/chuni/chuni-sun-cst.csv")?;
+        let file = File::open(data_path(&config().chuni.intl_constants_file))?;
         let lines = BufReader::new(file).lines();
         for line in lines.map_while(Result::ok) {
             let line = line.split('\t').collect_vec();
@@ -578,7 +610,7 @@ fn _set_intl_info_csv(
 
 fn set_manual_constants(charts: &mut HashMap<String, ChuniInfo>) -> eyre::Result<()> {
     // Add manual constant info
-    let file = File::open("data/chuni/chuni-manual-add.txt")?;
+    let file = File::open(data_path("chuni/chuni-manual-add.txt"))?;
     let lines = BufReader::new(file).lines();
     for line in lines.map_while(Result::ok) {
         let line = line.split('\t').collect_vec();
@@ -628,7 +660,7 @@ fn set_manual_constants(charts: &mut HashMap<String, ChuniInfo>) -> eyre::Result
 
 pub fn set_chuni_charts() -> Result<HashMap<String, ChuniInfo>, Error> {
     let mut charts = HashMap::new();
-    let jp_and_intl_version_is_different = true;
+    let jp_and_intl_version_is_different = config().chuni.jp_and_intl_version_is_different;
 
     set_jp_difficulty(&mut charts)?;
     set_intl_difficulty(&mut charts, jp_and_intl_version_is_different)?;
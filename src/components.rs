@@ -0,0 +1,301 @@
+//! Buttons and select menus that don't depend on the command that sent them still running.
+//! Everything needed to answer a click is in the component's custom id, e.g.
+//! `gcm:info:maimai:Selector`, and `event_handler` answers it from there, so components keep
+//! working for as long as their message exists, restarts included.
+
+use std::{fmt, sync::Arc};
+
+use poise::serenity_prelude::{
+    self as serenity, interaction::InteractionResponseType, Interaction,
+    MessageComponentInteraction,
+};
+
+use crate::{
+    artists::{artist_catalogs, artist_page},
+    catalog::GameCatalog,
+    chart_view::{chart_view, ChartChoice},
+    errors::Failure,
+    utils::*,
+};
+
+/// Marks custom ids as ours.
+const PREFIX: &str = "gcm";
+/// Discord's limit on custom ids.
+const MAX_ID_LEN: usize = 100;
+
+/// What a component does for its song.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// The info embed.
+    Info,
+    /// The chart card image.
+    Card,
+    Jacket,
+    /// The detailed chart view. The default choice sends a new message; the switcher buttons,
+    /// which name a chart, change the message they are on.
+    Chart(ChartChoice),
+    /// A page of `/artist`; the song slot holds the artist's name.
+    Artist(usize),
+}
+
+impl Action {
+    fn encode(self) -> String {
+        match self {
+            Action::Info => "info".to_string(),
+            Action::Card => "card".to_string(),
+            Action::Jacket => "jacket".to_string(),
+            Action::Chart(choice) if choice == ChartChoice::default() => "chart".to_string(),
+            Action::Chart(choice) => format!("chart.{}", choice.encode()),
+            Action::Artist(page) => format!("artist.{}", page),
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        if let Some(choice) = s.strip_prefix("chart.") {
+            return ChartChoice::decode(choice).map(Action::Chart);
+        }
+        if let Some(page) = s.strip_prefix("artist.") {
+            return page.parse().ok().map(Action::Artist);
+        }
+        Some(match s {
+            "info" => Action::Info,
+            "card" => Action::Card,
+            "jacket" => Action::Jacket,
+            "chart" => Action::Chart(ChartChoice::default()),
+            _ => return None,
+        })
+    }
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is the same in every build.
+fn stable_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// A parsed custom id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentId {
+    pub action: Action,
+    /// Catalog id of the game, or "all" for artists.
+    pub game: String,
+    /// The song title, or `#` and its hash if the title doesn't fit in a custom id.
+    song: String,
+}
+
+impl ComponentId {
+    pub fn new(action: Action, game: &str, title: &str) -> Self {
+        let mut id = Self {
+            action,
+            game: game.to_string(),
+            song: title.to_string(),
+        };
+        if title.starts_with('#') || id.to_string().len() > MAX_ID_LEN {
+            id.song = format!("#{:016x}", stable_hash(title));
+        }
+        id
+    }
+
+    /// `None` for ids that aren't ours.
+    pub fn parse(id: &str) -> Option<Self> {
+        let mut parts = id.splitn(4, ':');
+        if parts.next()? != PREFIX {
+            return None;
+        }
+        Some(Self {
+            action: Action::parse(parts.next()?)?,
+            game: parts.next()?.to_string(),
+            song: parts.next()?.to_string(),
+        })
+    }
+
+    /// Whether the id was made for `name`, which may have been stored as a hash.
+    pub fn names(&self, name: &str) -> bool {
+        match self.song.strip_prefix('#') {
+            Some(hash) => u64::from_str_radix(hash, 16).ok() == Some(stable_hash(name)),
+            None => self.song == name,
+        }
+    }
+
+    /// The title of the song in `catalog`, if it still exists.
+    pub fn title(&self, catalog: &dyn GameCatalog) -> Option<String> {
+        if self.song.starts_with('#') {
+            return catalog
+                .songs()
+                .map(|(title, _)| title)
+                .find(|title| self.names(title))
+                .cloned();
+        }
+        catalog.song(&self.song).map(|_| self.song.clone())
+    }
+}
+
+impl fmt::Display for ComponentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            PREFIX,
+            self.action.encode(),
+            self.game,
+            self.song
+        )
+    }
+}
+
+/// Answers a click on one of our components.
+async fn handle(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+    data: &Data,
+    id: &ComponentId,
+) -> eyre::Result<()> {
+    let settings = match component.guild_id {
+        Some(guild) => data.settings.lock().await.get(guild),
+        None => Default::default(),
+    };
+    if !settings.channel_allowed(component.channel_id) {
+        return respond(ctx, component, "Queries are turned off in this channel.").await;
+    }
+    if let Action::Artist(page) = id.action {
+        return artist_page_clicked(ctx, component, data, id, page).await;
+    }
+    let options = data
+        .preferences
+        .lock()
+        .await
+        .get(component.user.id)
+        .render_options(&settings);
+    let catalog = data
+        .catalog_by_id(&id.game)
+        .ok_or_else(|| eyre::eyre!("No game {:?}", id.game))?;
+    let Some(title) = id.title(catalog) else {
+        return respond(
+            ctx,
+            component,
+            "This song is not in the bot's data anymore.",
+        )
+        .await;
+    };
+
+    let mut attachment = None;
+    let mut document = None;
+    let mut view = None;
+    match id.action {
+        Action::Info => document = Some(catalog.document_with(&title, &options)?),
+        Action::Card => attachment = Some(card_attachment(data, catalog, &title, &options).await?),
+        Action::Jacket => attachment = jacket_attachment(data, catalog, &title).await,
+        Action::Chart(choice) => {
+            let chart = chart_view(catalog, &title, choice, &options)?;
+            if choice != ChartChoice::default() {
+                component
+                    .create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::UpdateMessage)
+                            .interaction_response_data(|d| {
+                                d.embed(|f| chart.document.embed(f))
+                                    .components(|c| chart.components(c))
+                            })
+                    })
+                    .await?;
+                return Ok(());
+            }
+            view = Some(chart);
+        }
+        Action::Artist(_) => unreachable!("artist pages are answered above"),
+    }
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.ephemeral(settings.ephemeral)
+                        .content(format!("Query by <@{}>", component.user.id));
+                    if let Some(attachment) = attachment {
+                        d.add_file(attachment);
+                    }
+                    if let Some(document) = document {
+                        d.embed(|f| document.embed(f));
+                    }
+                    if let Some(view) = view {
+                        d.embed(|f| view.document.embed(f))
+                            .components(|c| view.components(c));
+                    }
+                    d
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Turns the `/artist` message to another page.
+async fn artist_page_clicked(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+    data: &Data,
+    id: &ComponentId,
+    page: usize,
+) -> eyre::Result<()> {
+    let Some(artist) = data.artists.artists().find(|artist| id.names(&artist.name)) else {
+        return respond(
+            ctx,
+            component,
+            "This artist is not in the bot's data anymore.",
+        )
+        .await;
+    };
+    let page = artist_page(artist_catalogs(data), artist, page);
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|f| page.document.embed(f))
+                        .components(|c| page.components(c))
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+/// A private reply to whoever clicked.
+async fn respond(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+    text: &str,
+) -> eyre::Result<()> {
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.ephemeral(true).content(text))
+        })
+        .await?;
+    Ok(())
+}
+
+/// `FrameworkOptions::event_handler` of the bot.
+pub async fn event_handler(
+    ctx: &serenity::Context,
+    event: &poise::Event<'_>,
+    _framework: poise::FrameworkContext<'_, Arc<Data>, Error>,
+    data: &Arc<Data>,
+) -> Result<(), Error> {
+    let poise::Event::InteractionCreate {
+        interaction: Interaction::MessageComponent(component),
+    } = event
+    else {
+        return Ok(());
+    };
+    let Some(id) = ComponentId::parse(&component.data.custom_id) else {
+        return Ok(());
+    };
+    if let Err(e) = handle(ctx, component, data, &id).await {
+        eprintln!(
+            "Component {:?} by {} failed: {:?}",
+            component.data.custom_id, component.user.id, e
+        );
+        let text = Failure::Internal.message(component.locale.starts_with("ko"));
+        if let Err(e) = respond(ctx, component, &text).await {
+            eprintln!("Cannot tell the user about the error: {}", e);
+        }
+    }
+    Ok(())
+}
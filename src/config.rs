@@ -0,0 +1,475 @@
+//! Runtime settings, read from a TOML file and `GCM_*` environment variables.
+//!
+//! The file is `config.toml` in the working directory, or whatever `GCM_CONFIG` points to.
+//! Every setting can be overridden by an environment variable named after it:
+//! `GCM_DATA_DIR=/srv/gcm/data`, `GCM_TIMEOUTS_UPDATE_CHECK_HOURS=12`,
+//! `GCM_MAIMAI_JP_URL=https://...`, `GCM_OWNERS=1234,5678`. Values are read as the setting's
+//! type: text as is, `true`/`false`, numbers, and lists of ids as `1,2` or `[1, 2]`.
+//! Other `GCM_*` variables are ignored.
+//!
+//! Version names and release dates aren't settings; they are in `data/versions.toml`. Each
+//! game's `current_version` only picks one of those as the current JP version.
+//!
+//! Source URLs and jacket prefixes that aren't set are read from the files the bot used
+//! before there was a config, e.g. `data/maimai/maimai-jp.txt`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use eyre::{bail, ensure, WrapErr};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const ENV_PREFIX: &str = "GCM_";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Source files, aliases and bot state. Other paths in the config are relative to this.
+    pub data_dir: PathBuf,
+    /// Discord user ids allowed to use owner-only commands.
+    pub owners: Vec<u64>,
+    /// DM the owners a summary when a command fails.
+    pub notify_owners: bool,
+    pub timeouts: Timeouts,
+    pub maimai: MaiConfig,
+    pub chuni: ChuniConfig,
+    pub ongeki: OngekiConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("data"),
+            owners: vec![],
+            notify_owners: false,
+            timeouts: Timeouts::default(),
+            maimai: MaiConfig::default(),
+            chuni: ChuniConfig::default(),
+            ongeki: OngekiConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    /// How often subscribed games are re-fetched for update announcements.
+    pub update_check_hours: u64,
+    /// Pause between update announcements to different channels.
+    pub announce_delay_secs: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            update_check_hours: 6,
+            announce_delay_secs: 2,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn update_check(&self) -> Duration {
+        Duration::from_secs(self.update_check_hours * 60 * 60)
+    }
+
+    pub fn announce_delay(&self) -> Duration {
+        Duration::from_secs(self.announce_delay_secs)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaiConfig {
+    /// JP song list. Default: contents of `maimai/maimai-jp.txt`.
+    pub jp_url: Option<String>,
+    /// Song info (versions, sheets). Default: contents of `maimai/maimai-info.txt`.
+    pub info_url: Option<String>,
+    /// Default: contents of `maimai/maimai-jacket-prefix.txt`.
+    pub jacket_prefix: Option<String>,
+    /// Whether intl is on an older version than JP, with levels from `maimai/in_lv.csv`.
+    pub jp_and_intl_version_is_different: bool,
+    /// JP version that counts as current (for `--new` and the new-songs rating frame), by its
+    /// name in `versions.toml`. Default: the latest one released.
+    pub current_version: Option<String>,
+    /// Chart constants of the current JP version.
+    pub constants_file: PathBuf,
+    /// Chart viewer link shown in detailed chart info, with `{title}`, `{type}` and
+    /// `{difficulty}` filled in. No link if unset.
+    pub chart_viewer: Option<String>,
+}
+
+impl Default for MaiConfig {
+    fn default() -> Self {
+        Self {
+            jp_url: None,
+            info_url: None,
+            jacket_prefix: None,
+            jp_and_intl_version_is_different: false,
+            current_version: None,
+            constants_file: PathBuf::from("maimai/prismplus 2025-04-24.json"),
+            chart_viewer: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChuniConfig {
+    /// JP song list. Default: contents of `chuni/chuni-url.txt`.
+    pub jp_url: Option<String>,
+    /// Intl song list. Default: contents of `chuni/chuni-intl.txt`.
+    pub intl_url: Option<String>,
+    /// Song info and constants. Default: contents of `chuni/chuni-info.txt`.
+    pub info_url: Option<String>,
+    /// Default: contents of `chuni/chuni-jacket-prefix.txt`.
+    pub jacket_prefix: Option<String>,
+    /// Whether intl is on an older version than JP.
+    pub jp_and_intl_version_is_different: bool,
+    /// JP version that counts as current (for `--new` and the new-songs rating frame), by its
+    /// name in `versions.toml`. Default: the latest one released.
+    pub current_version: Option<String>,
+    /// Chart constants of the current JP version.
+    pub constants_file: PathBuf,
+    /// Intl levels and constants, used while intl is on an older version.
+    pub intl_levels_file: PathBuf,
+    pub intl_constants_file: PathBuf,
+    /// Chart viewer link shown in detailed chart info, with `{title}`, `{type}` and
+    /// `{difficulty}` filled in. No link if unset.
+    pub chart_viewer: Option<String>,
+}
+
+impl Default for ChuniConfig {
+    fn default() -> Self {
+        Self {
+            jp_url: None,
+            intl_url: None,
+            info_url: None,
+            jacket_prefix: None,
+            jp_and_intl_version_is_different: true,
+            current_version: None,
+            constants_file: PathBuf::from("chuni/chuni-info-verse.json"),
+            intl_levels_file: PathBuf::from("chuni/chuni-sun-lv.csv"),
+            intl_constants_file: PathBuf::from("chuni/chuni-sun-cst.csv"),
+            chart_viewer: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OngekiConfig {
+    /// Song list. Default: contents of `ongeki/ongeki-url.txt`.
+    pub url: Option<String>,
+    /// Deleted songs. Default: contents of `ongeki/ongeki-deleted.txt`.
+    pub deleted_url: Option<String>,
+    /// Chart constants. Default: contents of `ongeki/ongeki-info.txt`.
+    pub info_url: Option<String>,
+    /// Default: contents of `ongeki/ongeki-jacket-prefix.txt`.
+    pub jacket_prefix: Option<String>,
+    /// JP version that counts as current (for `--new` and the new-songs rating frame), by its
+    /// name in `versions.toml`. Default: the latest one released.
+    pub current_version: Option<String>,
+    /// Chart viewer link shown in detailed chart info, with `{title}`, `{type}` and
+    /// `{difficulty}` filled in. No link if unset.
+    pub chart_viewer: Option<String>,
+}
+
+/// How an environment variable is read.
+#[derive(Debug, Clone, Copy)]
+enum EnvKind {
+    Text,
+    Bool,
+    Integer,
+    /// `1,2` or `[1, 2]`.
+    Integers,
+}
+
+/// Every setting that can be set from the environment, as `section.key`.
+const ENV_SETTINGS: [(&str, EnvKind); 28] = [
+    ("data_dir", EnvKind::Text),
+    ("owners", EnvKind::Integers),
+    ("notify_owners", EnvKind::Bool),
+    ("timeouts.update_check_hours", EnvKind::Integer),
+    ("timeouts.announce_delay_secs", EnvKind::Integer),
+    ("maimai.jp_url", EnvKind::Text),
+    ("maimai.info_url", EnvKind::Text),
+    ("maimai.jacket_prefix", EnvKind::Text),
+    ("maimai.jp_and_intl_version_is_different", EnvKind::Bool),
+    ("maimai.current_version", EnvKind::Text),
+    ("maimai.constants_file", EnvKind::Text),
+    ("maimai.chart_viewer", EnvKind::Text),
+    ("chuni.jp_url", EnvKind::Text),
+    ("chuni.intl_url", EnvKind::Text),
+    ("chuni.info_url", EnvKind::Text),
+    ("chuni.jacket_prefix", EnvKind::Text),
+    ("chuni.jp_and_intl_version_is_different", EnvKind::Bool),
+    ("chuni.current_version", EnvKind::Text),
+    ("chuni.constants_file", EnvKind::Text),
+    ("chuni.intl_levels_file", EnvKind::Text),
+    ("chuni.intl_constants_file", EnvKind::Text),
+    ("chuni.chart_viewer", EnvKind::Text),
+    ("ongeki.url", EnvKind::Text),
+    ("ongeki.deleted_url", EnvKind::Text),
+    ("ongeki.info_url", EnvKind::Text),
+    ("ongeki.jacket_prefix", EnvKind::Text),
+    ("ongeki.current_version", EnvKind::Text),
+    ("ongeki.chart_viewer", EnvKind::Text),
+];
+
+/// `GCM_TIMEOUTS_UPDATE_CHECK_HOURS` for `timeouts.update_check_hours`.
+fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+/// The names of the environment variables that set something.
+pub fn env_names() -> Vec<String> {
+    ENV_SETTINGS.iter().map(|(key, _)| env_name(key)).collect()
+}
+
+/// Reads the value of the variable `name` as `kind`.
+fn env_value(name: &str, raw: &str, kind: EnvKind) -> eyre::Result<toml::Value> {
+    let integer = |s: &str| {
+        s.trim()
+            .parse::<i64>()
+            .wrap_err_with(|| format!("{} must be a whole number, got {:?}", name, raw))
+    };
+    Ok(match kind {
+        EnvKind::Text => toml::Value::String(raw.to_string()),
+        EnvKind::Bool => match raw.trim() {
+            "true" => toml::Value::Boolean(true),
+            "false" => toml::Value::Boolean(false),
+            _ => bail!("{} must be true or false, got {:?}", name, raw),
+        },
+        EnvKind::Integer => toml::Value::Integer(integer(raw)?),
+        EnvKind::Integers => {
+            let list = raw.trim().trim_start_matches('[').trim_end_matches(']');
+            let values = list
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| integer(s).map(toml::Value::Integer))
+                .collect::<eyre::Result<_>>()?;
+            toml::Value::Array(values)
+        }
+    })
+}
+
+/// Applies `GCM_*` variables from `vars` on top of `table`. Variables that don't name a
+/// setting are left alone, as other tools may use the prefix too.
+fn apply_env(
+    table: &mut toml::Table,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> eyre::Result<()> {
+    for (name, raw) in vars {
+        let Some(&(key, kind)) = ENV_SETTINGS.iter().find(|(key, _)| env_name(key) == name) else {
+            continue;
+        };
+        let value = env_value(&name, &raw, kind)?;
+        match key.split_once('.') {
+            Some((section, key)) => {
+                let inner = table
+                    .entry(section.to_string())
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+                let Some(inner) = inner.as_table_mut() else {
+                    bail!("[{}] in the config file is not a table", section);
+                };
+                inner.insert(key.to_string(), value);
+            }
+            None => {
+                table.insert(key.to_string(), value);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Reads `path` (if it exists) and applies the `GCM_*` environment variables.
+    /// `required` makes a missing file an error.
+    pub fn load(path: &Path, required: bool) -> eyre::Result<Self> {
+        let text = if path.exists() {
+            fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {:?}", path))?
+        } else if required {
+            bail!("Config file {:?} does not exist", path);
+        } else {
+            String::new()
+        };
+        Self::parse(&text, std::env::vars()).wrap_err_with(|| format!("Invalid config {:?}", path))
+    }
+
+    /// Parses the contents of a config file with `vars` as the environment.
+    pub fn parse(
+        text: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> eyre::Result<Self> {
+        let mut table = toml::from_str::<toml::Table>(text).wrap_err("Invalid TOML")?;
+        apply_env(&mut table, vars)?;
+        let config: Config = table
+            .try_into()
+            .wrap_err("Invalid settings in the file or GCM_* environment variables")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> eyre::Result<()> {
+        let t = &self.timeouts;
+        ensure!(
+            t.update_check_hours > 0,
+            "timeouts.update_check_hours must be positive"
+        );
+        Ok(())
+    }
+
+    /// Checks that the data directory and every source the loaders need are there,
+    /// so a bad deployment fails at startup rather than on first use.
+    pub fn check_sources(&self) -> eyre::Result<()> {
+        ensure!(
+            self.data_dir.is_dir(),
+            "data_dir {:?} is not a directory",
+            self.data_dir
+        );
+        let sources = [
+            (&self.maimai.jp_url, "maimai.jp_url", "maimai/maimai-jp.txt"),
+            (
+                &self.maimai.info_url,
+                "maimai.info_url",
+                "maimai/maimai-info.txt",
+            ),
+            (
+                &self.maimai.jacket_prefix,
+                "maimai.jacket_prefix",
+                "maimai/maimai-jacket-prefix.txt",
+            ),
+            (&self.chuni.jp_url, "chuni.jp_url", "chuni/chuni-url.txt"),
+            (
+                &self.chuni.intl_url,
+                "chuni.intl_url",
+                "chuni/chuni-intl.txt",
+            ),
+            (
+                &self.chuni.info_url,
+                "chuni.info_url",
+                "chuni/chuni-info.txt",
+            ),
+            (
+                &self.chuni.jacket_prefix,
+                "chuni.jacket_prefix",
+                "chuni/chuni-jacket-prefix.txt",
+            ),
+            (&self.ongeki.url, "ongeki.url", "ongeki/ongeki-url.txt"),
+            (
+                &self.ongeki.deleted_url,
+                "ongeki.deleted_url",
+                "ongeki/ongeki-deleted.txt",
+            ),
+            (
+                &self.ongeki.info_url,
+                "ongeki.info_url",
+                "ongeki/ongeki-info.txt",
+            ),
+            (
+                &self.ongeki.jacket_prefix,
+                "ongeki.jacket_prefix",
+                "ongeki/ongeki-jacket-prefix.txt",
+            ),
+        ];
+        for (configured, key, file) in sources {
+            let path = self.data_dir.join(file);
+            ensure!(
+                configured.is_some() || path.exists(),
+                "Set {} in the config, or put it in {:?}",
+                key,
+                path
+            );
+        }
+        for file in [&self.maimai.constants_file, &self.chuni.constants_file] {
+            let path = self.data_dir.join(file);
+            ensure!(path.exists(), "{:?} does not exist", path);
+        }
+        Ok(())
+    }
+
+    /// `data_dir`-relative path.
+    pub fn path(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.data_dir.join(path)
+    }
+
+    /// The configured `value`, or the contents of the data file it used to be read from.
+    pub fn source(&self, value: &Option<String>, file: &str) -> eyre::Result<String> {
+        match value {
+            Some(value) => Ok(value.clone()),
+            None => {
+                let path = self.path(file);
+                fs::read_to_string(&path).wrap_err_with(|| format!("Cannot read {:?}", path))
+            }
+        }
+    }
+
+    /// Jacket URL prefix for "maimai", "chuni" or "ongeki"; `None` for other games.
+    pub fn jacket_prefix(&self, game: &str) -> Option<eyre::Result<String>> {
+        let value = match game {
+            "maimai" => &self.maimai.jacket_prefix,
+            "chuni" => &self.chuni.jacket_prefix,
+            "ongeki" => &self.ongeki.jacket_prefix,
+            _ => return None,
+        };
+        Some(self.source(value, &format!("{0}/{0}-jacket-prefix.txt", game)))
+    }
+
+    /// Chart viewer URL template for "maimai", "chuni" or "ongeki", if set.
+    pub fn chart_viewer(&self, game: &str) -> Option<&str> {
+        match game {
+            "maimai" => self.maimai.chart_viewer.as_deref(),
+            "chuni" => self.chuni.chart_viewer.as_deref(),
+            "ongeki" => self.ongeki.chart_viewer.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Configured current JP version name for "maimai", "chuni" or "ongeki", if set.
+    pub fn current_version(&self, game: &str) -> Option<&str> {
+        match game {
+            "maimai" => self.maimai.current_version.as_deref(),
+            "chuni" => self.chuni.current_version.as_deref(),
+            "ongeki" => self.ongeki.current_version.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+fn config_path() -> (PathBuf, bool) {
+    match std::env::var_os("GCM_CONFIG") {
+        Some(path) => (PathBuf::from(path), true),
+        None => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+    }
+}
+
+/// Loads the config; call at startup to report errors before anything else happens.
+pub fn init() -> eyre::Result<&'static Config> {
+    if let Some(config) = CONFIG.get() {
+        return Ok(config);
+    }
+    let (path, required) = config_path();
+    let config = Config::load(&path, required)?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// The global config, loaded on first use.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        let (path, required) = config_path();
+        Config::load(&path, required).unwrap_or_else(|e| panic!("{:?}", e))
+    })
+}
+
+/// Shorthand for `config().path(path)`.
+pub fn data_path(path: impl AsRef<Path>) -> PathBuf {
+    config().path(path)
+}
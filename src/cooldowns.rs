@@ -0,0 +1,293 @@
+//! Per-guild rate limits on query commands: a cooldown per user and one per channel, with
+//! exempt channels (e.g. #bot-commands). Set with the admin commands below and kept in
+//! `data/cooldowns.json`; the last query times only live in memory, so a restart ends the
+//! cooldowns in progress.
+//!
+//! Query commands opt in with `check = "cooldown_check"`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use poise::serenity_prelude::{Channel, ChannelId, GuildId, UserId};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::data_path, settings::query_settings, utils::*};
+
+/// Relative to `data_dir`.
+pub const COOLDOWNS_FILE: &str = "cooldowns.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cooldown {
+    /// Seconds left for this user.
+    User(i64),
+    /// Seconds left for this channel.
+    Channel(i64),
+    None,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildCooldowns {
+    /// Seconds between queries of one user; 0 for no limit.
+    pub user_secs: i64,
+    /// Seconds between queries in one channel; 0 for no limit.
+    pub channel_secs: i64,
+    pub exempt_channels: BTreeSet<u64>,
+    /// Unix time of the last query of each user and in each channel.
+    #[serde(skip)]
+    last_user: BTreeMap<u64, i64>,
+    #[serde(skip)]
+    last_channel: BTreeMap<u64, i64>,
+}
+
+impl GuildCooldowns {
+    /// Forgets queries whose cooldowns are over.
+    fn prune(&mut self, now: i64) {
+        let (user_secs, channel_secs) = (self.user_secs, self.channel_secs);
+        self.last_user.retain(|_, then| now - *then < user_secs);
+        self.last_channel
+            .retain(|_, then| now - *then < channel_secs);
+    }
+}
+
+/// Cooldown settings and state of every guild that has any.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cooldowns {
+    guilds: BTreeMap<u64, GuildCooldowns>,
+}
+
+impl Cooldowns {
+    /// Empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn guild(&self, guild: GuildId) -> Option<&GuildCooldowns> {
+        self.guilds.get(&guild.0)
+    }
+
+    /// Sets both cooldowns of `guild`; 0 turns one off.
+    pub fn set(&mut self, guild: GuildId, user_secs: i64, channel_secs: i64) {
+        let settings = self.guilds.entry(guild.0).or_default();
+        settings.user_secs = user_secs.max(0);
+        settings.channel_secs = channel_secs.max(0);
+        if settings.user_secs == 0
+            && settings.channel_secs == 0
+            && settings.exempt_channels.is_empty()
+        {
+            self.guilds.remove(&guild.0);
+        }
+    }
+
+    /// Returns false if the channel already was (or wasn't) exempt.
+    pub fn set_exempt(&mut self, guild: GuildId, channel: ChannelId, exempt: bool) -> bool {
+        let settings = self.guilds.entry(guild.0).or_default();
+        if exempt {
+            settings.exempt_channels.insert(channel.0)
+        } else {
+            settings.exempt_channels.remove(&channel.0)
+        }
+    }
+
+    /// Whether queries in `channel` of `guild` are rate limited at all.
+    pub fn applies(&self, guild: GuildId, channel: ChannelId) -> bool {
+        self.guild(guild).is_some_and(|settings| {
+            (settings.user_secs > 0 || settings.channel_secs > 0)
+                && !settings.exempt_channels.contains(&channel.0)
+        })
+    }
+
+    /// Checks a query at unix time `now`, and records it if it may go ahead.
+    pub fn check(
+        &mut self,
+        guild: GuildId,
+        channel: ChannelId,
+        user: UserId,
+        now: i64,
+    ) -> Cooldown {
+        if !self.applies(guild, channel) {
+            return Cooldown::None;
+        }
+        let settings = self.guilds.get_mut(&guild.0).unwrap();
+        settings.prune(now);
+        if let Some(then) = settings.last_user.get(&user.0) {
+            return Cooldown::User(settings.user_secs - (now - then));
+        }
+        if let Some(then) = settings.last_channel.get(&channel.0) {
+            return Cooldown::Channel(settings.channel_secs - (now - then));
+        }
+        if settings.user_secs > 0 {
+            settings.last_user.insert(user.0, now);
+        }
+        if settings.channel_secs > 0 {
+            settings.last_channel.insert(channel.0, now);
+        }
+        Cooldown::None
+    }
+}
+
+/// Poise check for query commands: stops the command in channels where queries are turned
+/// off, and replies with the time left while the user or channel is on cooldown. Blocked
+/// queries don't start a cooldown.
+pub async fn cooldown_check(ctx: Context<'_>) -> Result<bool, Error> {
+    if query_settings(ctx).await?.is_none() {
+        return Ok(false);
+    }
+    let Some(guild) = ctx.guild_id() else {
+        return Ok(true);
+    };
+    let now = chrono::Utc::now().timestamp();
+    let cooldown =
+        ctx.data()
+            .cooldowns
+            .lock()
+            .await
+            .check(guild, ctx.channel_id(), ctx.author().id, now);
+    let is_slash_command = matches!(ctx, poise::Context::Application(_));
+    match cooldown {
+        Cooldown::None => return Ok(true),
+        Cooldown::Channel(t) => {
+            ctx.send(|f| {
+                f.ephemeral(is_slash_command).content(format!(
+                    "Channel cooldown: please wait {} seconds and try again, or try the #bot-commands channel for no cooldown.",
+                    t
+                ))
+            })
+            .await?;
+        }
+        // Prefix commands can't reply privately, so they don't answer to avoid spamming the channel.
+        Cooldown::User(t) if is_slash_command => {
+            ctx.send(|f| {
+                f.ephemeral(true).content(format!(
+                    "User cooldown: please wait {} seconds and try again, or try the #bot-commands channel for no cooldown.",
+                    t
+                ))
+            })
+            .await?;
+        }
+        Cooldown::User(_) => (),
+    }
+    Ok(false)
+}
+
+/// Set how often users and channels can query the bot in this server
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "set-cooldown",
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn set_cooldown(
+    ctx: Context<'_>,
+    #[description = "Seconds between queries of one user (0 for no limit)"]
+    #[min = 0]
+    user_seconds: i64,
+    #[description = "Seconds between queries in one channel (0 for no limit)"]
+    #[min = 0]
+    channel_seconds: i64,
+) -> Result<(), Error> {
+    let guild = ctx.guild_id().unwrap();
+    {
+        let mut cooldowns = ctx.data().cooldowns.lock().await;
+        cooldowns.set(guild, user_seconds, channel_seconds);
+        cooldowns.save(&data_path(COOLDOWNS_FILE))?;
+    }
+    let text = if user_seconds <= 0 && channel_seconds <= 0 {
+        "Cooldowns are off in this server.".to_string()
+    } else {
+        format!(
+            "Cooldowns set: {} seconds per user, {} seconds per channel.",
+            user_seconds.max(0),
+            channel_seconds.max(0)
+        )
+    };
+    ctx.send(|f| f.ephemeral(true).content(text)).await?;
+    Ok(())
+}
+
+async fn set_exempt(ctx: Context<'_>, channel: Option<Channel>, exempt: bool) -> Result<(), Error> {
+    let guild = ctx.guild_id().unwrap();
+    let channel = channel.map_or(ctx.channel_id(), |c| c.id());
+    let changed = {
+        let mut cooldowns = ctx.data().cooldowns.lock().await;
+        let changed = cooldowns.set_exempt(guild, channel, exempt);
+        cooldowns.save(&data_path(COOLDOWNS_FILE))?;
+        changed
+    };
+    let text = match (exempt, changed) {
+        (true, true) => format!("<#{}> has no cooldowns now.", channel),
+        (true, false) => format!("<#{}> is already exempt from cooldowns.", channel),
+        (false, true) => format!("<#{}> has cooldowns again.", channel),
+        (false, false) => format!("<#{}> is not exempt from cooldowns.", channel),
+    };
+    ctx.send(|f| f.ephemeral(true).content(text)).await?;
+    Ok(())
+}
+
+/// Turn cooldowns off for a channel
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "exempt-channel",
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn exempt_channel(
+    ctx: Context<'_>,
+    #[description = "The channel (default: this one)"] channel: Option<Channel>,
+) -> Result<(), Error> {
+    set_exempt(ctx, channel, true).await
+}
+
+/// Turn cooldowns back on for a channel
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "unexempt-channel",
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn unexempt_channel(
+    ctx: Context<'_>,
+    #[description = "The channel (default: this one)"] channel: Option<Channel>,
+) -> Result<(), Error> {
+    set_exempt(ctx, channel, false).await
+}
+
+/// Show the cooldown settings of this server
+#[poise::command(slash_command, prefix_command, guild_only)]
+pub async fn cooldowns(ctx: Context<'_>) -> Result<(), Error> {
+    let guild = ctx.guild_id().unwrap();
+    let text = match ctx.data().cooldowns.lock().await.guild(guild) {
+        Some(settings) if settings.user_secs > 0 || settings.channel_secs > 0 => {
+            let mut text = format!(
+                "{} seconds per user, {} seconds per channel.",
+                settings.user_secs, settings.channel_secs
+            );
+            if !settings.exempt_channels.is_empty() {
+                let channels = settings
+                    .exempt_channels
+                    .iter()
+                    .map(|c| format!("<#{}>", c))
+                    .collect::<Vec<_>>();
+                text += &format!("\nNo cooldowns in {}.", channels.join(", "));
+            }
+            text
+        }
+        _ => "No cooldowns in this server.".to_string(),
+    };
+    ctx.send(|f| f.ephemeral(true).content(text)).await?;
+    Ok(())
+}
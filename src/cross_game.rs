@@ -0,0 +1,204 @@
+//! `/info` without a game: one song looked up in maimai, CHUNITHM and O.N.G.E.K.I. at once,
+//! with the levels of every game that has it in a single embed.
+
+use std::io::Write as _;
+
+use poise::serenity_prelude::{CreateActionRow, CreateButton, GuildId};
+
+use crate::{
+    catalog::*,
+    components::{Action, ComponentId},
+    document::{Document, Field, FIELD_LIMIT},
+    preferences::render_options,
+    settings::query_settings,
+    utils::*,
+};
+
+/// A song found in one game.
+pub struct GameMatch<'a> {
+    pub catalog: &'a dyn GameCatalog,
+    /// The key of the song in `catalog`.
+    pub title: String,
+    /// Only the closest title, as no game knew the query.
+    pub closest: bool,
+}
+
+/// Where `query` is in each of `catalogs`, in their order. A song found in some games is also
+/// looked up in the others by its title, so an alias known to one game finds the song in every
+/// game that has it. If no game knows the query, each game gets its closest title instead.
+pub fn find_song<'a>(
+    catalogs: &[&'a dyn GameCatalog],
+    query: &str,
+    server_id: GuildId,
+) -> Vec<GameMatch<'a>> {
+    let found = catalogs
+        .iter()
+        .map(|catalog| catalog.lookup(query, server_id))
+        .collect::<Vec<_>>();
+    if found.iter().all(Option::is_none) {
+        return catalogs
+            .iter()
+            .map(|&catalog| GameMatch {
+                catalog,
+                title: get_closest_title(query, catalog.aliases(), server_id).1,
+                closest: true,
+            })
+            .collect();
+    }
+    let titles = catalogs
+        .iter()
+        .zip(&found)
+        .filter_map(|(catalog, title)| Some(catalog.display_title(title.as_ref()?)))
+        .collect::<Vec<_>>();
+    catalogs
+        .iter()
+        .zip(found)
+        .filter_map(|(&catalog, title)| {
+            let title = title.or_else(|| {
+                titles
+                    .iter()
+                    .find_map(|title| catalog.lookup(title, server_id))
+            })?;
+            Some(GameMatch {
+                catalog,
+                title,
+                closest: false,
+            })
+        })
+        .collect()
+}
+
+/// The levels of one game, shortened to fit in a field.
+fn levels(catalog: &dyn GameCatalog, title: &str, options: &RenderOptions) -> eyre::Result<String> {
+    let options = RenderOptions {
+        compact: true,
+        ..*options
+    };
+    let (mut levels, _) = catalog.render(title, &options)?;
+    if levels.len() > FIELD_LIMIT {
+        let options = RenderOptions {
+            links: false,
+            ..options
+        };
+        levels = catalog.render(title, &options)?.0;
+    }
+    if levels.len() > FIELD_LIMIT {
+        let mut end = FIELD_LIMIT - '…'.len_utf8();
+        while !levels.is_char_boundary(end) {
+            end -= 1;
+        }
+        levels.truncate(end);
+        levels.push('…');
+    }
+    Ok(levels)
+}
+
+/// One field per game, named after the game (and the song if its title differs there).
+pub fn cross_game_document(
+    matches: &[GameMatch],
+    query: &str,
+    options: &RenderOptions,
+) -> eyre::Result<Document> {
+    let Some(first) = matches.first() else {
+        eyre::bail!("No games to look {query} up in");
+    };
+    let title = first.catalog.display_title(&first.title);
+    let mut fields = vec![];
+    for game in matches {
+        let game_title = game.catalog.display_title(&game.title);
+        let mut name = game.catalog.name().to_string();
+        if game_title != title {
+            name = format!("{}: {}", name, game_title);
+        }
+        if game.closest {
+            name.push_str(" (closest match)");
+        }
+        fields.push(Field {
+            name,
+            value: levels(game.catalog, &game.title, options)?,
+            inline: false,
+        });
+    }
+    let description = if first.closest {
+        format!(
+            "I couldn't find **{}** in any game; here are the closest titles of each.",
+            query
+        )
+    } else {
+        String::new()
+    };
+    Ok(Document {
+        title,
+        description,
+        fields,
+        thumbnail: matches
+            .iter()
+            .find_map(|game| game.catalog.jacket_url(&game.title)),
+        color: first.catalog.color(),
+    })
+}
+
+/// `/info` without a game and no server default: the song in every game, as one embed with
+/// a jacket button per game, or a chart card per game with `card`.
+pub async fn cross_game_template(ctx: Context<'_>, title: String, card: bool) -> eyre::Result<()> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let options = render_options(ctx, &settings).await;
+    let data = ctx.data();
+    let catalogs = [Game::Maimai, Game::Chunithm, Game::Ongeki].map(|game| data.catalog(game));
+    let server_id = ctx.guild_id().unwrap_or(GuildId(0));
+    let matches = find_song(&catalogs, &title, server_id);
+    if matches.iter().any(|game| game.closest) {
+        let mut log = data.alias_log.lock().await;
+        for game in &matches {
+            writeln!(log, "{}\t{}", title, game.catalog.name())?;
+        }
+        log.sync_all()?;
+    }
+
+    if card {
+        let mut attachments = vec![];
+        for game in &matches {
+            attachments.push(card_attachment(data, game.catalog, &game.title, &options).await?);
+        }
+        ctx.send(|f| {
+            f.ephemeral(settings.ephemeral);
+            for attachment in attachments {
+                f.attachment(attachment);
+            }
+            f
+        })
+        .await?;
+        return Ok(());
+    }
+    let document = cross_game_document(&matches, &title, &options)?;
+    let buttons = matches
+        .iter()
+        .filter(|game| game.catalog.jacket_url(&game.title).is_some())
+        .map(|game| {
+            let id = ComponentId::new(Action::Jacket, game.catalog.id(), &game.title);
+            let mut button = CreateButton::default();
+            button
+                .custom_id(id.to_string())
+                .label(format!("{} jacket", game.catalog.name()));
+            button
+        })
+        .collect::<Vec<_>>();
+
+    ctx.send(|f| {
+        f.ephemeral(settings.ephemeral).embed(|f| document.embed(f));
+        if !buttons.is_empty() {
+            f.components(|c| {
+                let mut ar = CreateActionRow::default();
+                for button in buttons {
+                    ar.add_button(button);
+                }
+                c.set_action_row(ar)
+            });
+        }
+        f
+    })
+    .await?;
+    Ok(())
+}
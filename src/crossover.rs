@@ -0,0 +1,277 @@
+//! Songs in more than one of maimai, CHUNITHM and O.N.G.E.K.I., for `/crossover`, `gcm crossover`
+//! and the namu-wiki "grand slam" tables.
+//!
+//! Songs are matched by their key in each game's chart table. `data/crossover.toml` lists the
+//! exceptions the data can't tell: titles shared by different songs, originals missing from
+//! another game's data and the wiki page names of songs whose title is ambiguous on the wiki.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use eyre::WrapErr;
+use itertools::Itertools;
+use poise::serenity_prelude::AttachmentType;
+use serde::Deserialize;
+
+use crate::{
+    cooldowns::cooldown_check, document::DESCRIPTION_LIMIT, settings::query_settings, utils::*,
+};
+
+/// Relative to `data_dir`.
+pub const CROSSOVER_FILE: &str = "crossover.toml";
+/// Titles per row of the wiki table.
+const WIKI_COLUMNS: usize = 4;
+
+/// The exceptions in `data/crossover.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CrossoverData {
+    /// Titles used by different songs in different games.
+    pub exclude: BTreeSet<String>,
+    /// Originals of a game (by game id) that count as crossovers although another game's data
+    /// lacks them.
+    pub extra: BTreeMap<String, Vec<String>>,
+    /// Wiki page names of songs whose title leads elsewhere on the wiki.
+    pub wiki_pages: BTreeMap<String, String>,
+}
+
+impl CrossoverData {
+    /// Empty if the file doesn't exist.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {:?}", path))?;
+        toml::from_str(&text).wrap_err_with(|| format!("Invalid crossover list {:?}", path))
+    }
+}
+
+/// Which games a crossover has to be in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum GameSet {
+    #[default]
+    #[name = "All three games"]
+    All,
+    #[name = "maimai and CHUNITHM"]
+    MaimaiChunithm,
+    #[name = "maimai and O.N.G.E.K.I."]
+    MaimaiOngeki,
+    #[name = "CHUNITHM and O.N.G.E.K.I."]
+    ChunithmOngeki,
+}
+
+impl GameSet {
+    pub fn games(self) -> Vec<Game> {
+        match self {
+            GameSet::All => vec![Game::Maimai, Game::Chunithm, Game::Ongeki],
+            GameSet::MaimaiChunithm => vec![Game::Maimai, Game::Chunithm],
+            GameSet::MaimaiOngeki => vec![Game::Maimai, Game::Ongeki],
+            GameSet::ChunithmOngeki => vec![Game::Chunithm, Game::Ongeki],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossoverFilter {
+    /// Songs have to be in all of these.
+    pub games: Vec<Game>,
+    /// Only songs that are originals of this game.
+    pub origin: Option<Game>,
+}
+
+impl Default for CrossoverFilter {
+    fn default() -> Self {
+        Self {
+            games: GameSet::All.games(),
+            origin: None,
+        }
+    }
+}
+
+/// The chart tables of the three games.
+#[derive(Clone, Copy)]
+pub struct Charts<'a> {
+    pub maimai: &'a HashMap<String, MaiInfo>,
+    pub chuni: &'a HashMap<String, ChuniInfo>,
+    pub ongeki: &'a HashMap<String, OngekiInfo>,
+}
+
+impl<'a> Charts<'a> {
+    pub fn from_data(data: &'a Data) -> Self {
+        Self {
+            maimai: &data.maimai.charts,
+            chuni: &data.chuni.charts,
+            ongeki: &data.ongeki.charts,
+        }
+    }
+
+    fn titles(self, game: Game) -> Box<dyn Iterator<Item = &'a String> + 'a> {
+        match game {
+            Game::Maimai => Box::new(self.maimai.keys()),
+            Game::Chunithm => Box::new(self.chuni.keys()),
+            Game::Ongeki => Box::new(self.ongeki.keys()),
+        }
+    }
+
+    fn contains(self, game: Game, title: &str) -> bool {
+        match game {
+            Game::Maimai => self.maimai.contains_key(title),
+            Game::Chunithm => self.chuni.contains_key(title),
+            Game::Ongeki => self.ongeki.contains_key(title),
+        }
+    }
+
+    /// Whether the song is in the game's own category: maimai, CHUNITHM ORIGINAL or Irodori,
+    /// or オンゲキ.
+    pub fn is_original(self, game: Game, title: &str) -> bool {
+        match game {
+            Game::Maimai => self
+                .maimai
+                .get(title)
+                .is_some_and(|song| song.category == MaiCategory::Maimai),
+            Game::Chunithm => self.chuni.get(title).is_some_and(|song| {
+                matches!(
+                    song.category,
+                    ChuniCategory::Original | ChuniCategory::Irodori
+                )
+            }),
+            Game::Ongeki => self
+                .ongeki
+                .get(title)
+                .is_some_and(|song| song.category == OngekiCategory::Ongeki),
+        }
+    }
+
+    /// Kana readings sort like the in-game lists; only maimai has them.
+    fn sort_key<'t>(self, title: &'t str) -> &'t str
+    where
+        'a: 't,
+    {
+        self.maimai
+            .get(title)
+            .map_or(title, |song| song.title_kana.as_str())
+    }
+}
+
+/// Keys of the songs matching `filter`, in maimai's kana order.
+pub fn crossover_songs(
+    charts: Charts,
+    data: &CrossoverData,
+    filter: &CrossoverFilter,
+) -> Vec<String> {
+    let Some(&first) = filter.games.first() else {
+        return vec![];
+    };
+    let mut titles = charts
+        .titles(first)
+        .filter(|title| !data.exclude.contains(*title))
+        .filter(|title| {
+            filter
+                .games
+                .iter()
+                .all(|&game| charts.contains(game, title))
+        })
+        .filter(|title| {
+            filter
+                .origin
+                .is_none_or(|origin| charts.is_original(origin, title))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    for (game, extra) in &data.extra {
+        let counts = filter.games.iter().any(|g| g.id() == game)
+            && filter.origin.is_none_or(|origin| origin.id() == game);
+        if counts {
+            titles.extend(extra.iter().cloned());
+        }
+    }
+    titles.sort_by(|a, b| charts.sort_key(a).cmp(charts.sort_key(b)).then(a.cmp(b)));
+    titles.dedup();
+    titles
+}
+
+/// A namu-wiki table of links to `titles`, four to a row.
+pub fn wiki_table(titles: &[String], data: &CrossoverData) -> String {
+    let link = |title: &String| match data.wiki_pages.get(title) {
+        Some(page) => format!("[[{}|{}]]", page, title),
+        None => format!("[[{}]]", title),
+    };
+    titles
+        .chunks(WIKI_COLUMNS)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let cells = chunk
+                .iter()
+                .map(|title| {
+                    if idx == 0 {
+                        format!("<width=25%> {} ", link(title))
+                    } else {
+                        format!(" {} ", link(title))
+                    }
+                })
+                .join("||");
+            format!("||{}||\n", cells)
+        })
+        .collect()
+}
+
+/// List songs that are in several of maimai, CHUNITHM and O.N.G.E.K.I.
+#[poise::command(slash_command, prefix_command, check = "cooldown_check")]
+pub async fn crossover(
+    ctx: Context<'_>,
+    #[description = "Games the songs have to be in (default: all three)"] games: Option<GameSet>,
+    #[description = "Only originals of this game"] origin: Option<Game>,
+    #[description = "Also attach the list as a namu-wiki table"]
+    #[flag]
+    wiki: bool,
+) -> Result<(), Error> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let games = games.unwrap_or_default();
+    let filter = CrossoverFilter {
+        games: games.games(),
+        origin,
+    };
+    let data = ctx.data();
+    let titles = crossover_songs(Charts::from_data(data), &data.crossover, &filter);
+    let catalog = data.catalog(filter.games[0]);
+    let names = titles
+        .iter()
+        .map(|title| catalog.display_title(title))
+        .collect::<Vec<_>>();
+
+    let mut heading = format!("{}: {} songs", games, titles.len());
+    if let Some(origin) = origin {
+        heading.push_str(&format!(" originally from {}", origin));
+    }
+    let mut description = names.join(" / ");
+    let mut attachments = vec![];
+    if description.len() > DESCRIPTION_LIMIT {
+        description = "The list is too long for a message; see the attached file.".to_string();
+        attachments.push(AttachmentType::Bytes {
+            data: names.join("\n").into_bytes().into(),
+            filename: "crossover.txt".to_string(),
+        });
+    }
+    if wiki {
+        attachments.push(AttachmentType::Bytes {
+            data: wiki_table(&titles, &data.crossover).into_bytes().into(),
+            filename: "crossover-wiki.txt".to_string(),
+        });
+    }
+
+    ctx.send(|f| {
+        f.ephemeral(settings.ephemeral)
+            .embed(|e| e.title(heading).description(description));
+        for attachment in attachments {
+            f.attachment(attachment);
+        }
+        f
+    })
+    .await?;
+    Ok(())
+}
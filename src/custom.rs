@@ -0,0 +1,552 @@
+//! Catalogues for games without a hand-written module.
+//!
+//! Each `data/custom/*.json` file describes one game: where its song list is,
+//! which fields hold what, and how it should be presented. The song list itself
+//! is read through a [`DataSource`], so adding a format only needs a new adapter.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use eyre::{bail, eyre, WrapErr};
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+
+use crate::{catalog::*, config::data_path, cooldowns::cooldown_check, document::Field, utils::*};
+
+/// Relative to `data_dir`.
+pub const CUSTOM_GAME_DIR: &str = "custom";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomGameConfig {
+    /// Used for command names (`{id}-info`) and alias files (`data/aliases/*/{id}.tsv`).
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_color")]
+    pub color: (u8, u8, u8),
+    #[serde(default)]
+    pub jacket_prefix: String,
+    pub difficulty_names: Vec<String>,
+    pub source: SourceConfig,
+    pub fields: FieldMapping,
+}
+
+fn default_color() -> (u8, u8, u8) {
+    (255, 255, 255)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum SourceConfig {
+    /// An array of objects, or an object holding that array under `list_key`.
+    Json {
+        path: String,
+        list_key: Option<String>,
+    },
+    /// A header row followed by one song per row.
+    Csv {
+        path: String,
+        delimiter: Option<char>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldMapping {
+    pub title: String,
+    pub artist: Option<String>,
+    pub version: Option<String>,
+    pub jacket: Option<String>,
+    /// Truthy values ("true", "1", "yes") mark the song as deleted.
+    pub deleted: Option<String>,
+    /// One field per entry of `difficulty_names`; an empty value means the chart doesn't exist.
+    pub levels: Vec<String>,
+    #[serde(default)]
+    pub constants: Vec<String>,
+    /// Extra `**label:** value` lines shown in the info embed, in order.
+    #[serde(default)]
+    pub extra: Vec<ExtraField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtraField {
+    pub label: String,
+    pub field: String,
+}
+
+/// One song as field name -> value.
+pub type Record = HashMap<String, String>;
+
+pub trait DataSource {
+    fn records(&self) -> eyre::Result<Vec<Record>>;
+}
+
+pub struct JsonSource {
+    pub path: PathBuf,
+    pub list_key: Option<String>,
+}
+
+impl DataSource for JsonSource {
+    fn records(&self) -> eyre::Result<Vec<Record>> {
+        let file = File::open(&self.path).wrap_err_with(|| format!("{:?}", self.path))?;
+        let json: serde_json::Value = serde_json::from_reader(file)?;
+        let list = match &self.list_key {
+            Some(key) => &json[key],
+            None => &json,
+        };
+        let Some(list) = list.as_array() else {
+            bail!("{:?} does not contain a list of songs", self.path);
+        };
+
+        let mut records = vec![];
+        for song in list {
+            let Some(song) = song.as_object() else {
+                bail!("Non-object song entry {song} in {:?}", self.path);
+            };
+            let record = song
+                .iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        serde_json::Value::Null => "".to_string(),
+                        serde_json::Value::String(s) => s.clone(),
+                        v => v.to_string(),
+                    };
+                    (k.clone(), v)
+                })
+                .collect();
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+pub struct CsvSource {
+    pub path: PathBuf,
+    pub delimiter: u8,
+}
+
+impl DataSource for CsvSource {
+    fn records(&self) -> eyre::Result<Vec<Record>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .from_path(&self.path)
+            .wrap_err_with(|| format!("{:?}", self.path))?;
+        let headers = reader.headers()?.clone();
+        let mut records = vec![];
+        for row in reader.records() {
+            let row = row?;
+            records.push(
+                headers
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+        }
+        Ok(records)
+    }
+}
+
+impl SourceConfig {
+    /// Paths in the config are relative to `base`.
+    pub fn data_source(&self, base: &Path) -> eyre::Result<Box<dyn DataSource>> {
+        Ok(match self {
+            SourceConfig::Json { path, list_key } => Box::new(JsonSource {
+                path: base.join(path),
+                list_key: list_key.clone(),
+            }),
+            SourceConfig::Csv { path, delimiter } => {
+                let delimiter = delimiter.unwrap_or(',');
+                if !delimiter.is_ascii() {
+                    bail!("CSV delimiter must be ASCII, got {delimiter:?}");
+                }
+                Box::new(CsvSource {
+                    path: base.join(path),
+                    delimiter: delimiter as u8,
+                })
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CustomInfo {
+    pub title: String,
+    pub artist: String,
+    pub version: Option<String>,
+    pub jp_jacket: Option<String>,
+    pub deleted: bool,
+    /// Indexed like `difficulty_names`; `None` if the chart doesn't exist.
+    pub levels: Vec<Option<(String, Option<OrderedFloat<f32>>)>>,
+    pub extra: Vec<(String, String)>,
+}
+
+impl SongInfo for CustomInfo {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn jp_jacket(&self) -> Option<&str> {
+        self.jp_jacket.as_deref()
+    }
+
+    fn version(&self) -> Option<String> {
+        self.version.clone()
+    }
+
+    fn chart_entries(&self) -> Vec<ChartEntry> {
+        self.levels
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, lv)| {
+                let (level, constant) = lv.as_ref()?;
+                Some(ChartEntry {
+                    // Custom games have no regional split.
+                    region: Region::Jp,
+                    chart_type: None,
+                    difficulty: idx,
+                    level: level.clone(),
+                    constant: *constant,
+                    version: None,
+                })
+            })
+            .collect()
+    }
+}
+
+impl FieldMapping {
+    fn to_info(&self, record: &Record) -> eyre::Result<CustomInfo> {
+        let get = |field: &Option<String>| {
+            field
+                .as_ref()
+                .and_then(|f| record.get(f))
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let Some(title) = record.get(&self.title).filter(|t| !t.is_empty()) else {
+            bail!(
+                "Missing title field `{}` on record {:?}",
+                self.title,
+                record
+            );
+        };
+        let levels = self
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                let level = record.get(field).map(|l| l.trim()).unwrap_or_default();
+                if level.is_empty() {
+                    return None;
+                }
+                let constant = self
+                    .constants
+                    .get(idx)
+                    .and_then(|f| record.get(f))
+                    .and_then(|c| c.trim().parse::<f32>().ok())
+                    .map(OrderedFloat);
+                Some((level.to_string(), constant))
+            })
+            .collect();
+
+        Ok(CustomInfo {
+            title: title.clone(),
+            artist: get(&self.artist).unwrap_or_default(),
+            version: get(&self.version),
+            jp_jacket: get(&self.jacket),
+            deleted: get(&self.deleted)
+                .is_some_and(|d| ["true", "1", "yes"].contains(&d.to_lowercase().as_str())),
+            levels,
+            extra: self
+                .extra
+                .iter()
+                .filter_map(|e| {
+                    let value = record.get(&e.field)?.trim();
+                    (!value.is_empty()).then(|| (e.label.clone(), value.to_string()))
+                })
+                .collect(),
+        })
+    }
+}
+
+pub struct CustomCatalog {
+    pub config: CustomGameConfig,
+    pub charts: HashMap<String, CustomInfo>,
+    pub aliases: Aliases,
+}
+
+impl GameCatalog for CustomCatalog {
+    fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn color(&self) -> (u8, u8, u8) {
+        self.config.color
+    }
+
+    fn difficulty_names(&self) -> Vec<&str> {
+        self.config
+            .difficulty_names
+            .iter()
+            .map(|s| s.as_str())
+            .collect()
+    }
+
+    fn aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    fn jacket_prefix(&self) -> &str {
+        &self.config.jacket_prefix
+    }
+
+    fn song(&self, title: &str) -> Option<&dyn SongInfo> {
+        self.charts.get(title).map(|song| song as &dyn SongInfo)
+    }
+
+    fn songs(&self) -> Box<dyn Iterator<Item = (&String, &dyn SongInfo)> + '_> {
+        Box::new(
+            self.charts
+                .iter()
+                .map(|(title, song)| (title, song as &dyn SongInfo)),
+        )
+    }
+
+    fn display_title(&self, title: &str) -> String {
+        title.to_string()
+    }
+
+    fn song_json(&self, title: &str) -> Option<serde_json::Value> {
+        to_json_value(self.charts.get(title)?)
+    }
+
+    /// Custom games have no regional split or video links, so only `constants` and `compact`
+    /// apply.
+    fn render_fields(
+        &self,
+        title: &str,
+        options: &RenderOptions,
+    ) -> eyre::Result<(String, Vec<Field>, Option<String>)> {
+        let Some(song) = self.charts.get(title) else {
+            bail!("No data for {title}");
+        };
+
+        let mut description = if song.deleted {
+            "**THIS SONG IS DELETED**\n\n"
+        } else {
+            ""
+        }
+        .to_string();
+        if !options.compact {
+            description = format!(
+                "{}**Artist:** {}",
+                description,
+                song.artist.replace('*', "\\*")
+            );
+            if let Some(version) = &song.version {
+                description = format!("{}\n**Version:** {}", description, version);
+            }
+            for (label, value) in &song.extra {
+                description = format!("{}\n**{}:** {}", description, label, value);
+            }
+        }
+
+        let levels = song
+            .levels
+            .iter()
+            .zip(self.config.difficulty_names.iter())
+            .filter_map(|(lv, name)| {
+                let (level, constant) = lv.as_ref()?;
+                Some(format!(
+                    "{} **{}**{}",
+                    name,
+                    level,
+                    if options.constants {
+                        constant_to_string(*constant)
+                    } else {
+                        "".to_string()
+                    }
+                ))
+            })
+            .join(" / ");
+        let mut fields = vec![];
+        if !levels.is_empty() {
+            fields.push(Field::new("Level", levels));
+        }
+
+        Ok((
+            description.trim().to_string(),
+            fields,
+            song.jp_jacket.clone(),
+        ))
+    }
+}
+
+/// Discord's limit on command names, which have to fit `{id}-jacket`.
+const COMMAND_NAME_LIMIT: usize = 32;
+
+/// Fails unless `id` can name commands, alias files and button ids without clashing with
+/// a built-in game.
+fn check_id(id: &str) -> eyre::Result<()> {
+    let builtin = [Game::Maimai, Game::Chunithm, Game::Ongeki];
+    if builtin.iter().any(|game| game.id() == id) {
+        bail!("custom game id {id:?} is taken by a built-in game");
+    }
+    if id.is_empty() {
+        bail!("custom game id is empty");
+    }
+    // Also keeps out `:` (button ids) and `/` and `.` (alias file paths).
+    if let Some(c) = id
+        .chars()
+        .find(|&c| !(c == '-' || c == '_' || c.is_alphanumeric()) || c.is_uppercase())
+    {
+        bail!("custom game id {id:?} has {c:?}; use lowercase letters, digits, - and _");
+    }
+    let longest = format!("{id}-jacket");
+    if longest.chars().count() > COMMAND_NAME_LIMIT {
+        bail!("custom game id {id:?} is too long for the command name {longest}");
+    }
+    Ok(())
+}
+
+/// Reads every `*.json` config under `dir`, sorted by file name.
+pub fn load_custom_configs(dir: &Path) -> eyre::Result<Vec<CustomGameConfig>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        // No custom games configured.
+        return Ok(vec![]);
+    };
+    let mut paths = entries
+        .map_while(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect_vec();
+    paths.sort();
+
+    let mut configs: Vec<CustomGameConfig> = vec![];
+    for path in paths {
+        let file = File::open(&path)?;
+        let config: CustomGameConfig = serde_json::from_reader(file)
+            .wrap_err_with(|| format!("Invalid custom game config {:?}", path))?;
+        check_id(&config.id).wrap_err_with(|| format!("{:?}", path))?;
+        if config.fields.levels.len() != config.difficulty_names.len() {
+            bail!(
+                "{:?}: {} level fields for {} difficulty names",
+                path,
+                config.fields.levels.len(),
+                config.difficulty_names.len()
+            );
+        }
+        if configs.iter().any(|c| c.id == config.id) {
+            bail!("{:?}: duplicate custom game id {}", path, config.id);
+        }
+        configs.push(config);
+    }
+    Ok(configs)
+}
+
+pub fn set_custom_charts(
+    config: &CustomGameConfig,
+    base: &Path,
+) -> eyre::Result<HashMap<String, CustomInfo>> {
+    let records = config.source.data_source(base)?.records()?;
+    let mut charts = HashMap::new();
+    for record in records {
+        let info = config.fields.to_info(&record)?;
+        if let Some(old) = charts.insert(info.title.clone(), info) {
+            eprintln!(
+                "Duplicate title {} in custom game {}; keeping the later entry",
+                old.title, config.id
+            );
+        }
+    }
+    Ok(charts)
+}
+
+pub fn set_custom_catalog(config: CustomGameConfig) -> Result<CustomCatalog, Error> {
+    let charts = set_custom_charts(&config, &data_path(CUSTOM_GAME_DIR))?;
+    // `set_aliases` reads the `add-alias` submissions file, so make sure it exists.
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_path(format!("aliases/manual/{}.tsv", config.id)))?;
+    let aliases = set_aliases(charts.keys(), &config.id)?;
+    Ok(CustomCatalog {
+        config,
+        charts,
+        aliases,
+    })
+}
+
+fn custom_catalog<'a>(ctx: &Context<'a>) -> eyre::Result<&'a CustomCatalog> {
+    let id = ctx
+        .command()
+        .custom_data
+        .downcast_ref::<String>()
+        .ok_or_else(|| eyre!("custom game command without a game id"))?;
+    ctx.data()
+        .custom
+        .get(id)
+        .ok_or_else(|| eyre!("custom game {id} is not loaded"))
+}
+
+/// Get song info
+#[poise::command(slash_command, prefix_command, check = "cooldown_check")]
+pub async fn custom_info(
+    ctx: Context<'_>,
+    #[description = "Show a chart card image instead of text"]
+    #[flag]
+    card: bool,
+    #[description = "Song title. You don't have to be exact; try things out!"]
+    #[rest]
+    title: String,
+) -> Result<(), Error> {
+    info_template(ctx, custom_catalog(&ctx)?, title, card).await?;
+    Ok(())
+}
+
+/// Get song jacket
+#[poise::command(slash_command, prefix_command, check = "cooldown_check")]
+pub async fn custom_jacket(
+    ctx: Context<'_>,
+    #[description = "Song title. You don't have to be exact; try things out!"]
+    #[rest]
+    title: String,
+) -> Result<(), Error> {
+    jacket_template(ctx, custom_catalog(&ctx)?, title).await?;
+    Ok(())
+}
+
+/// `{id}-info` and `{id}-jacket` commands for a custom game.
+pub fn custom_commands(config: &CustomGameConfig) -> Vec<poise::Command<Arc<Data>, Error>> {
+    [
+        (custom_info(), "info", "song info"),
+        (custom_jacket(), "jacket", "song jacket"),
+    ]
+    .into_iter()
+    .map(|(mut command, suffix, what)| {
+        let name = format!("{}-{}", config.id, suffix);
+        command.name = name.clone();
+        command.qualified_name = name.clone();
+        command.identifying_name = name;
+        command.description = Some(format!("Get {} {}", config.name, what));
+        command.custom_data = Box::new(config.id.clone());
+        command
+    })
+    .collect()
+}
@@ -0,0 +1,144 @@
+//! Renderer-neutral song info, so the same output can go to Discord, a terminal or a file.
+
+use poise::serenity_prelude::{Color, CreateEmbed};
+use serde::Serialize;
+
+/// Discord's limits on an embed's description and on one field's value, in bytes.
+pub const DESCRIPTION_LIMIT: usize = 4096;
+pub const FIELD_LIMIT: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Field {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+impl Field {
+    /// A field on its own line.
+    pub fn new(name: impl Into<String>, value: String) -> Self {
+        Self {
+            name: name.into(),
+            value,
+            inline: false,
+        }
+    }
+}
+
+/// What the bot shows for a song. `description` and field values use Discord markdown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Document {
+    pub title: String,
+    pub description: String,
+    pub fields: Vec<Field>,
+    /// Full jacket URL.
+    pub thumbnail: Option<String>,
+    pub color: (u8, u8, u8),
+}
+
+impl Document {
+    /// Fills a Discord embed.
+    pub fn embed<'a>(&self, f: &'a mut CreateEmbed) -> &'a mut CreateEmbed {
+        let mut f = f
+            .title(self.title.replace('*', "\\*"))
+            .description(&self.description)
+            .color(Color::from_rgb(self.color.0, self.color.1, self.color.2));
+        for field in &self.fields {
+            f = f.field(&field.name, &field.value, field.inline);
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            f = f.thumbnail(thumbnail);
+        }
+        f
+    }
+
+    /// Markdown as Discord would show it, with emoji shortcodes spelled out.
+    pub fn to_markdown(&self) -> String {
+        let mut s = format!(
+            "## {}\n\n{}\n",
+            self.title,
+            replace_emojis(&self.description)
+        );
+        for field in &self.fields {
+            s.push_str(&format!(
+                "\n**{}**\n{}\n",
+                field.name,
+                replace_emojis(&field.value)
+            ));
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            s.push_str(&format!("\n![jacket]({})\n", thumbnail));
+        }
+        s
+    }
+
+    /// Plain text for terminals.
+    pub fn to_text(&self) -> String {
+        let mut s = format!("{}\n\n{}\n", self.title, strip_markdown(&self.description));
+        for field in &self.fields {
+            s.push_str(&format!(
+                "\n{}\n{}\n",
+                field.name,
+                strip_markdown(&field.value)
+            ));
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            s.push_str(&format!("\nJacket: {}\n", thumbnail));
+        }
+        s
+    }
+}
+
+const EMOJIS: [(&str, &str); 11] = [
+    (":flag_jp::globe_with_meridians:", "JP/INTL"),
+    (":flag_jp:", "JP"),
+    (":globe_with_meridians:", "INTL"),
+    (":green_square:", "BAS"),
+    (":yellow_square:", "ADV"),
+    (":red_square:", "EXP"),
+    (":purple_square:", "MAS"),
+    (":white_large_square:", "REM"),
+    (":fire:", "FIRE"),
+    (":leaves:", "LEAF"),
+    (":droplet:", "AQUA"),
+];
+
+fn replace_emojis(s: &str) -> String {
+    EMOJIS
+        .iter()
+        .fold(s.to_string(), |s, (emoji, text)| s.replace(emoji, text))
+}
+
+/// Drops bold/italic markers and link targets, keeping the text.
+fn strip_markdown(s: &str) -> String {
+    let chars = replace_emojis(s).chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                out.push(chars[i + 1]);
+                i += 1;
+            }
+            '*' => {}
+            '[' => {
+                // `[text](url)` becomes `text`; other brackets are kept.
+                let close = chars[i..].iter().position(|&c| c == ']').map(|p| p + i);
+                match close {
+                    Some(close) if chars.get(close + 1) == Some(&'(') => {
+                        let end = chars[close..]
+                            .iter()
+                            .position(|&c| c == ')')
+                            .map_or(chars.len(), |p| p + close);
+                        out.extend(&chars[i + 1..close]);
+                        i = end;
+                    }
+                    _ => out.push('['),
+                }
+            }
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
@@ -0,0 +1,187 @@
+//! The framework's `on_error` handler: tells the user what went wrong in a short message (in
+//! Korean for Korean Discord clients), logs the full error with the command, arguments and
+//! place, and DMs the owners a summary if `notify_owners` is set in the config.
+
+use std::time::Duration;
+
+use poise::{serenity_prelude::Permissions, FrameworkError};
+
+use crate::{config::config, utils::*};
+
+/// Discord's message length limit, with room for the code block around the error.
+const DM_LIMIT: usize = 1900;
+
+/// What the user is told went wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// A bug or bad data; the details are only logged.
+    Internal,
+    /// The arguments didn't parse; the offending input, if poise knows it.
+    BadArguments(Option<String>),
+    MissingUserPermissions(Option<Permissions>),
+    MissingBotPermissions(Permissions),
+    GuildOnly,
+    DmOnly,
+    NotAnOwner,
+    Cooldown(Duration),
+}
+
+impl Failure {
+    pub fn message(&self, korean: bool) -> String {
+        match (self, korean) {
+            (Failure::Internal, false) => {
+                "Something went wrong while running this command. Please try again later, or report it on the support server.".to_string()
+            }
+            (Failure::Internal, true) => {
+                "명령어를 실행하는 중에 오류가 발생했습니다. 잠시 후 다시 시도하시거나 서포트 서버에 제보해주세요.".to_string()
+            }
+            (Failure::BadArguments(Some(input)), false) => {
+                format!("I couldn't understand `{}`; please check the command's options and try again.", input)
+            }
+            (Failure::BadArguments(Some(input)), true) => {
+                format!("`{}`을(를) 이해하지 못했습니다. 명령어 옵션을 확인하고 다시 시도해주세요.", input)
+            }
+            (Failure::BadArguments(None), false) => {
+                "I couldn't understand the command's options; please check them and try again.".to_string()
+            }
+            (Failure::BadArguments(None), true) => {
+                "명령어 옵션을 이해하지 못했습니다. 확인하고 다시 시도해주세요.".to_string()
+            }
+            (Failure::MissingUserPermissions(Some(permissions)), false) => {
+                format!("You need the {} permission to use this command.", permissions)
+            }
+            (Failure::MissingUserPermissions(Some(permissions)), true) => {
+                format!("이 명령어를 사용하려면 {} 권한이 필요합니다.", permissions)
+            }
+            (Failure::MissingUserPermissions(None), false) => {
+                "You don't have the permissions to use this command.".to_string()
+            }
+            (Failure::MissingUserPermissions(None), true) => {
+                "이 명령어를 사용할 권한이 없습니다.".to_string()
+            }
+            (Failure::MissingBotPermissions(permissions), false) => {
+                format!("I need the {} permission in this channel for that.", permissions)
+            }
+            (Failure::MissingBotPermissions(permissions), true) => {
+                format!("이 채널에서 봇에게 {} 권한이 필요합니다.", permissions)
+            }
+            (Failure::GuildOnly, false) => "This command only works in servers.".to_string(),
+            (Failure::GuildOnly, true) => "이 명령어는 서버에서만 사용할 수 있습니다.".to_string(),
+            (Failure::DmOnly, false) => "This command only works in DMs.".to_string(),
+            (Failure::DmOnly, true) => "이 명령어는 DM에서만 사용할 수 있습니다.".to_string(),
+            (Failure::NotAnOwner, false) => "This command is only for the bot owners.".to_string(),
+            (Failure::NotAnOwner, true) => "봇 관리자만 사용할 수 있는 명령어입니다.".to_string(),
+            (Failure::Cooldown(left), false) => {
+                format!("Please wait {} seconds and try again.", left.as_secs().max(1))
+            }
+            (Failure::Cooldown(left), true) => {
+                format!("{}초 후에 다시 시도해주세요.", left.as_secs().max(1))
+            }
+        }
+    }
+}
+
+/// Where a command was used, for logs and owner DMs.
+fn place(ctx: Context<'_>) -> String {
+    match ctx.guild_id() {
+        Some(guild) => format!("guild {} channel {}", guild, ctx.channel_id()),
+        None => format!("DM channel {}", ctx.channel_id()),
+    }
+}
+
+/// Logs a failed command with its context and notifies the owners if configured.
+async fn report(ctx: Context<'_>, error: &str, details: &str) {
+    let author = ctx.author();
+    let summary = format!(
+        "`{}` by {} ({}) in {}",
+        ctx.invocation_string(),
+        author.tag(),
+        author.id,
+        place(ctx)
+    );
+    eprintln!("Command error: {}: {}", summary, details);
+    if !config().notify_owners {
+        return;
+    }
+    let mut text = format!("{}:\n```\n{}", summary, error);
+    if text.len() > DM_LIMIT {
+        let mut end = DM_LIMIT;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push('…');
+    }
+    text.push_str("\n```");
+    let http = &ctx.serenity_context().http;
+    for &owner in &config().owners {
+        let sent = match poise::serenity_prelude::UserId(owner)
+            .create_dm_channel(http)
+            .await
+        {
+            Ok(channel) => channel.say(http, &text).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            eprintln!("Cannot DM owner {} about the error: {}", owner, e);
+        }
+    }
+}
+
+/// `FrameworkOptions::on_error` of the bot.
+pub async fn on_error(error: FrameworkError<'_, std::sync::Arc<Data>, Error>) {
+    let (ctx, failure) = match error {
+        FrameworkError::Command { error, ctx }
+        | FrameworkError::CommandCheckFailed {
+            error: Some(error),
+            ctx,
+        } => {
+            report(ctx, &error.to_string(), &format!("{:?}", error)).await;
+            (ctx, Failure::Internal)
+        }
+        FrameworkError::CommandPanic { payload, ctx } => {
+            let payload = payload.unwrap_or_else(|| "panic".to_string());
+            report(ctx, &payload, &payload).await;
+            (ctx, Failure::Internal)
+        }
+        FrameworkError::ArgumentParse { error, input, ctx } => {
+            eprintln!(
+                "Cannot parse `{}` in {}: {}",
+                ctx.invocation_string(),
+                place(ctx),
+                error
+            );
+            (ctx, Failure::BadArguments(input))
+        }
+        FrameworkError::MissingUserPermissions {
+            missing_permissions,
+            ctx,
+        } => (ctx, Failure::MissingUserPermissions(missing_permissions)),
+        FrameworkError::MissingBotPermissions {
+            missing_permissions,
+            ctx,
+        } => (ctx, Failure::MissingBotPermissions(missing_permissions)),
+        FrameworkError::GuildOnly { ctx } => (ctx, Failure::GuildOnly),
+        FrameworkError::DmOnly { ctx } => (ctx, Failure::DmOnly),
+        FrameworkError::NotAnOwner { ctx } => (ctx, Failure::NotAnOwner),
+        FrameworkError::CooldownHit {
+            remaining_cooldown,
+            ctx,
+        } => (ctx, Failure::Cooldown(remaining_cooldown)),
+        // Checks that say no reply themselves, like the cooldown check.
+        FrameworkError::CommandCheckFailed { error: None, .. } => return,
+        error => {
+            if let Err(e) = poise::builtins::on_error(error).await {
+                eprintln!("Error while handling an error: {}", e);
+            }
+            return;
+        }
+    };
+    let korean = ctx.locale().is_some_and(|locale| locale.starts_with("ko"));
+    let sent = ctx
+        .send(|f| f.ephemeral(true).content(failure.message(korean)))
+        .await;
+    if let Err(e) = sent {
+        eprintln!("Cannot tell the user about the error: {}", e);
+    }
+}
@@ -0,0 +1,152 @@
+//! Dumps of the merged chart database, for diffing releases and for other tools.
+
+use std::{collections::BTreeMap, fs::File, io::BufWriter, path::Path};
+
+use serde::Serialize;
+
+use crate::{catalog::*, utils::*};
+
+/// One chart per row, as written to CSV and SQLite.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChartRow {
+    pub title: String,
+    /// "DX"/"ST" for maimai, empty otherwise.
+    #[serde(rename = "type")]
+    pub chart_type: String,
+    /// Difficulty name, e.g. "MASTER".
+    pub difficulty: String,
+    /// "JP" or "INTL".
+    pub region: String,
+    pub level: String,
+    pub constant: Option<f32>,
+    /// The version the chart was added in, or the song's version if it isn't in the version list.
+    pub version: Option<String>,
+}
+
+/// Every song's full info, keyed by title.
+pub fn songs_json(catalog: &dyn GameCatalog) -> BTreeMap<String, serde_json::Value> {
+    catalog
+        .songs()
+        .filter_map(|(title, _)| Some((title.clone(), catalog.song_json(title)?)))
+        .collect()
+}
+
+/// Flattened charts, sorted by title.
+pub fn chart_rows(catalog: &dyn GameCatalog) -> Vec<ChartRow> {
+    let names = catalog.difficulty_names();
+    let mut songs = catalog.songs().collect::<Vec<_>>();
+    songs.sort_by_key(|(title, _)| *title);
+    songs
+        .into_iter()
+        .flat_map(|(title, song)| {
+            let version = song.version();
+            song.chart_entries()
+                .into_iter()
+                .map(|chart| ChartRow {
+                    title: title.clone(),
+                    chart_type: chart.chart_type.unwrap_or_default().to_string(),
+                    difficulty: names
+                        .get(chart.difficulty)
+                        .map_or_else(|| chart.difficulty.to_string(), |n| n.to_string()),
+                    region: match chart.region {
+                        Region::Jp => "JP",
+                        Region::Intl => "INTL",
+                    }
+                    .to_string(),
+                    level: chart.level,
+                    constant: chart.constant.map(|c| c.0),
+                    version: chart
+                        .version
+                        .map(|v| v.name.clone())
+                        .or_else(|| version.clone()),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+pub fn write_json(catalog: &dyn GameCatalog, path: &Path) -> Result<(), Error> {
+    let file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(file, &songs_json(catalog))?;
+    Ok(())
+}
+
+pub fn write_csv(catalog: &dyn GameCatalog, path: &Path) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for row in chart_rows(catalog) {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes `songs` and `charts` tables for all `catalogs` into a fresh SQLite file.
+/// `songs.info` holds the same JSON as `write_json`.
+pub fn write_sqlite(catalogs: &[(&str, &dyn GameCatalog)], path: &Path) -> Result<(), Error> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let mut conn = rusqlite::Connection::open(path)?;
+    conn.execute_batch(
+        "CREATE TABLE songs (
+            game TEXT NOT NULL,
+            title TEXT NOT NULL,
+            artist TEXT NOT NULL,
+            version TEXT,
+            deleted INTEGER NOT NULL,
+            info TEXT NOT NULL,
+            PRIMARY KEY (game, title)
+        );
+        CREATE TABLE charts (
+            game TEXT NOT NULL,
+            title TEXT NOT NULL,
+            type TEXT NOT NULL,
+            difficulty TEXT NOT NULL,
+            region TEXT NOT NULL,
+            level TEXT NOT NULL,
+            constant REAL,
+            version TEXT
+        );
+        CREATE INDEX charts_title ON charts (game, title);",
+    )?;
+
+    let tx = conn.transaction()?;
+    {
+        let mut insert_song = tx.prepare(
+            "INSERT INTO songs (game, title, artist, version, deleted, info)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut insert_chart = tx.prepare(
+            "INSERT INTO charts (game, title, type, difficulty, region, level, constant, version)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for (game, catalog) in catalogs {
+            for (title, info) in songs_json(*catalog) {
+                let song = catalog.song(&title).unwrap();
+                insert_song.execute(rusqlite::params![
+                    game,
+                    title,
+                    song.artist(),
+                    song.version(),
+                    song.deleted(),
+                    info.to_string(),
+                ])?;
+            }
+            for row in chart_rows(*catalog) {
+                insert_chart.execute(rusqlite::params![
+                    game,
+                    row.title,
+                    row.chart_type,
+                    row.difficulty,
+                    row.region,
+                    row.level,
+                    // Through the decimal form, so 12.3 doesn't become 12.300000190734863.
+                    row.constant.map(|c| c.to_string().parse::<f64>().unwrap()),
+                    row.version,
+                ])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
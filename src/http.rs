@@ -0,0 +1,214 @@
+//! Read-only JSON API over the same chart data the bot serves.
+//!
+//! - `GET /songs/{game}`: every song of a game, keyed by title
+//! - `GET /songs/{game}/{title}`: one song; `title` may be any alias
+//! - `GET /resolve/{game}?q=...&server=...`: what `get_title` resolves a query to
+//! - `GET /search?game=...&level=...`: songs matching a `SearchFilter`, over all games if `game` is omitted
+//!
+//! `{game}` is "maimai", "chuni", "ongeki" or a custom game id.
+
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{catalog::*, export::songs_json, utils::*};
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+pub struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+fn not_found(what: String) -> ApiError {
+    ApiError(StatusCode::NOT_FOUND, what)
+}
+
+fn bad_request(what: String) -> ApiError {
+    ApiError(StatusCode::BAD_REQUEST, what)
+}
+
+fn catalog<'a>(data: &'a Data, game: &str) -> Result<&'a dyn GameCatalog, ApiError> {
+    data.catalog_by_id(game)
+        .ok_or_else(|| not_found(format!("Unknown game {game}")))
+}
+
+pub fn router(data: Arc<Data>) -> Router {
+    Router::new()
+        .route("/songs/:game", get(songs))
+        .route("/songs/:game/:title", get(song))
+        .route("/resolve/:game", get(resolve))
+        .route("/search", get(search))
+        .with_state(data)
+}
+
+/// Serves the API on `addr` until the process exits.
+pub async fn serve(data: Arc<Data>, addr: SocketAddr) -> Result<(), Error> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(data)).await?;
+    Ok(())
+}
+
+async fn songs(
+    State(data): State<Arc<Data>>,
+    Path(game): Path<String>,
+) -> ApiResult<BTreeMap<String, Value>> {
+    Ok(Json(songs_json(catalog(&data, &game)?)))
+}
+
+async fn song(
+    State(data): State<Arc<Data>>,
+    Path((game, title)): Path<(String, String)>,
+) -> ApiResult<Value> {
+    let catalog = catalog(&data, &game)?;
+    let key = if catalog.song(&title).is_some() {
+        title
+    } else {
+        catalog
+            .lookup(&title, GuildId(0))
+            .ok_or_else(|| not_found(format!("No song found for \"{title}\"")))?
+    };
+    catalog
+        .song_json(&key)
+        .map(Json)
+        .ok_or_else(|| not_found(format!("No data for {key}")))
+}
+
+#[derive(Deserialize)]
+struct ResolveQuery {
+    q: String,
+    /// Guild id, for server-specific aliases.
+    server: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct Closest {
+    alias: String,
+    title: String,
+}
+
+#[derive(Serialize)]
+struct Resolved {
+    query: String,
+    /// `None` if the query doesn't resolve; see `closest` then.
+    title: Option<String>,
+    closest: Option<Closest>,
+}
+
+async fn resolve(
+    State(data): State<Arc<Data>>,
+    Path(game): Path<String>,
+    Query(query): Query<ResolveQuery>,
+) -> ApiResult<Resolved> {
+    let catalog = catalog(&data, &game)?;
+    let server = GuildId(query.server.unwrap_or(0));
+    let title = catalog.lookup(&query.q, server);
+    let closest = if title.is_none() {
+        let (alias, title) = get_closest_title(&query.q, catalog.aliases(), server);
+        Some(Closest { alias, title })
+    } else {
+        None
+    };
+    Ok(Json(Resolved {
+        query: query.q,
+        title,
+        closest,
+    }))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    game: Option<String>,
+    level: Option<String>,
+    min_constant: Option<f32>,
+    max_constant: Option<f32>,
+    /// Difficulty name, e.g. "MASTER"; games without it are skipped.
+    difficulty: Option<String>,
+    chart_type: Option<String>,
+    /// "jp" or "intl".
+    region: Option<String>,
+    artist: Option<String>,
+    version: Option<String>,
+    /// Only charts added in the current version.
+    #[serde(default)]
+    new: bool,
+    #[serde(default)]
+    include_deleted: bool,
+}
+
+#[derive(Serialize)]
+struct SearchHit {
+    game: String,
+    title: String,
+    song: Value,
+}
+
+async fn search(
+    State(data): State<Arc<Data>>,
+    Query(query): Query<SearchQuery>,
+) -> ApiResult<Vec<SearchHit>> {
+    let region = match query.region.as_deref().map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("jp") => Some(Region::Jp),
+        Some("intl") => Some(Region::Intl),
+        Some(r) => return Err(bad_request(format!("Unknown region {r}"))),
+    };
+    let catalogs = match &query.game {
+        Some(game) => vec![(game.as_str(), catalog(&data, game)?)],
+        None => data.catalogs(),
+    };
+
+    let mut hits = vec![];
+    for (game, catalog) in catalogs {
+        let difficulty = match &query.difficulty {
+            None => None,
+            Some(name) => {
+                let idx = catalog
+                    .difficulty_names()
+                    .iter()
+                    .position(|d| d.eq_ignore_ascii_case(name));
+                match idx {
+                    Some(idx) => Some(idx),
+                    None if query.game.is_some() => {
+                        return Err(bad_request(format!("Unknown difficulty {name} for {game}")))
+                    }
+                    None => continue,
+                }
+            }
+        };
+        let filter = SearchFilter {
+            level: query.level.clone(),
+            min_constant: query.min_constant,
+            max_constant: query.max_constant,
+            difficulty,
+            chart_type: query.chart_type.clone(),
+            region,
+            artist: query.artist.clone(),
+            version: query.version.clone(),
+            new_only: query.new,
+            include_deleted: query.include_deleted,
+        };
+        for title in catalog.search(&filter) {
+            if let Some(song) = catalog.song_json(&title) {
+                hits.push(SearchHit {
+                    game: game.to_string(),
+                    title,
+                    song,
+                });
+            }
+        }
+    }
+    Ok(Json(hits))
+}
@@ -0,0 +1,179 @@
+//! Fonts and the few drawing primitives the generated images need, on top of `image` and
+//! `ab_glyph` so nothing outside the crate graph has to be installed.
+//!
+//! Every font in `data/fonts` is loaded in file name order, and each character is drawn with
+//! the first font that has a glyph for it. The bundled DejaVu Sans covers Latin, Greek and
+//! Cyrillic, and WenQuanYi Micro Hei the kana, kanji and Hangul of the rest of the titles.
+
+use std::{fs, io::Cursor, path::Path};
+
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use eyre::{ensure, WrapErr};
+use image::{Rgba, RgbaImage};
+
+/// Relative to `data_dir`.
+pub const FONT_DIR: &str = "fonts";
+
+pub type Color = (u8, u8, u8);
+
+pub struct Fonts {
+    fonts: Vec<FontVec>,
+}
+
+impl Fonts {
+    /// Loads every .ttf, .otf and .ttc file in `dir`; fails if there are none.
+    pub fn load(dir: &Path) -> eyre::Result<Self> {
+        let mut paths = fs::read_dir(dir)
+            .wrap_err_with(|| format!("Cannot read font directory {:?}", dir))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ["ttf", "otf", "ttc"].contains(&ext.to_lowercase().as_str()))
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        let mut fonts = vec![];
+        for path in paths {
+            let bytes = fs::read(&path).wrap_err_with(|| format!("Cannot read {:?}", path))?;
+            fonts.push(
+                FontVec::try_from_vec(bytes)
+                    .map_err(|e| eyre::eyre!("Invalid font {:?}: {}", path, e))?,
+            );
+        }
+        ensure!(!fonts.is_empty(), "No fonts in {:?}", dir);
+        Ok(Self { fonts })
+    }
+
+    /// The first font with a glyph for `c`.
+    fn find_glyph(&self, c: char) -> Option<(&FontVec, GlyphId)> {
+        self.fonts
+            .iter()
+            .map(|font| (font, font.glyph_id(c)))
+            .find(|(_, id)| id.0 != 0)
+    }
+
+    /// The first font with a glyph for `c`, or the first font's missing glyph.
+    fn glyph(&self, c: char) -> (&FontVec, GlyphId) {
+        self.find_glyph(c)
+            .unwrap_or_else(|| (&self.fonts[0], self.fonts[0].glyph_id(c)))
+    }
+
+    /// Whether any font can draw `c`.
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.find_glyph(c).is_some()
+    }
+
+    /// Width of `text` in pixels at `size`.
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars()
+            .map(|c| {
+                let (font, id) = self.glyph(c);
+                font.as_scaled(PxScale::from(size)).h_advance(id)
+            })
+            .sum()
+    }
+
+    /// `text`, cut short with "…" if it is wider than `width`.
+    pub fn fit(&self, text: &str, size: f32, width: f32) -> String {
+        if self.text_width(text, size) <= width {
+            return text.to_string();
+        }
+        let mut fitted = text.to_string();
+        while !fitted.is_empty() && self.text_width(&format!("{}…", fitted), size) > width {
+            fitted.pop();
+        }
+        format!("{}…", fitted.trim_end())
+    }
+
+    /// Draws `text` with its top-left corner at (`x`, `y`).
+    pub fn draw_text(
+        &self,
+        image: &mut RgbaImage,
+        text: &str,
+        size: f32,
+        (x, y): (f32, f32),
+        color: Color,
+    ) {
+        let scale = PxScale::from(size);
+        let mut caret = x;
+        for c in text.chars() {
+            let (font, id) = self.glyph(c);
+            let scaled = font.as_scaled(scale);
+            let glyph = id.with_scale_and_position(scale, point(caret, y + scaled.ascent()));
+            caret += scaled.h_advance(id);
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + gx as i64;
+                let py = bounds.min.y as i64 + gy as i64;
+                blend(image, px, py, color, coverage);
+            });
+        }
+    }
+
+    /// Draws `text` centered horizontally on `center_x`.
+    pub fn draw_centered(
+        &self,
+        image: &mut RgbaImage,
+        text: &str,
+        size: f32,
+        (center_x, y): (f32, f32),
+        color: Color,
+    ) {
+        let x = center_x - self.text_width(text, size) / 2.;
+        self.draw_text(image, text, size, (x, y), color);
+    }
+}
+
+fn blend(image: &mut RgbaImage, x: i64, y: i64, (r, g, b): Color, alpha: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    let alpha = alpha.clamp(0., 1.);
+    let mix = |under: u8, over: u8| (under as f32 * (1. - alpha) + over as f32 * alpha) as u8;
+    *pixel = Rgba([mix(pixel[0], r), mix(pixel[1], g), mix(pixel[2], b), 255]);
+}
+
+pub fn fill_rect(image: &mut RgbaImage, (x, y): (u32, u32), (w, h): (u32, u32), (r, g, b): Color) {
+    for py in y..(y + h).min(image.height()) {
+        for px in x..(x + w).min(image.width()) {
+            image.put_pixel(px, py, Rgba([r, g, b, 255]));
+        }
+    }
+}
+
+/// The in-game colour of a difficulty, if it is one of the usual ones.
+pub fn difficulty_color(name: &str) -> Option<Color> {
+    Some(match name.to_uppercase().as_str() {
+        "BASIC" => (34, 187, 91),
+        "ADVANCED" => (251, 156, 45),
+        "EXPERT" => (246, 72, 97),
+        "MASTER" => (158, 69, 226),
+        "RE:MASTER" => (219, 170, 255),
+        "ULTIMA" => (40, 40, 40),
+        "LUNATIC" => (235, 235, 235),
+        _ => return None,
+    })
+}
+
+/// Black or white, whichever reads better on `background`.
+pub fn text_color((r, g, b): Color) -> Color {
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luminance > 150. {
+        (0, 0, 0)
+    } else {
+        (255, 255, 255)
+    }
+}
+
+pub fn encode_png(image: &RgbaImage) -> eyre::Result<Vec<u8>> {
+    let mut bytes = Cursor::new(vec![]);
+    image
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .wrap_err("Cannot encode PNG")?;
+    Ok(bytes.into_inner())
+}
@@ -0,0 +1,96 @@
+//! Jackets downloaded once and kept under `data/jackets/{game}/`, so replies can attach the
+//! image itself instead of handing Discord a remote URL that may be slow or gone.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::WrapErr;
+
+use crate::{catalog::GameCatalog, utils::get_curl_bytes};
+
+/// Relative to `data_dir`.
+pub const JACKET_DIR: &str = "jackets";
+
+#[derive(Debug, Clone)]
+pub struct JacketCache {
+    dir: PathBuf,
+}
+
+/// Fails unless `bytes` decode as an image.
+pub fn verify(bytes: &[u8]) -> eyre::Result<()> {
+    image::load_from_memory(bytes).wrap_err("Not a readable image")?;
+    Ok(())
+}
+
+impl JacketCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Where the jacket named `jacket` of `game` is stored. Directories in the name are kept, so
+    /// jackets sharing a file name under different remote directories don't collide.
+    pub fn path(&self, game: &str, jacket: &str) -> PathBuf {
+        let mut path = self.dir.join(game);
+        for part in jacket.split(['/', '\\']) {
+            if !matches!(part, "" | "." | "..") {
+                path.push(part);
+            }
+        }
+        path
+    }
+
+    pub fn is_cached(&self, game: &str, jacket: &str) -> bool {
+        self.path(game, jacket).exists()
+    }
+
+    /// The jacket's bytes, downloaded from `url` and stored first if it isn't cached yet.
+    /// Blocking.
+    pub fn get(&self, game: &str, jacket: &str, url: &str) -> eyre::Result<Vec<u8>> {
+        let path = self.path(game, jacket);
+        if let Ok(bytes) = fs::read(&path) {
+            return Ok(bytes);
+        }
+        let bytes = get_curl_bytes(url).wrap_err_with(|| format!("Cannot download {}", url))?;
+        verify(&bytes).wrap_err_with(|| format!("Bad jacket at {}", url))?;
+        store(&path, &bytes).wrap_err_with(|| format!("Cannot write {:?}", path))?;
+        Ok(bytes)
+    }
+
+    /// Like `get`, paired with the jacket's file name for attaching it. Blocking.
+    pub fn get_named(
+        &self,
+        game: &str,
+        jacket: &str,
+        url: &str,
+    ) -> eyre::Result<(String, Vec<u8>)> {
+        let bytes = self.get(game, jacket, url)?;
+        let path = self.path(game, jacket);
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        Ok((name.into_owned(), bytes))
+    }
+
+    /// The jacket of `title` as (file name, bytes); `None` if the song has no jacket.
+    /// Blocking.
+    pub fn jacket(
+        &self,
+        catalog: &dyn GameCatalog,
+        title: &str,
+    ) -> Option<eyre::Result<(String, Vec<u8>)>> {
+        let jacket = catalog.song(title)?.jp_jacket()?;
+        let url = catalog.jacket_url(title)?;
+        Some(self.get_named(catalog.id(), jacket, &url))
+    }
+}
+
+/// Writes through a temporary file so a crash never leaves a truncated jacket behind.
+fn store(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".part");
+    fs::write(&tmp, bytes)?;
+    fs::rename(tmp, path)
+}
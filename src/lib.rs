@@ -1,7 +1,31 @@
+pub mod artists;
+pub mod card;
+pub mod catalog;
+pub mod changes;
+pub mod chart_view;
 pub mod chuni;
+pub mod components;
+pub mod config;
+pub mod cooldowns;
+pub mod cross_game;
+pub mod crossover;
+pub mod custom;
+pub mod document;
+pub mod errors;
+pub mod export;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod images;
+pub mod jackets;
 pub mod maimai;
 pub mod ongeki;
+pub mod preferences;
+pub mod rating;
+pub mod regions;
+pub mod settings;
+pub mod updates;
 pub mod utils;
+pub mod versions;
 
 #[cfg(test)]
 mod tests {
@@ -15,6 +39,7 @@ mod tests {
     };
 
     #[test]
+    #[ignore = "requires network access and the untracked data files"]
     fn full_test() -> Result<(), Error> {
         let files_not_urls = [
             "data/maimai/maimai-jacket-prefix.txt",
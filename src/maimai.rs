@@ -2,18 +2,17 @@ use eyre::bail;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use ordered_float::OrderedFloat;
-use poise::serenity_prelude::{
-    interaction::InteractionResponseType, Color, CreateActionRow, CreateButton,
-};
 use std::{
     collections::{HashMap, HashSet},
-    fs::{self, File},
-    io::{BufRead, BufReader, Write},
-    sync::Arc,
-    time::Duration,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
 };
 
-use crate::utils::*;
+use crate::{
+    catalog::*, chart_view::chart_template, config::*, cooldowns::cooldown_check, document::Field,
+    utils::*, versions::*,
+};
 
 lazy_static! {
     static ref SONG_REPLACEMENT: HashMap<String, String> = {
@@ -210,172 +209,164 @@ lazy_static! {
     };
 }
 
-fn get_mai_embed(title: String, ctx: &Context<'_>) -> eyre::Result<(String, Option<String>)> {
-    let embed = get_mai_embed_inner(title.clone(), ctx, true)?;
-    if embed.0.len() < 4096 {
-        Ok(embed)
-    } else {
-        get_mai_embed_inner(title, ctx, false)
+impl SongInfo for MaiInfo {
+    fn title(&self) -> &str {
+        &self.title
     }
-}
 
-fn get_mai_embed_inner(
-    title: String,
-    ctx: &Context<'_>,
-    use_links: bool,
-) -> eyre::Result<(String, Option<String>)> {
-    let Some(song) = ctx.data().mai_charts.get(&title) else {
-        bail!("No data for {title}");
-    };
-
-    let mut description = if song.deleted {
-        "**THIS SONG IS DELETED**\n\n"
-    } else {
-        ""
+    fn artist(&self) -> &str {
+        &self.artist
     }
-    .to_string();
 
-    description = format!(
-        "{}**Artist:** {}",
-        description,
-        song.artist.replace('*', "\\*")
-    );
-    if let Some(version) = &song.version {
-        description = format!("{}\n**Version:** {}", description, version);
+    fn deleted(&self) -> bool {
+        self.deleted
     }
-    if let Some(version) = &song.additional_remas_version {
-        description = format!("{}\n**Version (Re:MASTER):** {}", description, version);
-    }
-    if let Some(version) = &song.additional_st_version {
-        description = format!("{}\n**Version (ST):** {}", description, version);
+
+    fn jp_jacket(&self) -> Option<&str> {
+        self.jp_jacket.as_deref()
     }
-    if let Some(version) = &song.additional_dx_version {
-        description = format!("{}\n**Version (DX):** {}", description, version);
+
+    fn version(&self) -> Option<String> {
+        self.version.clone()
     }
-    if let Some(bpm) = song.bpm {
-        description = format!("{}\n**BPM:** {}", description, bpm);
+
+    fn chart_entries(&self) -> Vec<ChartEntry> {
+        let find =
+            |version: &Option<String>| version.as_ref().and_then(|v| versions().find("maimai", v));
+        let version = find(&self.version);
+        let dx_version = find(&self.additional_dx_version).or(version);
+        let st_version = find(&self.additional_st_version).or(version);
+        let mut entries = vec![];
+        for (region, lv) in [(Region::Jp, &self.jp_lv), (Region::Intl, &self.intl_lv)] {
+            let Some(lv) = lv else {
+                continue;
+            };
+            if let Some(dx) = &lv.dx {
+                entries.extend(difficulty_entries(dx, region, Some("DX"), dx_version));
+            }
+            if let Some(st) = &lv.st {
+                entries.extend(difficulty_entries(st, region, Some("ST"), st_version));
+            }
+        }
+        if let Some(remas_version) = find(&self.additional_remas_version) {
+            for chart in entries.iter_mut().filter(|c| c.difficulty == 4) {
+                chart.version = Some(remas_version);
+            }
+        }
+        entries
     }
-    if song.deleted {
-        let (st, dx) = if let Some(jp_lv) = &song.jp_lv {
-            (jp_lv.st.is_some(), jp_lv.dx.is_some())
-        } else {
-            (false, false)
-        };
 
+    fn chart_details(&self, chart_type: Option<&str>, difficulty: usize) -> Option<ChartDetails> {
+        let (sheets, dx) = match chart_type {
+            Some("DX") => (&self.dx_sheets, true),
+            Some("ST") => (&self.st_sheets, false),
+            _ => return None,
+        };
+        let sheet = sheets.get(difficulty)?;
+        let mut notes = vec![
+            ("Tap", sheet.tap),
+            ("Hold", sheet.hold),
+            ("Slide", sheet.slide),
+        ];
+        // Only DX charts have touch notes.
         if dx {
-            description = format!(
-                "{}\n\n**Level(DX):**\n{}",
-                description,
-                level_description(
-                    song.jp_lv.as_ref().unwrap().dx.as_ref().unwrap(),
-                    &title,
-                    use_links
-                )
-            )
+            notes.push(("Touch", sheet.touch));
         }
-        if st {
-            description = format!(
-                "{}\n\n**Level(ST):**\n{}",
-                description,
-                level_description(
-                    song.jp_lv.as_ref().unwrap().st.as_ref().unwrap(),
-                    &title,
-                    use_links
-                )
-            )
+        notes.push(("Break", sheet.brk));
+        // Unknown counts are filled with large placeholders.
+        if notes.iter().map(|(_, count)| count).sum::<usize>() >= 99999 {
+            notes.clear();
         }
-    } else {
-        let in_lv = &song.intl_lv;
-        let jp_lv = &song.jp_lv;
+        Some(ChartDetails {
+            designer: sheet.designer.clone(),
+            notes,
+        })
+    }
+}
 
-        let (in_st, in_dx) = if let Some(in_lv) = in_lv {
-            (in_lv.st.is_some(), in_lv.dx.is_some())
-        } else {
-            (false, false)
-        };
-        let (jp_st, jp_dx) = if let Some(jp_lv) = jp_lv {
-            (jp_lv.st.is_some(), jp_lv.dx.is_some())
-        } else {
-            (false, false)
-        };
+impl GameSong for MaiInfo {
+    const NAME: &'static str = "maimai";
+    const COLOR: (u8, u8, u8) = (0, 255, 255);
+    const DIFFICULTY_NAMES: &'static [&'static str] =
+        &["BASIC", "ADVANCED", "EXPERT", "MASTER", "Re:MASTER"];
 
-        let jp_dx_txt = if jp_dx {
-            level_description(
-                jp_lv.as_ref().unwrap().dx.as_ref().unwrap(),
-                &title,
-                use_links,
-            )
-        } else {
-            "**Unreleased**".to_string()
-        };
-        let in_dx_txt = if in_dx {
-            level_description(
-                in_lv.as_ref().unwrap().dx.as_ref().unwrap(),
-                &title,
-                use_links,
-            )
-        } else {
-            "**Unreleased**".to_string()
-        };
-        if in_dx || jp_dx {
-            if jp_dx_txt == in_dx_txt {
-                description = format!(
-                    "{}
-
-**Level(DX)**
-:flag_jp::globe_with_meridians: {}",
-                    description, jp_dx_txt
-                );
-            } else {
-                description = format!(
-                    "{}
+    fn display_title(title: &str) -> String {
+        mai_duplicate_alias_to_title(title)
+    }
 
-**Level(DX)**
-:flag_jp: {}
-:globe_with_meridians: {}",
-                    description, jp_dx_txt, in_dx_txt
-                );
-            }
-        };
+    fn render(&self, title: &str, options: &RenderOptions) -> eyre::Result<(String, Vec<Field>)> {
+        get_mai_embed(self, title, options)
+    }
+}
 
-        let jp_st_txt = if jp_st {
-            level_description(
-                jp_lv.as_ref().unwrap().st.as_ref().unwrap(),
-                &title,
-                use_links,
-            )
-        } else {
-            "**Unreleased**".to_string()
-        };
-        let in_st_txt = if in_st {
-            level_description(
-                in_lv.as_ref().unwrap().st.as_ref().unwrap(),
-                &title,
-                use_links,
-            )
-        } else {
-            "**Unreleased**".to_string()
-        };
-        if in_st || jp_st {
-            if in_st_txt == jp_st_txt {
-                description = format!(
-                    "{}
-
-**Level(ST)**
-:flag_jp::globe_with_meridians: {}",
-                    description, jp_st_txt
-                );
-            } else {
-                description = format!(
-                    "{}
+/// The DX or ST levels of one region, if it has that chart type.
+fn chart_levels(lv: &Option<MaiDifficulty>, dx: bool) -> Option<&Difficulty> {
+    let lv = lv.as_ref()?;
+    if dx {
+        lv.dx.as_ref()
+    } else {
+        lv.st.as_ref()
+    }
+}
 
-**Level(ST)**
-:flag_jp: {}
-:globe_with_meridians: {}",
-                    description, jp_st_txt, in_st_txt
-                );
+fn get_mai_embed(
+    song: &MaiInfo,
+    title: &str,
+    options: &RenderOptions,
+) -> eyre::Result<(String, Vec<Field>)> {
+    let mut fields = vec![];
+    let mut description = if song.deleted {
+        "**THIS SONG IS DELETED**\n\n"
+    } else {
+        ""
+    }
+    .to_string();
+
+    if !options.compact {
+        description = format!(
+            "{}**Artist:** {}",
+            description,
+            song.artist.replace('*', "\\*")
+        );
+        if let Some(version) = &song.version {
+            description = format!("{}\n**Version:** {}", description, version);
+        }
+        if let Some(version) = &song.additional_remas_version {
+            description = format!("{}\n**Version (Re:MASTER):** {}", description, version);
+        }
+        if let Some(version) = &song.additional_st_version {
+            description = format!("{}\n**Version (ST):** {}", description, version);
+        }
+        if let Some(version) = &song.additional_dx_version {
+            description = format!("{}\n**Version (DX):** {}", description, version);
+        }
+        if let Some(bpm) = song.bpm {
+            description = format!("{}\n**BPM:** {}", description, bpm);
+        }
+    }
+    for (name, dx) in [("Level(DX)", true), ("Level(ST)", false)] {
+        let jp = chart_levels(&song.jp_lv, dx);
+        if song.deleted {
+            if let Some(jp) = jp {
+                fields.push(Field::new(name, level_description(jp, title, options)));
             }
+            continue;
+        }
+        let intl = chart_levels(&song.intl_lv, dx);
+        if jp.is_none() && intl.is_none() {
+            continue;
+        }
+        let text = |lv: Option<&Difficulty>| match lv {
+            Some(lv) => level_description(lv, title, options),
+            None => "**Unreleased**".to_string(),
         };
+        fields.push(Field::new(
+            name,
+            region_lines(&text(jp), &text(intl), options),
+        ));
+    }
+    if fields.is_empty() && song.utages.is_empty() {
+        bail!("No levels for {title}");
     }
 
     if !song.utages.is_empty() {
@@ -384,17 +375,12 @@ fn get_mai_embed_inner(
             .iter()
             .map(|utage| format!("{} **{}** *{}*", utage.kanji, utage.level, utage.comment))
             .join("\n");
-        description = format!(
-            "{description}
-
-**U･TA･GE**
-{utage_info}"
-        );
+        fields.push(Field::new("U･TA･GE", utage_info));
     }
-    Ok((description, song.jp_jacket.clone()))
+    Ok((description.trim().to_string(), fields))
 }
 
-fn mai_duplicate_alias_to_title(title: &String) -> String {
+fn mai_duplicate_alias_to_title(title: &str) -> String {
     if title == "Link (maimai)" {
         "Link".to_string()
     } else {
@@ -403,87 +389,49 @@ fn mai_duplicate_alias_to_title(title: &String) -> String {
 }
 
 /// Get maimai song info
-#[poise::command(slash_command, prefix_command, rename = "mai-info")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "mai-info",
+    check = "cooldown_check"
+)]
 pub async fn mai_info(
     ctx: Context<'_>,
+    #[description = "Show a chart card image instead of text"]
+    #[flag]
+    card: bool,
     #[description = "Song title e.g. \"Selector\", \"bbb\", etc. You don't have to be exact; try things out!"]
     #[rest]
     title: String,
 ) -> Result<(), Error> {
-    info_template(
-        ctx,
-        title,
-        Game::Maimai,
-        Arc::new(get_mai_embed),
-        (0, 255, 255),
-        Arc::new(mai_duplicate_alias_to_title),
-    )
-    .await?;
+    info_template(ctx, &ctx.data().maimai, title, card).await?;
     Ok(())
 }
 
-fn level_description(lv: &Difficulty, title: &str, use_links: bool) -> String {
-    let title = title.replace(" -", " ");
-    let title = title.strip_prefix('-').unwrap_or(&title);
-    let title = urlencoding::encode(title);
-    if use_links {
-        format!(
-            // "BAS **{}{}**/ADV **{}{}**/EXP **{}{}**/MAS **{}{}**{}",
-            "[B](https://www.youtube.com/results?search_query=maimai+{}+BASIC) **{}**{} / [A](https://www.youtube.com/results?search_query=maimai+{}+ADVANCED) **{}**{} / [E](https://www.youtube.com/results?search_query=maimai+{}+EXPERT) **{}**{} / [M](https://www.youtube.com/results?search_query=maimai+{}+MASTER) **{}**{}{}",
-            title,
-            lv.bas,
-            constant_to_string(lv.bas_c),
-            title,
-            lv.adv,
-            constant_to_string(lv.adv_c),
-            title,
-            lv.exp,
-            constant_to_string(lv.exp_c),
-            title,
-            lv.mas,
-            constant_to_string(lv.mas_c),
-            if let Some(rem) = &lv.extra {
-                format!(" / [R](https://www.youtube.com/results?search_query=maimai+{}+Re:MASTER) **{}**{}", title, rem, constant_to_string(lv.extra_c))
-            } else {
-                "".to_string()
-            }
-        )
-    } else {
-        format!(
-            // "BAS **{}{}**/ADV **{}{}**/EXP **{}{}**/MAS **{}{}**{}",
-            "B **{}**{} / A **{}**{} / E **{}**{} / M **{}**{}{}",
-            lv.bas,
-            constant_to_string(lv.bas_c),
-            lv.adv,
-            constant_to_string(lv.adv_c),
-            lv.exp,
-            constant_to_string(lv.exp_c),
-            lv.mas,
-            constant_to_string(lv.mas_c),
-            if let Some(rem) = &lv.extra {
-                format!(" / R **{}**{}", rem, constant_to_string(lv.extra_c))
-            } else {
-                "".to_string()
-            }
-        )
-    }
+fn level_description(lv: &Difficulty, title: &str, options: &RenderOptions) -> String {
+    difficulty_line(lv, "maimai", title, "Re:MASTER", options)
 }
 
 /// Get maimai song jacket
-#[poise::command(slash_command, prefix_command, rename = "mai-jacket")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "mai-jacket",
+    check = "cooldown_check"
+)]
 pub async fn mai_jacket(
     ctx: Context<'_>,
     #[description = "Song title e.g. \"Selector\", \"bbb\", etc. You don't have to be exact; try things out!"]
     #[rest]
     title: String,
 ) -> Result<(), Error> {
-    jacket_template(ctx, title, Game::Maimai).await?;
+    jacket_template(ctx, &ctx.data().maimai, title).await?;
     Ok(())
 }
 
 fn set_jp_difficulty(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()> {
     // Get JP difficulty.
-    let jp_url = fs::read_to_string("data/maimai/maimai-jp.txt")?;
+    let jp_url = config().source(&config().maimai.jp_url, "maimai/maimai-jp.txt")?;
     let jp_url = jp_url.trim();
     let s = get_curl(jp_url);
 
@@ -585,7 +533,7 @@ fn set_jp_difficulty(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()>
 #[allow(dead_code)]
 fn set_jp_constants(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()> {
     // Get jp constants
-    let file = File::open("data/maimai/jp_lv.csv")?;
+    let file = File::open(data_path("maimai/jp_lv.csv"))?;
     let reader = BufReader::new(file);
 
     for line in reader.lines() {
@@ -710,7 +658,7 @@ fn set_jp_constants(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()> {
 
 fn set_actual_constants(
     charts: &mut HashMap<String, MaiInfo>,
-    filename: &str,
+    filename: &Path,
     is_jp: bool,
 ) -> eyre::Result<()> {
     // Get jp constants from second source.
@@ -766,17 +714,18 @@ fn set_actual_constants(
             let Some(mai_diff) = dx_or_st_chart.as_mut() else {
                 continue;
             };
-            let current_cc = mai_diff.get_constant(diff);
-            if current_cc.is_some() && format!("{:.1}", current_cc.unwrap()) != cc {
-                eprintln!(
-                    "{} constant sources different on song {} {} {} - {:.1} vs {}",
-                    if is_jp { "JP" } else { "INTL" },
-                    title,
-                    dx,
-                    diff,
-                    current_cc.unwrap(),
-                    cc
-                );
+            if let Some(current_cc) = mai_diff.get_constant(diff) {
+                if format!("{:.1}", current_cc) != cc {
+                    eprintln!(
+                        "{} constant sources different on song {} {} {} - {:.1} vs {}",
+                        if is_jp { "JP" } else { "INTL" },
+                        title,
+                        dx,
+                        diff,
+                        current_cc,
+                        cc
+                    );
+                }
             }
             mai_diff.set_constant(diff, cc.to_string());
         }
@@ -790,7 +739,7 @@ fn set_intl_difficulty(
 ) -> eyre::Result<()> {
     // Get intl difficulty.
     if jp_and_intl_version_is_different {
-        let file = File::open("data/maimai/in_lv.csv")?;
+        let file = File::open(data_path("maimai/in_lv.csv"))?;
         let reader = BufReader::new(file);
 
         for line in reader.lines() {
@@ -902,7 +851,7 @@ fn set_intl_difficulty(
 
 fn set_song_info(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()> {
     // Get info DB
-    let info = fs::read_to_string("data/maimai/maimai-info.txt")?;
+    let info = config().source(&config().maimai.info_url, "maimai/maimai-info.txt")?;
     let info = info.trim();
     let s = get_curl(info);
 
@@ -1255,7 +1204,7 @@ fn set_song_info(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()> {
 
 fn set_manual_constants(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()> {
     // Add manual constant info
-    let file = File::open("data/maimai/maimai-manual-add.txt")?;
+    let file = File::open(data_path("maimai/maimai-manual-add.txt"))?;
     let lines = BufReader::new(file).lines();
     for line in lines.map_while(Result::ok) {
         let line = line.split('\t').collect_vec();
@@ -1343,11 +1292,12 @@ fn set_additional_info(charts: &mut HashMap<String, MaiInfo>) -> eyre::Result<()
 
 pub fn set_mai_charts() -> Result<HashMap<String, MaiInfo>, Error> {
     let mut charts = HashMap::new();
+    let config = &config().maimai;
 
     set_jp_difficulty(&mut charts)?;
-    set_actual_constants(&mut charts, "data/maimai/prismplus 2025-04-24.json", true)?;
+    set_actual_constants(&mut charts, &data_path(&config.constants_file), true)?;
     // set_jp_constants(&mut charts)?;
-    set_intl_difficulty(&mut charts, false)?;
+    set_intl_difficulty(&mut charts, config.jp_and_intl_version_is_different)?;
     // set_actual_constants(&mut charts, "data/maimai/prism 2025-01-24.json", false)?;
     set_song_info(&mut charts)?;
     set_manual_constants(&mut charts)?;
@@ -1356,184 +1306,19 @@ pub fn set_mai_charts() -> Result<HashMap<String, MaiInfo>, Error> {
     Ok(charts)
 }
 
-fn mai_chart_embed(title: String, ctx: &Context<'_>) -> Result<(String, Option<String>), Error> {
-    let song = ctx.data().mai_charts.get(&title);
-    let song = song.unwrap();
-
-    let mut embed =
-        String::from("Chart info legend:\n**Total notes** / Tap / Hold / Slide / Touch / Break");
-
-    let squares = ["green", "yellow", "red", "purple", "white_large"];
-
-    if !song.dx_sheets.is_empty() {
-        let mut dx_str = String::from("**DX Chart Info:**");
-        for (idx, sheet) in song.dx_sheets.iter().enumerate() {
-            let lvs = song.jp_lv.as_ref().unwrap().dx.as_ref().unwrap();
-            dx_str.push_str(&format!(
-                "\n:{}_square: Lv.{}{}  Designer: {}",
-                squares[idx],
-                lvs.lv(idx),
-                constant_to_string(lvs.get_constant(idx)),
-                sheet.designer.as_ref().unwrap_or(&"-".to_string())
-            ));
-            let total = sheet.brk + sheet.tap + sheet.hold + sheet.slide + sheet.touch;
-            if total >= 99999 {
-                continue;
-            }
-            dx_str.push_str(&format!(
-                "\n**{}** / {} / {} / {} / {} / {}",
-                total, sheet.tap, sheet.hold, sheet.slide, sheet.touch, sheet.brk
-            ));
-        }
-        embed.push_str("\n\n");
-        embed.push_str(&dx_str);
-    }
-
-    if !song.st_sheets.is_empty() {
-        let mut st_str = String::from("**ST Chart Info:**");
-        for (idx, sheet) in song.st_sheets.iter().enumerate() {
-            let lvs = song.jp_lv.as_ref().unwrap().st.as_ref().unwrap();
-            st_str.push_str(&format!(
-                "\n:{}_square: Lv.{}{}  Designer: {}",
-                squares[idx],
-                lvs.lv(idx),
-                constant_to_string(lvs.get_constant(idx)),
-                sheet.designer.as_ref().unwrap_or(&"-".to_string())
-            ));
-            let total = sheet.brk + sheet.tap + sheet.hold + sheet.slide + sheet.touch;
-            if total >= 99999 {
-                continue;
-            }
-            st_str.push_str(&format!(
-                "\n**{}** / {} / {} / {} / {} / {}",
-                total, sheet.tap, sheet.hold, sheet.slide, sheet.touch, sheet.brk
-            ));
-        }
-        embed.push_str("\n\n");
-        embed.push_str(&st_str);
-    }
-
-    Ok((embed, song.jp_jacket.clone()))
-}
-
-/// Get detailed info about song charts in maimai
-#[poise::command(slash_command, prefix_command, rename = "detailed-mai-info")]
+/// Get detailed info about each chart of a maimai song
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "detailed-mai-info",
+    check = "cooldown_check"
+)]
 pub async fn mai_chart(
     ctx: Context<'_>,
     #[description = "Song title e.g. \"Selector\", \"bbb\", etc. You don't have to be exact; try things out!"]
     #[rest]
     title: String,
 ) -> Result<(), Error> {
-    let aliases = &ctx.data().mai_aliases;
-    let actual_title = get_title(
-        &title,
-        aliases,
-        ctx.guild_id()
-            .unwrap_or(poise::serenity_prelude::GuildId(0)),
-    );
-    if actual_title.is_none() {
-        let mut log = ctx.data().alias_log.lock().await;
-        writeln!(log, "{}\tmaimai", title)?;
-        log.sync_all()?;
-        drop(log);
-        let closest = get_closest_title(
-            &title,
-            aliases,
-            ctx.guild_id()
-                .unwrap_or(poise::serenity_prelude::GuildId(0)),
-        );
-        let reply = format!(
-            "I couldn't find the results for **{}**;
-Did you mean **{}** (for **{}**)?
-(P.S. You can also use the `/add-alias` command to add this alias to the bot.)",
-            title, closest.0, closest.1
-        );
-        let sent = ctx
-            .send(|f| {
-                let mut f = f.ephemeral(true).content(reply);
-                if let Context::Application(_) = ctx {
-                    f = f.components(|c| {
-                        let mut button = CreateButton::default();
-                        button.custom_id(closest.0);
-                        button.label(format!("Yes (times out after {} seconds)", 10));
-                        let mut ar = CreateActionRow::default();
-                        ar.add_button(button);
-                        c.set_action_row(ar)
-                    })
-                }
-                f
-            })
-            .await?;
-        let reply = sent.into_message().await?;
-        if let Context::Application(poise_ctx) = ctx {
-            let serenity_ctx = poise_ctx.serenity_context();
-            let mci = match reply
-                .await_component_interaction(serenity_ctx)
-                .timeout(Duration::from_secs(10))
-                .await
-            {
-                Some(ci) => ci,
-                None => {
-                    // ctx.send(|f| f.ephemeral(true).content("Timed out"))
-                    //     .await
-                    //     .unwrap();
-                    return Ok(());
-                }
-            };
-            let actual_title = get_title(
-                &mci.data.custom_id,
-                aliases,
-                ctx.guild_id()
-                    .unwrap_or(poise::serenity_prelude::GuildId(0)),
-            )
-            .unwrap();
-            mci.create_interaction_response(&serenity_ctx.http, |r| {
-                r.kind(InteractionResponseType::ChannelMessageWithSource)
-                    .interaction_response_data(|d| {
-                        // Make the message hidden for other users by setting `ephemeral(true)`.
-                        d.ephemeral(false)
-                            .content(format!("Query by <@{}>", ctx.author().id))
-                            .embed(|f| {
-                                let (description, jacket) =
-                                    mai_chart_embed(actual_title.to_string(), &ctx).unwrap();
-
-                                let mut f = f
-                                    .title(mai_duplicate_alias_to_title(&actual_title))
-                                    .description(description)
-                                    .color(Color::from_rgb(0, 255, 255));
-                                if let Some(jacket) = jacket {
-                                    f = f.thumbnail(format!(
-                                        "{}{}",
-                                        ctx.data().mai_jacket_prefix,
-                                        jacket
-                                    ));
-                                }
-
-                                f
-                            })
-                    })
-            })
-            .await?;
-        }
-        return Ok(());
-    }
-
-    let title = actual_title.unwrap();
-    let (description, jacket) = mai_chart_embed(title.clone(), &ctx)?;
-
-    ctx.send(|f| {
-        f.embed(|f| {
-            let mut f = f
-                .title(mai_duplicate_alias_to_title(&title).replace('*', "\\*"))
-                .description(description)
-                .color(Color::from_rgb(0, 255, 255));
-            if let Some(jacket) = jacket {
-                f = f.thumbnail(format!("{}{}", ctx.data().mai_jacket_prefix, jacket));
-            }
-
-            f
-        })
-    })
-    .await?;
+    chart_template(ctx, &ctx.data().maimai, title).await?;
     Ok(())
 }
@@ -1,19 +1,18 @@
 use poise::serenity_prelude::{self as serenity, GuildId};
 use std::io::Write;
 use std::{
-    fs::{self, File, OpenOptions},
+    collections::HashMap,
+    fs::{File, OpenOptions},
     sync::Arc,
 };
 use tokio::sync::Mutex;
 
-mod utils;
-use utils::*;
-mod maimai;
-use maimai::*;
-mod chuni;
-use chuni::*;
-mod ongeki;
-use ongeki::*;
+use gcm_bot::{
+    artists::*, catalog::*, changes::*, chuni::*, components::event_handler, config, cooldowns::*,
+    cross_game::cross_game_template, crossover::*, custom::*, errors::on_error, images::*,
+    jackets::*, maimai::*, ongeki::*, preferences::*, rating::rating, regions::*, settings::*,
+    updates::*, utils::*, versions,
+};
 
 /// Print help message
 #[poise::command(slash_command, prefix_command)]
@@ -105,28 +104,65 @@ async fn rating_and_play_count(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Display song info from one game, or from every game that has the song
+#[poise::command(slash_command, prefix_command, check = "cooldown_check")]
+async fn info(
+    ctx: Context<'_>,
+    #[description = "The game (default: the server's default game, or every game)"] game: Option<
+        Game,
+    >,
+    #[description = "Show a chart card image instead of text"]
+    #[flag]
+    card: bool,
+    #[description = "Song title. You don't have to be exact; try things out!"]
+    #[rest]
+    title: String,
+) -> Result<(), Error> {
+    let catalog = match game {
+        Some(game) => Some(ctx.data().catalog(game)),
+        None => guild_settings(ctx)
+            .await
+            .default_game
+            .and_then(|id| ctx.data().catalog_by_id(&id)),
+    };
+    let Some(catalog) = catalog else {
+        cross_game_template(ctx, title, card).await?;
+        return Ok(());
+    };
+    info_template(ctx, catalog, title, card).await?;
+    Ok(())
+}
+
 /// Manually add song alias
 #[poise::command(slash_command, prefix_command, rename = "add-alias")]
 async fn add_alias(
     ctx: Context<'_>,
-    #[description = "The game that you're trying to add the alias to"] game: Game,
+    #[description = "The game that you're trying to add the alias to"]
+    #[autocomplete = "autocomplete_game"]
+    game: String,
     #[description = "The actual title of the song - you can use an existing alias"]
     song_title: String,
     #[description = "The alias that you're adding"] alias: String,
 ) -> Result<(), Error> {
-    let (aliases, manual_aliases) = match game {
-        Game::Maimai => (
-            &ctx.data().mai_aliases,
-            &ctx.data().manual_alias_file_maimai,
-        ),
-        Game::Chunithm => (
-            &ctx.data().chuni_aliases,
-            &ctx.data().manual_alias_file_chuni,
-        ),
-        Game::Ongeki => (
-            &ctx.data().ongeki_aliases,
-            &ctx.data().manual_alias_file_ongeki,
-        ),
+    if !can_submit_aliases(ctx).await {
+        ctx.send(|f| {
+            f.ephemeral(true)
+                .content("Alias submission is turned off for you in this server.")
+        })
+        .await?;
+        return Ok(());
+    }
+    let Some((id, catalog)) = ctx.data().find_game(&game) else {
+        ctx.send(|f| {
+            f.ephemeral(true)
+                .content("Unknown game; pick one from the list.")
+        })
+        .await?;
+        return Ok(());
+    };
+    let aliases = catalog.aliases();
+    let Some(manual_aliases) = ctx.data().manual_alias_file(id) else {
+        return Err(format!("No alias submission file for {}", id).into());
     };
     let text = if let Some(title) = get_title(&alias, aliases, ctx.guild_id().unwrap_or(GuildId(0)))
     {
@@ -150,13 +186,13 @@ async fn add_alias(
             let mut log = ctx.data().alias_log.lock().await;
             writeln!(
                 log,
-                "{}\t{}\t{}\t{}\t{}\t{:?}",
+                "{}\t{}\t{}\t{}\t{}\t{}",
                 title,
                 alias,
                 ctx.author().name,
                 ctx.author().discriminator,
                 ctx.guild_id().unwrap_or(GuildId(0)),
-                game
+                id
             )?;
         }
         format!("Alias \"{}\" for song \"{}\" has been submitted!\nThe change will take place at 10AM KST, so please wait until then. Thank you!", alias, title)
@@ -178,109 +214,146 @@ async fn register(ctx: Context<'_>) -> Result<(), Error> {
 
 #[tokio::main]
 async fn main() {
+    let config = match config::init()
+        .and_then(|config| config.check_sources().map(|_| config))
+        .and_then(|config| versions::init().map(|_| config))
+    {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    let custom_configs = match load_custom_configs(&config.path(CUSTOM_GAME_DIR)) {
+        Ok(configs) => configs,
+        Err(e) => {
+            eprintln!("Invalid custom game: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut commands = vec![
+        info(),
+        mai_info(),
+        mai_jacket(),
+        mai_chart(),
+        chuni_info(),
+        chuni_jacket(),
+        chuni_chart(),
+        ongeki_info(),
+        ongeki_jacket(),
+        ongeki_chart(),
+        add_alias(),
+        changes(),
+        region_diff_command(),
+        crossover(),
+        artist(),
+        rating(),
+        subscribe_updates(),
+        unsubscribe_updates(),
+        set_cooldown(),
+        exempt_channel(),
+        unexempt_channel(),
+        cooldowns(),
+        settings(),
+        preferences(),
+        help(),
+        help_kr(),
+        how_to_improve(),
+        how_to_actually_improve(),
+        break_the_moonbase_with_beam(),
+        rating_and_play_count(),
+        register(),
+    ];
+    for config in &custom_configs {
+        commands.extend(custom_commands(config));
+    }
+    commands.iter_mut().for_each(required_options_first);
+
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                mai_info(),
-                mai_jacket(),
-                mai_chart(),
-                chuni_info(),
-                chuni_jacket(),
-                ongeki_info(),
-                ongeki_jacket(),
-                add_alias(),
-                help(),
-                help_kr(),
-                how_to_improve(),
-                how_to_actually_improve(),
-                break_the_moonbase_with_beam(),
-                rating_and_play_count(),
-                register(),
-            ],
+            commands,
+            owners: config
+                .owners
+                .iter()
+                .map(|&id| serenity::UserId(id))
+                .collect(),
+            on_error: |error| Box::pin(on_error(error)),
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(event_handler(ctx, event, framework, data))
+            },
             ..Default::default()
         })
         .token(std::env::var("DISCORD_TOKEN").expect("missing DISCORD_TOKEN"))
         .intents(serenity::GatewayIntents::non_privileged())
-        .setup(move |_ctx, _ready, _framework| {
+        .setup(move |ctx, _ready, _framework| {
             Box::pin(async move {
-                let mai_charts = set_mai_charts()?;
-                let mai_aliases = set_aliases(mai_charts.keys(), "maimai")?;
-                let chuni_charts = set_chuni_charts()?;
-                let chuni_aliases = set_aliases(chuni_charts.keys(), "chuni")?;
-                let ongeki_charts = set_ongeki_charts()?;
-                let ongeki_aliases = set_aliases(ongeki_charts.keys(), "ongeki")?;
-                // let cooldown_server_ids = {
-                //     let file = File::open("data/cooldown-server-ids.txt")?;
-                //     BufReader::new(file)
-                //         .lines()
-                //         .map(|l| l.unwrap().parse::<u64>())
-                //         .filter(|b| b.is_ok())
-                //         .map(|l| GuildId(l.unwrap()))
-                //         .collect::<HashSet<_>>()
-                // };
-                // let cooldown_channel_exception_ids = {
-                //     let file = File::open("data/cooldown-channel-exception-ids.txt")?;
-                //     BufReader::new(file)
-                //         .lines()
-                //         .map(|l| l.unwrap().parse::<u64>())
-                //         .filter(|b| b.is_ok())
-                //         .map(|l| ChannelId(l.unwrap()))
-                //         .collect::<HashSet<_>>()
-                // };
-                // let timestamps = Arc::new(Mutex::new(
-                //     cooldown_server_ids
-                //         .iter()
-                //         .map(|k| (*k, (HashMap::new(), HashMap::new())))
-                //         .collect(),
-                // ));
+                let maimai = Catalog::load(set_mai_charts()?, "maimai")?;
+                let chuni = Catalog::load(set_chuni_charts()?, "chuni")?;
+                let ongeki = Catalog::load(set_ongeki_charts()?, "ongeki")?;
+                let mut custom = HashMap::new();
+                for config in custom_configs {
+                    custom.insert(config.id.clone(), set_custom_catalog(config)?);
+                }
                 let alias_log = Arc::new(Mutex::new(File::create(format!(
                     "alias_log_{}.txt",
                     chrono::prelude::Utc::now()
                 ))?));
 
-                let manual_alias_file_maimai = Arc::new(Mutex::new(
-                    OpenOptions::new()
-                        .append(true)
-                        .open("data/aliases/manual/maimai.tsv")
-                        .unwrap(),
-                ));
-                let manual_alias_file_chuni = Arc::new(Mutex::new(
-                    OpenOptions::new()
-                        .append(true)
-                        .open("data/aliases/manual/chuni.tsv")
-                        .unwrap(),
-                ));
-                let manual_alias_file_ongeki = Arc::new(Mutex::new(
-                    OpenOptions::new()
+                let mut manual_alias_files = HashMap::new();
+                let ids = ["maimai", "chuni", "ongeki"]
+                    .into_iter()
+                    .chain(custom.keys().map(String::as_str));
+                for id in ids {
+                    let file = OpenOptions::new()
                         .append(true)
-                        .open("data/aliases/manual/ongeki.tsv")
-                        .unwrap(),
-                ));
+                        .open(config.path(format!("aliases/manual/{}.tsv", id)))?;
+                    manual_alias_files.insert(id.to_string(), Arc::new(Mutex::new(file)));
+                }
 
-                Ok(Data {
-                    mai_charts,
-                    mai_aliases,
-                    mai_jacket_prefix: fs::read_to_string("data/maimai/maimai-jacket-prefix.txt")?,
+                let mut data = Data {
+                    maimai,
+                    chuni,
+                    ongeki,
+                    custom,
+                    previous_snapshot: None,
+                    subscriptions: Arc::new(Mutex::new(Subscriptions::load(
+                        &config.path(SUBSCRIPTIONS_FILE),
+                    )?)),
+                    cooldowns: Arc::new(Mutex::new(Cooldowns::load(&config.path(COOLDOWNS_FILE))?)),
+                    settings: Arc::new(Mutex::new(Settings::load(&config.path(SETTINGS_FILE))?)),
+                    preferences: Arc::new(Mutex::new(Preferences::load(
+                        &config.path(PREFERENCES_FILE),
+                    )?)),
+                    crossover: CrossoverData::load(&config.path(CROSSOVER_FILE))?,
+                    artists: ArtistIndex::default(),
+                    jackets: JacketCache::new(config.path(JACKET_DIR)),
+                    fonts: Arc::new(Fonts::load(&config.path(FONT_DIR))?),
 
-                    chuni_charts,
-                    chuni_aliases,
-                    chuni_jacket_prefix: fs::read_to_string("data/chuni/chuni-jacket-prefix.txt")?,
+                    manual_alias_files,
 
-                    ongeki_charts,
-                    ongeki_aliases,
-                    ongeki_jacket_prefix: fs::read_to_string(
-                        "data/ongeki/ongeki-jacket-prefix.txt",
-                    )?,
+                    alias_log,
+                };
+                data.artists = ArtistIndex::build(
+                    Charts::from_data(&data),
+                    ArtistAliases::load(&config.path(ARTIST_ALIASES_FILE))?,
+                );
+                let live = Snapshot::capture(&data.catalogs());
+                data.previous_snapshot = rotate_snapshots(&config.path(SNAPSHOT_DIR), &live)?;
+                let data = Arc::new(data);
+                tokio::spawn(check_updates(ctx.http.clone(), data.clone()));
 
-                    manual_alias_file_maimai,
-                    manual_alias_file_chuni,
-                    manual_alias_file_ongeki,
+                #[cfg(feature = "http")]
+                if let Ok(addr) = std::env::var("HTTP_ADDR") {
+                    let addr = addr.parse()?;
+                    let data = data.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = gcm_bot::http::serve(data, addr).await {
+                            eprintln!("HTTP server stopped: {e}");
+                        }
+                    });
+                }
 
-                    // cooldown_server_ids,
-                    // cooldown_channel_exception_ids,
-                    // timestamps,
-                    alias_log,
-                })
+                Ok(data)
             })
         });
 
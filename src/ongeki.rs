@@ -1,13 +1,16 @@
 use std::{
     collections::HashMap,
     fs::{self},
-    sync::Arc,
 };
 
-use eyre::bail;
+use chrono::NaiveDate;
+use eyre::{bail, eyre};
 use itertools::Itertools;
 
-use crate::utils::*;
+use crate::{
+    catalog::*, chart_view::chart_template, config::*, cooldowns::cooldown_check, document::Field,
+    utils::*, versions::*,
+};
 
 lazy_static::lazy_static! {
     static ref LV_SOURCE_REPLACEMENT: HashMap<String, String> = {
@@ -196,30 +199,63 @@ lazy_static::lazy_static! {
     ].iter().map(|s| s.to_string()).collect();
 }
 
-fn get_ongeki_embed(title: String, ctx: &Context<'_>) -> eyre::Result<(String, Option<String>)> {
-    let song = ctx.data().ongeki_charts.get(&title);
-
-    let song = song.unwrap();
-    let date = song.date;
-    let version = if date >= 20220303 {
-        "bright MEMORY"
-    } else if date >= 20211021 {
-        "bright"
-    } else if date >= 20210331 {
-        "R.E.D. PLUS"
-    } else if date >= 20200930 {
-        "R.E.D."
-    } else if date >= 20200220 {
-        "SUMMER PLUS"
-    } else if date >= 20190822 {
-        "SUMMER"
-    } else if date >= 20190207 {
-        "PLUS"
-    } else if date >= 20180726 {
-        "オンゲキ"
-    } else {
-        unreachable!()
-    };
+impl SongInfo for OngekiInfo {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn jp_jacket(&self) -> Option<&str> {
+        self.jp_jacket.as_deref()
+    }
+
+    fn version(&self) -> Option<String> {
+        ongeki_version(self.date).map(|v| v.name.clone())
+    }
+
+    fn chart_entries(&self) -> Vec<ChartEntry> {
+        self.lv
+            .as_ref()
+            .map(|lv| difficulty_entries(lv, Region::Jp, None, ongeki_version(self.date)))
+            .unwrap_or_default()
+    }
+}
+
+impl GameSong for OngekiInfo {
+    const NAME: &'static str = "O.N.G.E.K.I.";
+    const COLOR: (u8, u8, u8) = (255, 127, 255);
+    const DIFFICULTY_NAMES: &'static [&'static str] =
+        &["BASIC", "ADVANCED", "EXPERT", "MASTER", "LUNATIC"];
+
+    fn display_title(title: &str) -> String {
+        ongeki_duplicate_alias_to_title(title)
+    }
+
+    /// O.N.G.E.K.I. is JP only, so `options.region` doesn't matter.
+    fn render(&self, title: &str, options: &RenderOptions) -> eyre::Result<(String, Vec<Field>)> {
+        get_ongeki_embed(self, title, options)
+    }
+}
+
+/// The version that was current when a song with `date` (yyyymmdd) came out.
+fn ongeki_version(date: usize) -> Option<&'static Version> {
+    let date = NaiveDate::parse_from_str(&date.to_string(), "%Y%m%d").ok()?;
+    versions().game("ongeki")?.at(date, Region::Jp)
+}
+
+fn get_ongeki_embed(
+    song: &OngekiInfo,
+    title: &str,
+    options: &RenderOptions,
+) -> eyre::Result<(String, Vec<Field>)> {
+    let version = song.version().unwrap_or_else(|| "?".to_string());
 
     let mut vs = song.character.clone();
     if CHARACTER_REPLACEMENT.contains_key(&song.character) {
@@ -255,22 +291,29 @@ fn get_ongeki_embed(title: String, ctx: &Context<'_>) -> eyre::Result<(String, O
     }
     .to_string();
 
-    let description = format!(
-        "{}**Artist:** {}
-**Version**: {}
-{}
-**Level:** {}",
-        description,
-        song.artist.replace('*', "\\*"),
-        version,
-        vs_text,
-        level_description(song.lv.as_ref().unwrap(), &title)
-    );
-
-    Ok((description, song.jp_jacket.clone()))
+    let details = if options.compact {
+        "".to_string()
+    } else {
+        format!(
+            "**Artist:** {}\n**Version**: {}\n{}\n",
+            song.artist.replace('*', "\\*"),
+            version,
+            vs_text
+        )
+    };
+    let lv = song
+        .lv
+        .as_ref()
+        .ok_or_else(|| eyre!("No levels for {title}"))?;
+    let levels = level_description(lv, title, options);
+
+    Ok((
+        format!("{}{}", description, details).trim().to_string(),
+        vec![Field::new("Level", levels)],
+    ))
 }
 
-fn ongeki_duplicate_alias_to_title(title: &String) -> String {
+fn ongeki_duplicate_alias_to_title(title: &str) -> String {
     if title == "Singularity (Arcaea)" || title == "Singularity (MJ)" {
         "Singularity".to_string()
     } else if title == "Hand in Hand (deleted)" {
@@ -283,81 +326,66 @@ fn ongeki_duplicate_alias_to_title(title: &String) -> String {
 }
 
 /// Get Ongeki song info
-#[poise::command(slash_command, prefix_command, rename = "ongeki-info")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "ongeki-info",
+    check = "cooldown_check"
+)]
 pub async fn ongeki_info(
     ctx: Context<'_>,
+    #[description = "Show a chart card image instead of text"]
+    #[flag]
+    card: bool,
     #[description = "Song title e.g. \"w4\", \"Apollo\", etc. You don't have to be exact; try things out!"]
     #[rest]
     title: String,
 ) -> Result<(), Error> {
-    info_template(
-        ctx,
-        title,
-        Game::Ongeki,
-        Arc::new(get_ongeki_embed),
-        (255, 127, 255),
-        Arc::new(ongeki_duplicate_alias_to_title),
-    )
-    .await?;
+    info_template(ctx, &ctx.data().ongeki, title, card).await?;
     // info_template!("ongeki", "255, 127, 255", "ctx.data().ongeki_jacket_prefix");
     Ok(())
 }
 
-fn level_description(lv: &Difficulty, title: &str) -> String {
-    let title = title.replace(" -", " ");
-    let title = title.strip_prefix('-').unwrap_or(&title);
-    let title = urlencoding::encode(title);
-    if !lv.bas.is_empty() {
-        format!(
-        // "BAS **{}{}**/ADV **{}{}**/EXP **{}{}**/MAS **{}{}**{}",
-        "[B](https://www.youtube.com/results?search_query=オンゲキ+{}+BASIC) **{}**{} / [A](https://www.youtube.com/results?search_query=オンゲキ+{}+ADVANCED) **{}**{} / [E](https://www.youtube.com/results?search_query=オンゲキ+{}+EXPERT) **{}**{} / [M](https://www.youtube.com/results?search_query=オンゲキ+{}+MASTER) **{}**{}{}",
-        title,
-        lv.bas,
-        constant_to_string(lv.bas_c),
-        title,
-        lv.adv,
-        constant_to_string(lv.adv_c),
-        title,
-        lv.exp,
-        constant_to_string(lv.exp_c),
-        title,
-        lv.mas,
-        constant_to_string(lv.mas_c),
-        if let Some(rem) = &lv.extra {
-            format!(" / [L](https://www.youtube.com/results?search_query=オンゲキ+{}+LUNATIC) **{}**{}", title, rem, constant_to_string(lv.extra_c))
-        } else {
-            "".to_string()
-        }
-    )
-    } else {
-        // Assume level has LUNATIC
-        if let Some(rem) = &lv.extra {
-            format!(
-                "[L](https://www.youtube.com/results?search_query=オンゲキ+{}+LUNATIC) **{}**{}",
-                title,
-                rem,
-                constant_to_string(lv.extra_c)
-            )
-        } else {
-            "".to_string()
-        }
-    }
+/// Get detailed info about each chart of a Ongeki song
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "detailed-ongeki-info",
+    check = "cooldown_check"
+)]
+pub async fn ongeki_chart(
+    ctx: Context<'_>,
+    #[description = "Song title e.g. \"w4\", \"Apollo\", etc. You don't have to be exact; try things out!"]
+    #[rest]
+    title: String,
+) -> Result<(), Error> {
+    chart_template(ctx, &ctx.data().ongeki, title).await?;
+    Ok(())
+}
+
+fn level_description(lv: &Difficulty, title: &str, options: &RenderOptions) -> String {
+    difficulty_line(lv, "オンゲキ", title, "LUNATIC", options)
 }
 
 /// Get Ongeki song jacket
-#[poise::command(slash_command, prefix_command, rename = "ongeki-jacket")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "ongeki-jacket",
+    check = "cooldown_check"
+)]
 pub async fn ongeki_jacket(
     ctx: Context<'_>,
     #[description = "Song title e.g. \"w4\", \"Apollo\", etc. You don't have to be exact; try things out!"]
     #[rest]
     title: String,
 ) -> Result<(), Error> {
-    jacket_template(ctx, title, Game::Ongeki).await?;
+    jacket_template(ctx, &ctx.data().ongeki, title).await?;
     Ok(())
 }
 
 fn set_jp_difficulty(charts: &mut HashMap<String, OngekiInfo>) -> eyre::Result<()> {
-    let url = fs::read_to_string("data/ongeki/ongeki-url.txt")?;
+    let url = config().source(&config().ongeki.url, "ongeki/ongeki-url.txt")?;
     let url = url.trim();
     let s = get_curl(url);
     let songs: serde_json::Value = serde_json::from_str(&s)?;
@@ -442,7 +470,7 @@ fn set_jp_difficulty(charts: &mut HashMap<String, OngekiInfo>) -> eyre::Result<(
 }
 
 fn set_deleted_songs(charts: &mut HashMap<String, OngekiInfo>) -> eyre::Result<()> {
-    let url = fs::read_to_string("data/ongeki/ongeki-deleted.txt")?;
+    let url = config().source(&config().ongeki.deleted_url, "ongeki/ongeki-deleted.txt")?;
     let url = url.trim();
     let s = get_curl(url);
     let songs: serde_json::Value = serde_json::from_str(&s)?;
@@ -540,7 +568,7 @@ fn set_deleted_songs(charts: &mut HashMap<String, OngekiInfo>) -> eyre::Result<(
 
 #[allow(clippy::get_first)]
 fn set_constants(charts: &mut HashMap<String, OngekiInfo>) -> eyre::Result<()> {
-    let url = fs::read_to_string("data/ongeki/ongeki-info.txt")?;
+    let url = config().source(&config().ongeki.info_url, "ongeki/ongeki-info.txt")?;
     let url = url.trim();
     let s = get_curl(url);
 
@@ -676,7 +704,7 @@ fn set_constants(charts: &mut HashMap<String, OngekiInfo>) -> eyre::Result<()> {
 
 fn set_vs_character_level_element(charts: &mut HashMap<String, OngekiInfo>) -> eyre::Result<()> {
     // Get VS character level and element.
-    let s = fs::read_to_string("data/ongeki/ongeki-curl.html")?;
+    let s = fs::read_to_string(data_path("ongeki/ongeki-curl.html"))?;
     let dom = tl::parse(&s, tl::ParserOptions::default())?;
     let parser = dom.parser();
     let element = dom
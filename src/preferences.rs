@@ -0,0 +1,150 @@
+//! Per-user display preferences, set with `/preferences` and kept in `data/preferences.json`.
+//! They apply on top of the guild settings wherever the user queries songs, DMs included.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use poise::serenity_prelude::UserId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    catalog::RenderOptions,
+    config::data_path,
+    settings::{GuildSettings, RegionSetting},
+    utils::*,
+};
+
+/// Relative to `data_dir`.
+pub const PREFERENCES_FILE: &str = "preferences.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserPreferences {
+    /// Home region; `None` to follow the server setting.
+    pub region: Option<RegionSetting>,
+    pub constants: bool,
+    pub links: bool,
+    pub compact: bool,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            region: None,
+            constants: true,
+            links: true,
+            compact: false,
+        }
+    }
+}
+
+impl UserPreferences {
+    /// The render options for this user in a guild with `settings`.
+    pub fn render_options(&self, settings: &GuildSettings) -> RenderOptions {
+        let guild = settings.render_options();
+        RenderOptions {
+            region: self.region.map_or(guild.region, RegionSetting::region),
+            constants: self.constants,
+            links: self.links,
+            compact: self.compact,
+        }
+    }
+}
+
+/// Preferences of every user that changed any.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Preferences {
+    users: BTreeMap<u64, UserPreferences>,
+}
+
+impl Preferences {
+    /// Empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The preferences of `user`, or the defaults.
+    pub fn get(&self, user: UserId) -> UserPreferences {
+        self.users.get(&user.0).cloned().unwrap_or_default()
+    }
+
+    /// Changes the preferences of `user`; users back at the defaults aren't stored.
+    pub fn update(&mut self, user: UserId, change: impl FnOnce(&mut UserPreferences)) {
+        let preferences = self.users.entry(user.0).or_default();
+        change(preferences);
+        if *preferences == UserPreferences::default() {
+            self.users.remove(&user.0);
+        }
+    }
+}
+
+/// How to render songs for the author of `ctx` in a guild with `settings`.
+pub async fn render_options(ctx: Context<'_>, settings: &GuildSettings) -> RenderOptions {
+    ctx.data()
+        .preferences
+        .lock()
+        .await
+        .get(ctx.author().id)
+        .render_options(settings)
+}
+
+/// Choose how info commands show songs to you
+#[poise::command(slash_command, prefix_command)]
+pub async fn preferences(
+    ctx: Context<'_>,
+    #[description = "The region whose levels you want to see (default: the server's setting)"]
+    region: Option<RegionSetting>,
+    #[description = "Show chart constants"] constants: Option<bool>,
+    #[description = "Link difficulties to YouTube searches"] links: Option<bool>,
+    #[description = "Only show levels, without artist, version and BPM"] compact: Option<bool>,
+    #[description = "Go back to the defaults"]
+    #[flag]
+    reset: bool,
+) -> Result<(), Error> {
+    let user = ctx.author().id;
+    let preferences = {
+        let mut all = ctx.data().preferences.lock().await;
+        all.update(user, |preferences| {
+            if reset {
+                *preferences = UserPreferences::default();
+            }
+            if let Some(region) = region {
+                preferences.region = Some(region);
+            }
+            if let Some(constants) = constants {
+                preferences.constants = constants;
+            }
+            if let Some(links) = links {
+                preferences.links = links;
+            }
+            if let Some(compact) = compact {
+                preferences.compact = compact;
+            }
+        });
+        all.save(&data_path(PREFERENCES_FILE))?;
+        all.get(user)
+    };
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let text = format!(
+        "**Region:** {}\n**Constants:** {}\n**YouTube links:** {}\n**Output:** {}",
+        preferences
+            .region
+            .map_or("server setting".to_string(), |region| region.to_string()),
+        on_off(preferences.constants),
+        on_off(preferences.links),
+        if preferences.compact {
+            "compact"
+        } else {
+            "full"
+        },
+    );
+    ctx.send(|f| f.ephemeral(true).content(text)).await?;
+    Ok(())
+}
@@ -0,0 +1,602 @@
+//! Best-rating sheets from a player's scores: maimai's 35 old + 15 new charts, CHUNITHM's
+//! best 30 + new 20 and O.N.G.E.K.I.'s best 30 + new 15, with the image players share.
+//! Recent frames depend on play history, which a score list doesn't have, so they are left out.
+//!
+//! Scores come as one `title<TAB>difficulty<TAB>score[<TAB>DX|ST]` line per chart. Titles go
+//! through the usual alias lookup, and constants come from the chart data; charts without a
+//! known constant use the lowest constant of their level and are marked with "~".
+
+use std::{borrow::Cow, collections::HashMap};
+
+use chrono::NaiveDate;
+use eyre::{bail, eyre, WrapErr};
+use image::{imageops, RgbaImage};
+use poise::serenity_prelude::{self as serenity, AttachmentType, GuildId};
+
+use crate::{
+    catalog::*,
+    cooldowns::cooldown_check,
+    images::{difficulty_color, encode_png, fill_rect, text_color, Color, Fonts},
+    settings::query_settings,
+    utils::{Context, Error, Game},
+    versions::versions,
+};
+
+/// How a game turns scores into rating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rules {
+    Maimai,
+    Chunithm,
+    Ongeki,
+}
+
+/// Achievement (in 0.0001%) at which each maimai rank starts, and its rating factor times 10.
+const MAI_RANKS: &[(i64, i64)] = &[
+    (1_005_000, 224),
+    (1_000_000, 216),
+    (995_000, 211),
+    (990_000, 208),
+    (980_000, 203),
+    (970_000, 200),
+    (940_000, 168),
+    (900_000, 152),
+    (800_000, 136),
+    (750_000, 120),
+    (700_000, 112),
+    (600_000, 96),
+    (500_000, 80),
+    (400_000, 64),
+    (300_000, 48),
+    (200_000, 32),
+    (100_000, 16),
+];
+
+impl Rules {
+    pub fn for_game(id: &str) -> Option<Self> {
+        match id {
+            "maimai" => Some(Rules::Maimai),
+            "chuni" => Some(Rules::Chunithm),
+            "ongeki" => Some(Rules::Ongeki),
+            _ => None,
+        }
+    }
+
+    /// (name, size) of the frame for older charts and the one for new charts.
+    pub fn frames(self) -> [(&'static str, usize); 2] {
+        match self {
+            Rules::Maimai => [("Old", 35), ("New", 15)],
+            Rules::Chunithm => [("Best", 30), ("New", 20)],
+            Rules::Ongeki => [("Best", 30), ("New", 15)],
+        }
+    }
+
+    /// Lowest constant of a "+" level above the plain level.
+    fn plus(self) -> f32 {
+        match self {
+            Rules::Maimai => 0.6,
+            Rules::Chunithm => 0.5,
+            Rules::Ongeki => 0.7,
+        }
+    }
+
+    /// Achievement percentage for maimai ("100.5", "100.5000%"),
+    /// points for CHUNITHM and technical score for O.N.G.E.K.I. ("1009000", "1,009,000").
+    pub fn parse_score(self, score: &str) -> eyre::Result<i64> {
+        let score = score.trim().trim_end_matches('%').replace(',', "");
+        match self {
+            Rules::Maimai => {
+                let achievement: f64 = score.parse()?;
+                if !(0. ..=101.).contains(&achievement) {
+                    bail!("achievement {} is not between 0% and 101%", achievement);
+                }
+                Ok((achievement * 10000.).round() as i64)
+            }
+            Rules::Chunithm | Rules::Ongeki => {
+                let points: i64 = score.parse()?;
+                if !(0..=1_010_000).contains(&points) {
+                    bail!("score {} is not between 0 and 1,010,000", points);
+                }
+                Ok(points)
+            }
+        }
+    }
+
+    pub fn format_score(self, score: i64) -> String {
+        match self {
+            Rules::Maimai => format!("{}.{:04}%", score / 10000, score % 10000),
+            Rules::Chunithm | Rules::Ongeki => format!(
+                "{},{:03},{:03}",
+                score / 1_000_000,
+                score / 1000 % 1000,
+                score % 1000
+            ),
+        }
+    }
+
+    /// Rating of one chart: whole points for maimai, hundredths for CHUNITHM and O.N.G.E.K.I.
+    pub fn chart_rating(self, constant: f32, score: i64) -> i64 {
+        match self {
+            Rules::Maimai => {
+                let constant = (constant * 10.).round() as i64;
+                let achievement = score.min(1_005_000);
+                let factor = MAI_RANKS
+                    .iter()
+                    .find(|(start, _)| score >= *start)
+                    .map_or(0, |(_, factor)| *factor);
+                constant * achievement * factor / 100_000_000
+            }
+            Rules::Chunithm => {
+                let c = (constant * 100.).round() as i64;
+                let rating = match score {
+                    1_009_000.. => c + 215,
+                    1_007_500.. => c + 200 + (score - 1_007_500) / 100,
+                    1_005_000.. => c + 150 + (score - 1_005_000) / 50,
+                    1_000_000.. => c + 100 + (score - 1_000_000) / 100,
+                    975_000.. => c + (score - 975_000) / 250,
+                    925_000.. => c - 300 + (score - 925_000) * 300 / 50_000,
+                    900_000.. => c - 500 + (score - 900_000) * 200 / 25_000,
+                    800_000.. => (c - 500) / 2 + (score - 800_000) * ((c - 500) / 2) / 100_000,
+                    500_000.. => (c - 500) / 2 * (score - 500_000) / 300_000,
+                    _ => 0,
+                };
+                rating.max(0)
+            }
+            Rules::Ongeki => {
+                let c = (constant * 100.).round() as i64;
+                let rating = match score {
+                    1_007_500.. => c + 200,
+                    1_000_000.. => c + 150 + (score - 1_000_000) / 150,
+                    990_000.. => c + 100 + (score - 990_000) / 200,
+                    970_000.. => c + (score - 970_000) / 200,
+                    900_000.. => c - 400 + (score - 900_000) / 175,
+                    800_000.. => c - 600 + (score - 800_000) / 500,
+                    _ => 0,
+                };
+                rating.max(0)
+            }
+        }
+    }
+
+    /// Player rating from the sum of both frames.
+    pub fn total(self, sum: i64) -> i64 {
+        match self {
+            Rules::Maimai => sum,
+            Rules::Chunithm | Rules::Ongeki => {
+                let [(_, best), (_, new)] = self.frames();
+                sum / (best + new) as i64
+            }
+        }
+    }
+
+    pub fn format_rating(self, rating: i64) -> String {
+        match self {
+            Rules::Maimai => rating.to_string(),
+            Rules::Chunithm | Rules::Ongeki => format!("{}.{:02}", rating / 100, rating % 100),
+        }
+    }
+}
+
+/// A chart the player has a score on, with its rating.
+#[derive(Debug, Clone)]
+pub struct RatedChart {
+    /// Display title.
+    pub title: String,
+    pub jacket: Option<String>,
+    pub jacket_url: Option<String>,
+    pub chart: ChartEntry,
+    pub constant: f32,
+    /// Whether `constant` is a guess from the level.
+    pub estimated: bool,
+    pub score: i64,
+    pub rating: i64,
+}
+
+pub struct RatingSheet {
+    pub rules: Rules,
+    pub game: String,
+    pub color: Color,
+    pub difficulty_names: Vec<String>,
+    pub region: Region,
+    /// Best charts of the older and the new frame, highest rating first.
+    pub frames: [Vec<RatedChart>; 2],
+    /// Score lines that could not be used, with the reason.
+    pub skipped: Vec<String>,
+}
+
+/// Difficulty index of `name` in `names`: the full name or its first letters,
+/// ignoring case and punctuation ("MAS", "Re:MASTER", "remaster").
+fn difficulty_index(name: &str, names: &[&str]) -> Option<usize> {
+    let key = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let name = key(name);
+    names.iter().position(|n| key(n) == name).or_else(|| {
+        names
+            .iter()
+            .position(|n| name.len() >= 3 && key(n).starts_with(&name))
+    })
+}
+
+impl RatingSheet {
+    /// Rates the score lines in `scores` against `catalog`; only the best score of each chart counts.
+    pub fn build(
+        catalog: &dyn GameCatalog,
+        region: Region,
+        scores: &str,
+        server: GuildId,
+    ) -> eyre::Result<Self> {
+        let today = chrono::Local::now().date_naive();
+        Self::build_at(catalog, region, scores, server, today)
+    }
+
+    /// Like `build`, with the new-songs frame as of `date`.
+    pub fn build_at(
+        catalog: &dyn GameCatalog,
+        region: Region,
+        scores: &str,
+        server: GuildId,
+        date: NaiveDate,
+    ) -> eyre::Result<Self> {
+        let rules = Rules::for_game(catalog.id())
+            .ok_or_else(|| eyre!("Rating is not supported for {}", catalog.name()))?;
+        let names = catalog.difficulty_names();
+        let mut best: HashMap<(String, Option<&'static str>, usize), RatedChart> = HashMap::new();
+        let mut skipped = vec![];
+
+        for (number, line) in scores.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rated = (|| {
+                let fields = line.split('\t').map(str::trim).collect::<Vec<_>>();
+                let [title, difficulty, score, rest @ ..] = fields.as_slice() else {
+                    bail!("expected title, difficulty and score separated by tabs");
+                };
+                let key = catalog
+                    .lookup(title, server)
+                    .ok_or_else(|| eyre!("no song found for \"{}\"", title))?;
+                let song = catalog
+                    .song(&key)
+                    .ok_or_else(|| eyre!("no data for {}", key))?;
+                let difficulty = difficulty_index(difficulty, &names)
+                    .ok_or_else(|| eyre!("unknown difficulty \"{}\"", difficulty))?;
+                let score = rules
+                    .parse_score(score)
+                    .wrap_err_with(|| format!("bad score \"{}\"", score))?;
+                let chart_type = rest.first().filter(|t| !t.is_empty());
+                let charts = song
+                    .chart_entries()
+                    .into_iter()
+                    .filter(|c| c.region == region && c.difficulty == difficulty)
+                    .filter(|c| {
+                        chart_type.is_none_or(|t| {
+                            c.chart_type.is_some_and(|ct| ct.eq_ignore_ascii_case(t))
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let chart = match charts.as_slice() {
+                    [chart] => chart.clone(),
+                    [] => bail!("{} has no such chart in {:?}", key, region),
+                    _ => bail!("{} has both DX and ST charts; add DX or ST", key),
+                };
+                let (constant, estimated) = match chart.constant {
+                    Some(constant) => (constant.0, false),
+                    None => {
+                        let level = chart.level.trim_end_matches('+');
+                        let plus = if chart.level.ends_with('+') {
+                            rules.plus()
+                        } else {
+                            0.
+                        };
+                        let level: f32 = level
+                            .parse()
+                            .map_err(|_| eyre!("no constant for level {}", chart.level))?;
+                        (level + plus, true)
+                    }
+                };
+                Ok((
+                    (key.clone(), chart.chart_type, difficulty),
+                    RatedChart {
+                        title: catalog.display_title(&key),
+                        jacket: song.jp_jacket().map(String::from),
+                        jacket_url: catalog.jacket_url(&key),
+                        rating: rules.chart_rating(constant, score),
+                        chart,
+                        constant,
+                        estimated,
+                        score,
+                    },
+                ))
+            })();
+            match rated {
+                Ok((key, rated)) => {
+                    if best.get(&key).is_none_or(|b| b.score < rated.score) {
+                        best.insert(key, rated);
+                    }
+                }
+                Err(e) => skipped.push(format!("line {}: {}", number + 1, e)),
+            }
+        }
+
+        let new_frame = versions()
+            .game(catalog.id())
+            .map(|list| list.new_frame_at(date, region))
+            .unwrap_or_default();
+        let (mut new, mut old): (Vec<_>, Vec<_>) = best
+            .into_values()
+            .partition(|r| r.chart.version.is_some_and(|v| new_frame.contains(&v)));
+        let [(_, old_size), (_, new_size)] = rules.frames();
+        for (frame, size) in [(&mut old, old_size), (&mut new, new_size)] {
+            frame.sort_by(|a, b| {
+                b.rating
+                    .cmp(&a.rating)
+                    .then(b.score.cmp(&a.score))
+                    .then(a.title.cmp(&b.title))
+            });
+            frame.truncate(size);
+        }
+        Ok(Self {
+            rules,
+            game: catalog.name().to_string(),
+            color: catalog.color(),
+            difficulty_names: names.into_iter().map(String::from).collect(),
+            region,
+            frames: [old, new],
+            skipped,
+        })
+    }
+
+    pub fn frame_sum(&self, frame: usize) -> i64 {
+        self.frames[frame].iter().map(|r| r.rating).sum()
+    }
+
+    pub fn total(&self) -> i64 {
+        self.rules.total(self.frame_sum(0) + self.frame_sum(1))
+    }
+}
+
+const COLUMNS: u32 = 5;
+const CELL_WIDTH: u32 = 270;
+const CELL_HEIGHT: u32 = 104;
+const GAP: u32 = 8;
+const PADDING: u32 = 24;
+const HEADER: u32 = 96;
+const SECTION_HEADER: u32 = 44;
+const THUMBNAIL: u32 = 84;
+
+const BACKGROUND: Color = (32, 34, 40);
+const CELL: Color = (48, 51, 60);
+const TEXT: Color = (240, 240, 240);
+const SUBTLE: Color = (170, 170, 180);
+
+impl RatingSheet {
+    /// The sheet as PNG bytes. `jacket` gives the jacket image of a chart, if there is one.
+    pub fn render(
+        &self,
+        fonts: &Fonts,
+        jacket: impl Fn(&RatedChart) -> Option<Vec<u8>>,
+    ) -> eyre::Result<Vec<u8>> {
+        let width = PADDING * 2 + COLUMNS * CELL_WIDTH + (COLUMNS - 1) * GAP;
+        let section_height = |charts: usize| {
+            let rows = (charts as u32).div_ceil(COLUMNS).max(1);
+            SECTION_HEADER + rows * (CELL_HEIGHT + GAP)
+        };
+        let height = HEADER
+            + self
+                .frames
+                .iter()
+                .map(|frame| section_height(frame.len()))
+                .sum::<u32>()
+            + PADDING;
+        let mut image = RgbaImage::new(width, height);
+        fill_rect(&mut image, (0, 0), (width, height), BACKGROUND);
+        fill_rect(&mut image, (0, 0), (width, 8), self.color);
+
+        let region = match self.region {
+            Region::Jp => "JP",
+            Region::Intl => "International",
+        };
+        fonts.draw_text(
+            &mut image,
+            &format!("{} rating ({})", self.game, region),
+            24.,
+            (PADDING as f32, 26.),
+            SUBTLE,
+        );
+        let total = self.rules.format_rating(self.total());
+        let size = 44.;
+        let x = (width - PADDING) as f32 - fonts.text_width(&total, size);
+        fonts.draw_text(&mut image, &total, size, (x, 22.), TEXT);
+
+        let mut y = HEADER;
+        for (frame, ((name, size), charts)) in
+            self.rules.frames().iter().zip(&self.frames).enumerate()
+        {
+            let heading = format!(
+                "{} {}/{} — {}",
+                name,
+                charts.len(),
+                size,
+                self.rules.format_rating(self.frame_sum(frame))
+            );
+            fonts.draw_text(
+                &mut image,
+                &heading,
+                22.,
+                (PADDING as f32, (y + 8) as f32),
+                TEXT,
+            );
+            let top = y + SECTION_HEADER;
+            for (i, rated) in charts.iter().enumerate() {
+                let i = i as u32;
+                let x = PADDING + (i % COLUMNS) * (CELL_WIDTH + GAP);
+                let y = top + (i / COLUMNS) * (CELL_HEIGHT + GAP);
+                self.draw_cell(&mut image, fonts, rated, i + 1, (x, y), jacket(rated));
+            }
+            y += section_height(charts.len());
+        }
+
+        encode_png(&image)
+    }
+
+    fn draw_cell(
+        &self,
+        image: &mut RgbaImage,
+        fonts: &Fonts,
+        rated: &RatedChart,
+        rank: u32,
+        (x, y): (u32, u32),
+        jacket: Option<Vec<u8>>,
+    ) {
+        let name = self
+            .difficulty_names
+            .get(rated.chart.difficulty)
+            .map(String::as_str)
+            .unwrap_or_default();
+        let color = difficulty_color(name).unwrap_or(self.color);
+        fill_rect(image, (x, y), (CELL_WIDTH, CELL_HEIGHT), CELL);
+        fill_rect(image, (x, y), (6, CELL_HEIGHT), color);
+
+        let (jx, jy) = (x + 14, y + (CELL_HEIGHT - THUMBNAIL) / 2);
+        match jacket.and_then(|bytes| image::load_from_memory(&bytes).ok()) {
+            Some(jacket) => {
+                let jacket = jacket
+                    .resize_to_fill(THUMBNAIL, THUMBNAIL, imageops::FilterType::Triangle)
+                    .to_rgba8();
+                imageops::overlay(image, &jacket, jx as i64, jy as i64);
+            }
+            None => fill_rect(image, (jx, jy), (THUMBNAIL, THUMBNAIL), (90, 90, 100)),
+        }
+        // Rank in the frame, over the jacket's corner
+        let label = format!("#{}", rank);
+        let label_width = fonts.text_width(&label, 13.) as u32 + 8;
+        fill_rect(image, (jx, jy), (label_width, 18), color);
+        fonts.draw_text(
+            image,
+            &label,
+            13.,
+            ((jx + 4) as f32, (jy + 1) as f32),
+            text_color(color),
+        );
+
+        let tx = (jx + THUMBNAIL + 10) as f32;
+        let text_width = (x + CELL_WIDTH - 10) as f32 - tx;
+        let title = fonts.fit(&rated.title, 16., text_width);
+        fonts.draw_text(image, &title, 16., (tx, (y + 10) as f32), TEXT);
+
+        let chart_type = rated
+            .chart
+            .chart_type
+            .map(|t| format!("{} ", t))
+            .unwrap_or_default();
+        let constant = format!(
+            "{}{} {}{:.1}",
+            chart_type,
+            name,
+            if rated.estimated { "~" } else { "" },
+            rated.constant
+        );
+        let constant = fonts.fit(&constant, 13., text_width);
+        fonts.draw_text(image, &constant, 13., (tx, (y + 34) as f32), SUBTLE);
+        let score = self.rules.format_score(rated.score);
+        fonts.draw_text(image, &score, 15., (tx, (y + 54) as f32), TEXT);
+        let rating = self.rules.format_rating(rated.rating);
+        fonts.draw_text(
+            image,
+            &rating,
+            20.,
+            (tx, (y + 74) as f32),
+            color_or_text(color),
+        );
+    }
+}
+
+/// Difficulty colours too dark or too light to read on a cell fall back to plain text.
+fn color_or_text(color: Color) -> Color {
+    let (r, g, b) = color;
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if (60. ..230.).contains(&luminance) {
+        color
+    } else {
+        TEXT
+    }
+}
+
+/// Largest score list `/rating` downloads.
+pub const MAX_SCORES_SIZE: u64 = 256 * 1024;
+
+/// Fails unless an attachment of `size` bytes and `content_type` looks like a score list,
+/// so nothing else is downloaded.
+pub fn check_scores_attachment(size: u64, content_type: Option<&str>) -> eyre::Result<()> {
+    if size > MAX_SCORES_SIZE {
+        bail!(
+            "The score list is {} KiB; the limit is {} KiB",
+            size.div_ceil(1024),
+            MAX_SCORES_SIZE / 1024
+        );
+    }
+    if let Some(content_type) = content_type.filter(|t| !t.starts_with("text/")) {
+        bail!("The score list has to be a text file, not {}", content_type);
+    }
+    Ok(())
+}
+
+/// Draw a best-rating image from a score list
+#[poise::command(slash_command, prefix_command, check = "cooldown_check")]
+pub async fn rating(
+    ctx: Context<'_>,
+    #[description = "The game to rate"] game: Game,
+    #[description = "Text file with one \"title<TAB>difficulty<TAB>score\" line per chart"]
+    scores: serenity::Attachment,
+    #[description = "Use international versions and constants"]
+    #[flag]
+    intl: bool,
+) -> Result<(), Error> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let catalog = ctx.data().catalog(game);
+    let region = if intl { Region::Intl } else { Region::Jp };
+    check_scores_attachment(scores.size, scores.content_type.as_deref())?;
+    let text = String::from_utf8(scores.download().await?)
+        .map_err(|_| eyre!("The score list is not a UTF-8 text file"))?;
+    let sheet = RatingSheet::build(catalog, region, &text, ctx.guild_id().unwrap_or(GuildId(0)))?;
+
+    let jackets = ctx.data().jackets.clone();
+    let fonts = ctx.data().fonts.clone();
+    let game_id = catalog.id().to_string();
+    let (sheet, png) = tokio::task::spawn_blocking(move || {
+        let png = sheet.render(&fonts, |rated| {
+            let (jacket, url) = rated.jacket.as_ref().zip(rated.jacket_url.as_ref())?;
+            jackets.get(&game_id, jacket, url).ok()
+        });
+        (sheet, png)
+    })
+    .await?;
+
+    let mut content = format!("Rating: **{}**", sheet.rules.format_rating(sheet.total()));
+    if !sheet.skipped.is_empty() {
+        content += &format!("\nSkipped {} lines:", sheet.skipped.len());
+        for line in sheet.skipped.iter().take(10) {
+            content += &format!("\n- {}", line);
+        }
+        if sheet.skipped.len() > 10 {
+            content += "\n- ...";
+        }
+    }
+    let attachment = AttachmentType::Bytes {
+        data: Cow::Owned(png?),
+        filename: "rating.png".to_string(),
+    };
+    ctx.send(|f| {
+        f.ephemeral(settings.ephemeral)
+            .content(content)
+            .attachment(attachment)
+    })
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,204 @@
+//! Differences between the JP and international versions of a game, from `jp_lv` vs `intl_lv`.
+
+use serde::Serialize;
+
+use crate::{catalog::*, cooldowns::cooldown_check, settings::query_settings, utils::*};
+
+/// A chart that only one region has.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegionChart {
+    pub title: String,
+    pub chart_type: Option<&'static str>,
+    pub difficulty: String,
+    pub level: String,
+    pub constant: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LevelGap {
+    pub title: String,
+    pub chart_type: Option<&'static str>,
+    pub difficulty: String,
+    pub jp: (String, Option<f32>),
+    pub intl: (String, Option<f32>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegionDiff {
+    pub name: String,
+    /// False for games without international data, where everything else is empty.
+    pub has_intl: bool,
+    pub jp_only_songs: Vec<String>,
+    pub intl_only_songs: Vec<String>,
+    /// Charts missing from the other region, for songs that are in both.
+    pub jp_only_charts: Vec<RegionChart>,
+    pub intl_only_charts: Vec<RegionChart>,
+    /// Charts in both regions whose level or constant differs.
+    pub level_gaps: Vec<LevelGap>,
+}
+
+fn chart_name(chart_type: Option<&str>, difficulty: &str) -> String {
+    match chart_type {
+        Some(t) => format!("{} {}", t, difficulty),
+        None => difficulty.to_string(),
+    }
+}
+
+fn level_string((level, constant): &(String, Option<f32>)) -> String {
+    match constant {
+        Some(cc) => format!("{} ({:.1})", level, cc),
+        None => level.clone(),
+    }
+}
+
+/// Compares the JP and international charts of every song that isn't deleted.
+pub fn region_diff(catalog: &dyn GameCatalog) -> RegionDiff {
+    let names = catalog.difficulty_names();
+    let difficulty_name = |idx: usize| {
+        names
+            .get(idx)
+            .map_or_else(|| idx.to_string(), |n| n.to_string())
+    };
+    let mut diff = RegionDiff {
+        name: catalog.name().to_string(),
+        has_intl: false,
+        jp_only_songs: vec![],
+        intl_only_songs: vec![],
+        jp_only_charts: vec![],
+        intl_only_charts: vec![],
+        level_gaps: vec![],
+    };
+
+    let mut songs = catalog
+        .songs()
+        .filter(|(_, song)| !song.deleted())
+        .collect::<Vec<_>>();
+    songs.sort_by_key(|(title, _)| *title);
+    for (title, song) in songs {
+        let entries = song.chart_entries();
+        let (jp, intl): (Vec<_>, Vec<_>) = entries.iter().partition(|c| c.region == Region::Jp);
+        diff.has_intl |= !intl.is_empty();
+        match (jp.is_empty(), intl.is_empty()) {
+            (true, true) => continue,
+            (false, true) => {
+                diff.jp_only_songs.push(title.clone());
+                continue;
+            }
+            (true, false) => {
+                diff.intl_only_songs.push(title.clone());
+                continue;
+            }
+            (false, false) => {}
+        }
+
+        let same = |a: &ChartEntry, b: &ChartEntry| {
+            a.chart_type == b.chart_type && a.difficulty == b.difficulty
+        };
+        let region_chart = |c: &ChartEntry| RegionChart {
+            title: title.clone(),
+            chart_type: c.chart_type,
+            difficulty: difficulty_name(c.difficulty),
+            level: c.level.clone(),
+            constant: c.constant.map(|cc| cc.0),
+        };
+        for jp_chart in &jp {
+            match intl.iter().find(|c| same(c, jp_chart)) {
+                None => diff.jp_only_charts.push(region_chart(jp_chart)),
+                Some(intl_chart)
+                    if intl_chart.level != jp_chart.level
+                        || intl_chart.constant != jp_chart.constant =>
+                {
+                    diff.level_gaps.push(LevelGap {
+                        title: title.clone(),
+                        chart_type: jp_chart.chart_type,
+                        difficulty: difficulty_name(jp_chart.difficulty),
+                        jp: (jp_chart.level.clone(), jp_chart.constant.map(|cc| cc.0)),
+                        intl: (intl_chart.level.clone(), intl_chart.constant.map(|cc| cc.0)),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for intl_chart in &intl {
+            if !jp.iter().any(|c| same(c, intl_chart)) {
+                diff.intl_only_charts.push(region_chart(intl_chart));
+            }
+        }
+    }
+
+    if !diff.has_intl {
+        diff.jp_only_songs.clear();
+    }
+    diff
+}
+
+impl RegionDiff {
+    /// Discord markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut s = format!("**{}: JP vs international**\n", self.name);
+        if !self.has_intl {
+            s.push_str("No international version data.\n");
+            return s;
+        }
+        let mut section = |heading: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                s.push_str(&format!("__{} ({})__\n", heading, lines.len()));
+                for line in lines {
+                    s.push_str(&format!("- {}\n", line));
+                }
+            }
+        };
+        let charts = |charts: &[RegionChart]| {
+            charts
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{} {}: {}",
+                        c.title,
+                        chart_name(c.chart_type, &c.difficulty),
+                        level_string(&(c.level.clone(), c.constant))
+                    )
+                })
+                .collect()
+        };
+        section("JP only songs", self.jp_only_songs.clone());
+        section("International only songs", self.intl_only_songs.clone());
+        section("JP only charts", charts(&self.jp_only_charts));
+        section("International only charts", charts(&self.intl_only_charts));
+        section(
+            "Level differences (JP → intl)",
+            self.level_gaps
+                .iter()
+                .map(|gap| {
+                    format!(
+                        "{} {}: {} → {}",
+                        gap.title,
+                        chart_name(gap.chart_type, &gap.difficulty),
+                        level_string(&gap.jp),
+                        level_string(&gap.intl)
+                    )
+                })
+                .collect(),
+        );
+        s
+    }
+}
+
+/// List songs and charts that differ between JP and international versions
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "region-diff",
+    check = "cooldown_check"
+)]
+pub async fn region_diff_command(
+    ctx: Context<'_>,
+    #[description = "The game to compare"] game: Game,
+) -> Result<(), Error> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let report = region_diff(ctx.data().catalog(game)).to_markdown();
+    say_or_attach(ctx, report, "region-diff.md", settings.ephemeral).await?;
+    Ok(())
+}
@@ -0,0 +1,398 @@
+//! Per-guild settings, changed with the `/settings` commands by members who can manage the
+//! server and kept in `data/settings.json`.
+//!
+//! `info_template` and `jacket_template` consult them for the channel lists, the region shown
+//! and whether replies are ephemeral; `/info` uses the default game and `/add-alias` the alias
+//! submission permission.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use poise::serenity_prelude::{Channel, ChannelId, GuildId, Permissions};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    catalog::{Region, RenderOptions},
+    config::data_path,
+    utils::*,
+};
+
+/// Relative to `data_dir`.
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Which region's levels info embeds show.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+pub enum RegionSetting {
+    #[name = "JP"]
+    Jp,
+    #[name = "International"]
+    Intl,
+    #[default]
+    #[name = "Both"]
+    Both,
+}
+
+impl RegionSetting {
+    pub fn region(self) -> Option<Region> {
+        match self {
+            RegionSetting::Jp => Some(Region::Jp),
+            RegionSetting::Intl => Some(Region::Intl),
+            RegionSetting::Both => None,
+        }
+    }
+}
+
+/// Who may use `/add-alias`.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+pub enum AliasPermission {
+    #[default]
+    #[name = "Everyone"]
+    Everyone,
+    #[name = "Members who can manage messages"]
+    Moderators,
+    #[name = "Nobody"]
+    Nobody,
+}
+
+impl AliasPermission {
+    pub fn allows(self, permissions: Permissions) -> bool {
+        match self {
+            AliasPermission::Everyone => true,
+            AliasPermission::Moderators => {
+                permissions.manage_messages() || permissions.administrator()
+            }
+            AliasPermission::Nobody => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub region: RegionSetting,
+    /// Game id `/info` uses when no game is given.
+    pub default_game: Option<String>,
+    /// If not empty, queries only work in these channels.
+    pub allowed_channels: BTreeSet<u64>,
+    pub blocked_channels: BTreeSet<u64>,
+    /// Whether query replies are only shown to the member who asked (slash commands only).
+    pub ephemeral: bool,
+    pub alias_submission: AliasPermission,
+}
+
+impl GuildSettings {
+    pub fn channel_allowed(&self, channel: ChannelId) -> bool {
+        !self.blocked_channels.contains(&channel.0)
+            && (self.allowed_channels.is_empty() || self.allowed_channels.contains(&channel.0))
+    }
+
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            region: self.region.region(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Settings of every guild that changed any.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Settings {
+    guilds: BTreeMap<u64, GuildSettings>,
+}
+
+impl Settings {
+    /// Empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The settings of `guild`, or the defaults.
+    pub fn get(&self, guild: GuildId) -> GuildSettings {
+        self.guilds.get(&guild.0).cloned().unwrap_or_default()
+    }
+
+    /// Changes the settings of `guild`; guilds back at the defaults aren't stored.
+    pub fn update(&mut self, guild: GuildId, change: impl FnOnce(&mut GuildSettings)) {
+        let settings = self.guilds.entry(guild.0).or_default();
+        change(settings);
+        if *settings == GuildSettings::default() {
+            self.guilds.remove(&guild.0);
+        }
+    }
+}
+
+/// The settings of the guild `ctx` is in; the defaults in DMs.
+pub async fn guild_settings(ctx: Context<'_>) -> GuildSettings {
+    match ctx.guild_id() {
+        Some(guild) => ctx.data().settings.lock().await.get(guild),
+        None => GuildSettings::default(),
+    }
+}
+
+/// The guild settings for a query, or `None` after telling the user that queries are turned
+/// off in this channel.
+pub async fn query_settings(ctx: Context<'_>) -> eyre::Result<Option<GuildSettings>> {
+    let settings = guild_settings(ctx).await;
+    if settings.channel_allowed(ctx.channel_id()) {
+        return Ok(Some(settings));
+    }
+    ctx.send(|f| {
+        f.ephemeral(true)
+            .content("Queries are turned off in this channel.")
+    })
+    .await?;
+    Ok(None)
+}
+
+/// Whether the author may submit aliases in this guild; always true in DMs.
+pub async fn can_submit_aliases(ctx: Context<'_>) -> bool {
+    let permission = guild_settings(ctx).await.alias_submission;
+    if permission == AliasPermission::Everyone {
+        return true;
+    }
+    let Some(member) = ctx.author_member().await else {
+        return ctx.guild_id().is_none();
+    };
+    let permissions = member
+        .permissions
+        .or_else(|| member.permissions(ctx.serenity_context()).ok())
+        .unwrap_or_else(Permissions::empty);
+    permission.allows(permissions)
+}
+
+async fn update(ctx: Context<'_>, change: impl FnOnce(&mut GuildSettings)) -> Result<(), Error> {
+    let guild = ctx.guild_id().unwrap();
+    let mut settings = ctx.data().settings.lock().await;
+    settings.update(guild, change);
+    settings.save(&data_path(SETTINGS_FILE))?;
+    Ok(())
+}
+
+async fn reply(ctx: Context<'_>, text: impl Into<String>) -> Result<(), Error> {
+    ctx.send(|f| f.ephemeral(true).content(text.into())).await?;
+    Ok(())
+}
+
+/// Change how the bot behaves in this server
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands(
+        "show",
+        "region",
+        "default_game",
+        "allow_channel",
+        "block_channel",
+        "reset_channel",
+        "ephemeral",
+        "alias_submission"
+    )
+)]
+pub async fn settings(ctx: Context<'_>) -> Result<(), Error> {
+    show_inner(ctx).await
+}
+
+async fn show_inner(ctx: Context<'_>) -> Result<(), Error> {
+    let settings = guild_settings(ctx).await;
+    let channels = |ids: &BTreeSet<u64>| {
+        ids.iter()
+            .map(|c| format!("<#{}>", c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let default_game = settings
+        .default_game
+        .as_deref()
+        .and_then(|id| ctx.data().catalog_by_id(id))
+        .map_or("none".to_string(), |catalog| catalog.name().to_string());
+    let mut text = format!(
+        "**Region:** {}\n**Default game:** {}\n**Ephemeral replies:** {}\n**Alias submission:** {}",
+        settings.region,
+        default_game,
+        if settings.ephemeral { "on" } else { "off" },
+        settings.alias_submission,
+    );
+    if !settings.allowed_channels.is_empty() {
+        text += &format!("\n**Only in:** {}", channels(&settings.allowed_channels));
+    }
+    if !settings.blocked_channels.is_empty() {
+        text += &format!("\n**Not in:** {}", channels(&settings.blocked_channels));
+    }
+    reply(ctx, text).await
+}
+
+/// Show the settings of this server
+#[poise::command(slash_command, prefix_command, guild_only)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    show_inner(ctx).await
+}
+
+/// Which region's levels info commands show
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn region(
+    ctx: Context<'_>,
+    #[description = "JP, International or both"] region: RegionSetting,
+) -> Result<(), Error> {
+    update(ctx, |settings| settings.region = region).await?;
+    reply(ctx, format!("Info commands show {} levels now.", region)).await
+}
+
+/// The game /info looks songs up in when none is given
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    rename = "default-game",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn default_game(
+    ctx: Context<'_>,
+    #[description = "The game (leave out for none)"]
+    #[autocomplete = "autocomplete_game"]
+    game: Option<String>,
+) -> Result<(), Error> {
+    let game = match game {
+        Some(game) => match ctx.data().find_game(&game) {
+            Some(found) => Some(found),
+            None => return reply(ctx, format!("There is no game called {}.", game)).await,
+        },
+        None => None,
+    };
+    update(ctx, |settings| {
+        settings.default_game = game.map(|(id, _)| id.to_string())
+    })
+    .await?;
+    let text = match game {
+        Some((_, catalog)) => format!("/info looks up {} songs by default now.", catalog.name()),
+        None => "/info needs a game now.".to_string(),
+    };
+    reply(ctx, text).await
+}
+
+/// Only answer queries in the allowed channels
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    rename = "allow-channel",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn allow_channel(
+    ctx: Context<'_>,
+    #[description = "The channel (default: this one)"] channel: Option<Channel>,
+) -> Result<(), Error> {
+    let channel = channel.map_or(ctx.channel_id(), |c| c.id());
+    update(ctx, |settings| {
+        settings.blocked_channels.remove(&channel.0);
+        settings.allowed_channels.insert(channel.0);
+    })
+    .await?;
+    reply(
+        ctx,
+        format!(
+            "Queries work in <#{}> and other allowed channels only.",
+            channel
+        ),
+    )
+    .await
+}
+
+/// Don't answer queries in a channel
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    rename = "block-channel",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn block_channel(
+    ctx: Context<'_>,
+    #[description = "The channel (default: this one)"] channel: Option<Channel>,
+) -> Result<(), Error> {
+    let channel = channel.map_or(ctx.channel_id(), |c| c.id());
+    update(ctx, |settings| {
+        settings.allowed_channels.remove(&channel.0);
+        settings.blocked_channels.insert(channel.0);
+    })
+    .await?;
+    reply(ctx, format!("Queries are turned off in <#{}>.", channel)).await
+}
+
+/// Take a channel off the allowed and blocked lists
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    rename = "reset-channel",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn reset_channel(
+    ctx: Context<'_>,
+    #[description = "The channel (default: this one)"] channel: Option<Channel>,
+) -> Result<(), Error> {
+    let channel = channel.map_or(ctx.channel_id(), |c| c.id());
+    update(ctx, |settings| {
+        settings.allowed_channels.remove(&channel.0);
+        settings.blocked_channels.remove(&channel.0);
+    })
+    .await?;
+    reply(ctx, format!("<#{}> is on neither list now.", channel)).await
+}
+
+/// Only show query replies to the member who asked
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn ephemeral(
+    ctx: Context<'_>,
+    #[description = "On or off"] ephemeral: bool,
+) -> Result<(), Error> {
+    update(ctx, |settings| settings.ephemeral = ephemeral).await?;
+    let text = if ephemeral {
+        "Query replies are only shown to whoever asked now."
+    } else {
+        "Query replies are shown to everyone now."
+    };
+    reply(ctx, text).await
+}
+
+/// Who may submit aliases with /add-alias
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    rename = "alias-submission",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn alias_submission(
+    ctx: Context<'_>,
+    #[description = "Who may submit aliases"] permission: AliasPermission,
+) -> Result<(), Error> {
+    update(ctx, |settings| settings.alias_submission = permission).await?;
+    reply(ctx, format!("Alias submission: {}.", permission)).await
+}
@@ -0,0 +1,228 @@
+//! Update announcements: channels subscribe to a game, and the bot periodically re-fetches
+//! that game's sources and posts what changed.
+//!
+//! The loaded data itself is not replaced; commands keep using it until the next restart.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+use poise::serenity_prelude::{ChannelId, Color, CreateEmbed, Http};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    catalog::*, changes::*, chuni::set_chuni_charts, config::*, maimai::set_mai_charts,
+    ongeki::set_ongeki_charts, utils::*,
+};
+
+/// Relative to `data_dir`, like `ANNOUNCED_SNAPSHOT`.
+pub const SUBSCRIPTIONS_FILE: &str = "subscriptions.json";
+/// The data as of the last announcement, so restarts don't announce the same changes again.
+pub const ANNOUNCED_SNAPSHOT: &str = "snapshots/announced.json";
+/// Discord allows 10 embeds per message; one is the summary.
+const MAX_SONG_EMBEDS: usize = 9;
+
+/// Channel ids subscribed to each game id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Subscriptions {
+    games: BTreeMap<String, BTreeSet<u64>>,
+}
+
+impl Subscriptions {
+    /// Empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Returns false if the channel was already subscribed.
+    pub fn subscribe(&mut self, game: &str, channel: ChannelId) -> bool {
+        self.games
+            .entry(game.to_string())
+            .or_default()
+            .insert(channel.0)
+    }
+
+    /// Returns false if the channel wasn't subscribed.
+    pub fn unsubscribe(&mut self, game: &str, channel: ChannelId) -> bool {
+        self.games
+            .get_mut(game)
+            .is_some_and(|channels| channels.remove(&channel.0))
+    }
+
+    pub fn channels(&self, game: &str) -> Vec<ChannelId> {
+        self.games
+            .get(game)
+            .map(|channels| channels.iter().map(|&c| ChannelId(c)).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Loads a game from its sources again. Blocking.
+fn fetch(game: Game) -> Result<Box<dyn GameCatalog>, Error> {
+    Ok(match game {
+        Game::Maimai => Box::new(Catalog::load(set_mai_charts()?, "maimai")?),
+        Game::Chunithm => Box::new(Catalog::load(set_chuni_charts()?, "chuni")?),
+        Game::Ongeki => Box::new(Catalog::load(set_ongeki_charts()?, "ongeki")?),
+    })
+}
+
+/// The summary embed, with the report cut to 2000 characters, and one embed per new song up to
+/// Discord's limit.
+pub fn announcement_embeds(changes: &GameChanges, catalog: &dyn GameCatalog) -> Vec<CreateEmbed> {
+    let (r, g, b) = catalog.color();
+    let mut description = changes.to_markdown();
+    if description.chars().count() > 2000 {
+        description = description.chars().take(1990).collect::<String>() + "\n…";
+    }
+    let mut summary = CreateEmbed::default();
+    summary
+        .title(format!("{} data update", changes.name))
+        .description(description)
+        .color(Color::from_rgb(r, g, b));
+
+    let mut embeds = vec![summary];
+    for title in changes.new_songs.iter().take(MAX_SONG_EMBEDS) {
+        let mut embed = CreateEmbed::default();
+        embed
+            .title(catalog.display_title(title).replace('*', "\\*"))
+            .color(Color::from_rgb(r, g, b));
+        if let Some(song) = catalog.song(title) {
+            embed.description(song.artist());
+        }
+        if let Some(jacket) = catalog.jacket_url(title) {
+            embed.thumbnail(jacket);
+        }
+        embeds.push(embed);
+    }
+    embeds
+}
+
+/// Diffs a freshly fetched game against the announced data and marks it announced in
+/// `announced` and its file at `path`. `None` if nothing changed or the game wasn't known yet.
+pub fn unannounced_changes(
+    announced: &mut Snapshot,
+    fetched: Snapshot,
+    path: &Path,
+) -> Option<GameChanges> {
+    let changes = diff(announced, &fetched)
+        .into_iter()
+        .next()
+        .filter(|changes| !changes.is_empty());
+    announced.games.extend(fetched.games);
+    if let Err(e) = announced.save(path) {
+        eprintln!("Could not save {:?}: {}", path, e);
+    }
+    changes
+}
+
+/// Re-fetches subscribed games every `timeouts.update_check_hours` and announces changes.
+/// Runs forever.
+pub async fn check_updates(http: Arc<Http>, data: Arc<Data>) {
+    let path = &data_path(ANNOUNCED_SNAPSHOT);
+    let mut announced = match Snapshot::load(path) {
+        Ok(snapshot) => snapshot,
+        Err(_) => Snapshot::capture(&data.catalogs()),
+    };
+
+    loop {
+        tokio::time::sleep(config().timeouts.update_check()).await;
+        for game in [Game::Maimai, Game::Chunithm, Game::Ongeki] {
+            let channels = data.subscriptions.lock().await.channels(game.id());
+            if channels.is_empty() {
+                continue;
+            }
+            let catalog =
+                match tokio::task::spawn_blocking(move || fetch(game).map_err(|e| e.to_string()))
+                    .await
+                {
+                    Ok(Ok(catalog)) => catalog,
+                    Ok(Err(e)) => {
+                        eprintln!("Update check for {} failed: {}", game.id(), e);
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("Update check for {} panicked: {}", game.id(), e);
+                        continue;
+                    }
+                };
+
+            let fetched = Snapshot::capture(&[(game.id(), catalog.as_ref())]);
+            let Some(changes) = unannounced_changes(&mut announced, fetched, path) else {
+                continue;
+            };
+            let embeds = announcement_embeds(&changes, catalog.as_ref());
+            for channel in channels {
+                if let Err(e) = channel
+                    .send_message(&http, |m| m.set_embeds(embeds.clone()))
+                    .await
+                {
+                    eprintln!("Could not announce to channel {}: {}", channel, e);
+                }
+                tokio::time::sleep(config().timeouts.announce_delay()).await;
+            }
+        }
+    }
+}
+
+async fn set_subscription(ctx: Context<'_>, game: Game, subscribe: bool) -> Result<(), Error> {
+    let changed = {
+        let mut subscriptions = ctx.data().subscriptions.lock().await;
+        let changed = if subscribe {
+            subscriptions.subscribe(game.id(), ctx.channel_id())
+        } else {
+            subscriptions.unsubscribe(game.id(), ctx.channel_id())
+        };
+        subscriptions.save(&data_path(SUBSCRIPTIONS_FILE))?;
+        changed
+    };
+    let name = ctx.data().catalog(game).name().to_string();
+    let text = match (subscribe, changed) {
+        (true, true) => format!("This channel will get {} data update announcements.", name),
+        (true, false) => format!("This channel is already subscribed to {} updates.", name),
+        (false, true) => format!("This channel will no longer get {} updates.", name),
+        (false, false) => format!("This channel is not subscribed to {} updates.", name),
+    };
+    ctx.send(|f| f.ephemeral(true).content(text)).await?;
+    Ok(())
+}
+
+/// Announce new songs and constant changes in this channel
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "subscribe-updates",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub async fn subscribe_updates(
+    ctx: Context<'_>,
+    #[description = "The game to get update announcements for"] game: Game,
+) -> Result<(), Error> {
+    set_subscription(ctx, game, true).await
+}
+
+/// Stop update announcements in this channel
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "unsubscribe-updates",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub async fn unsubscribe_updates(
+    ctx: Context<'_>,
+    #[description = "The game to stop update announcements for"] game: Game,
+) -> Result<(), Error> {
+    set_subscription(ctx, game, false).await
+}
@@ -1,27 +1,37 @@
 use ordered_float::OrderedFloat;
-use poise::serenity_prelude::{
-    model::application::interaction::InteractionResponseType, AttachmentType, Color,
-    CreateActionRow, CreateButton, GuildId,
-};
+use poise::serenity_prelude::{AttachmentType, CreateActionRow, CreateButton, GuildId};
+use serde::Serialize;
 use std::{
+    borrow::Cow,
     collections::HashMap,
-    fs::File,
+    fs::{self, File},
     io::{BufRead, BufReader, Write},
     sync::Arc,
-    time::Duration,
 };
 use tokio::sync::Mutex;
 use walkdir::WalkDir;
 
+use crate::{
+    artists::ArtistIndex,
+    card::ChartCard,
+    catalog::*,
+    changes::Snapshot,
+    components::{Action, ComponentId},
+    config::*,
+    cooldowns::Cooldowns,
+    crossover::CrossoverData,
+    custom::CustomCatalog,
+    images::Fonts,
+    jackets::JacketCache,
+    preferences::{render_options, Preferences},
+    settings::{query_settings, Settings},
+    updates::Subscriptions,
+};
+
 /////////////////////// General utils ///////////////////////
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
-pub type Context<'a> = poise::Context<'a, Data, Error>;
-
-// pub const USER_COOLDOWN: i64 = 1800;
-// pub const CHANNEL_COOLDOWN: i64 = 300;
-
-// type Maps = (HashMap<UserId, i64>, HashMap<ChannelId, i64>);
+pub type Context<'a> = poise::Context<'a, Arc<Data>, Error>;
 
 #[derive(Debug, poise::ChoiceParameter, Copy, Clone, PartialEq)]
 pub enum Game {
@@ -33,38 +43,125 @@ pub enum Game {
     Ongeki,
 }
 
+impl Game {
+    /// Data directory name, also used as the game id by `Data::catalog_by_id`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Game::Maimai => "maimai",
+            Game::Chunithm => "chuni",
+            Game::Ongeki => "ongeki",
+        }
+    }
+}
+
 // User data, which is stored and accessible in all command invocations
 pub struct Data {
-    pub mai_charts: HashMap<String, MaiInfo>,
-    pub mai_aliases: Aliases,
-    pub mai_jacket_prefix: String,
+    pub maimai: Catalog<MaiInfo>,
+    pub chuni: Catalog<ChuniInfo>,
+    pub ongeki: Catalog<OngekiInfo>,
+    /// Games loaded from `data/custom`, keyed by config id.
+    pub custom: HashMap<String, CustomCatalog>,
+    /// Chart data before the last update, for `/changes`.
+    pub previous_snapshot: Option<Snapshot>,
+    pub subscriptions: Arc<Mutex<Subscriptions>>,
+    pub cooldowns: Arc<Mutex<Cooldowns>>,
+    pub settings: Arc<Mutex<Settings>>,
+    pub preferences: Arc<Mutex<Preferences>>,
+    /// Exceptions for `/crossover`.
+    pub crossover: CrossoverData,
+    /// For `/artist`; built from the catalogs above.
+    pub artists: ArtistIndex,
+    pub jackets: JacketCache,
+    /// For chart cards.
+    pub fonts: Arc<Fonts>,
+
+    /// `aliases/manual/{id}.tsv` of every game, keyed by game id.
+    pub manual_alias_files: HashMap<String, Arc<Mutex<File>>>,
+
+    pub alias_log: Arc<Mutex<File>>,
+}
 
-    pub chuni_charts: HashMap<String, ChuniInfo>,
-    pub chuni_aliases: Aliases,
-    pub chuni_jacket_prefix: String,
+impl Data {
+    pub fn catalog(&self, game: Game) -> &dyn GameCatalog {
+        match game {
+            Game::Maimai => &self.maimai,
+            Game::Chunithm => &self.chuni,
+            Game::Ongeki => &self.ongeki,
+        }
+    }
 
-    pub ongeki_charts: HashMap<String, OngekiInfo>,
-    pub ongeki_aliases: Aliases,
-    pub ongeki_jacket_prefix: String,
+    /// Catalog by data directory name ("maimai", "chuni", "ongeki") or custom game id.
+    pub fn catalog_by_id(&self, id: &str) -> Option<&dyn GameCatalog> {
+        match id {
+            "maimai" => Some(&self.maimai),
+            "chuni" => Some(&self.chuni),
+            "ongeki" => Some(&self.ongeki),
+            _ => self.custom.get(id).map(|c| c as &dyn GameCatalog),
+        }
+    }
 
-    pub manual_alias_file_maimai: Arc<Mutex<File>>,
-    pub manual_alias_file_chuni: Arc<Mutex<File>>,
-    pub manual_alias_file_ongeki: Arc<Mutex<File>>,
+    /// (id, catalog) for every loaded game, built-in games first.
+    pub fn catalogs(&self) -> Vec<(&str, &dyn GameCatalog)> {
+        let mut catalogs: Vec<(&str, &dyn GameCatalog)> = vec![
+            ("maimai", &self.maimai),
+            ("chuni", &self.chuni),
+            ("ongeki", &self.ongeki),
+        ];
+        let mut custom = self.custom.iter().collect::<Vec<_>>();
+        custom.sort_by_key(|(id, _)| *id);
+        catalogs.extend(
+            custom
+                .into_iter()
+                .map(|(id, c)| (id.as_str(), c as &dyn GameCatalog)),
+        );
+        catalogs
+    }
 
-    // pub cooldown_server_ids: HashSet<GuildId>,
-    // pub cooldown_channel_exception_ids: HashSet<ChannelId>,
-    // pub timestamps: Arc<Mutex<HashMap<GuildId, Maps>>>,
-    pub alias_log: Arc<Mutex<File>>,
+    pub fn manual_alias_file(&self, id: &str) -> Option<&Arc<Mutex<File>>> {
+        self.manual_alias_files.get(id)
+    }
+
+    /// The game `query` names; see `find_game`.
+    pub fn find_game(&self, query: &str) -> Option<(&str, &dyn GameCatalog)> {
+        find_game(&self.catalogs(), query)
+    }
 }
 
-#[allow(dead_code)]
-pub enum Cooldown {
-    User(i64),
-    Channel(i64),
-    None,
+/// The catalog whose id or name is `query`, ignoring case.
+pub fn find_game<'a>(
+    catalogs: &[(&'a str, &'a dyn GameCatalog)],
+    query: &str,
+) -> Option<(&'a str, &'a dyn GameCatalog)> {
+    let query = query.trim();
+    catalogs.iter().copied().find(|(id, catalog)| {
+        id.eq_ignore_ascii_case(query) || catalog.name().eq_ignore_ascii_case(query)
+    })
+}
+
+/// (name, id) of the games whose id or name contains `partial`, ignoring case.
+pub fn game_choices(catalogs: &[(&str, &dyn GameCatalog)], partial: &str) -> Vec<(String, String)> {
+    let partial = partial.to_lowercase();
+    catalogs
+        .iter()
+        .filter(|(id, catalog)| {
+            id.to_lowercase().contains(&partial) || catalog.name().to_lowercase().contains(&partial)
+        })
+        .map(|(id, catalog)| (catalog.name().to_string(), id.to_string()))
+        .collect()
+}
+
+/// Autocomplete for game parameters that take the id of any loaded game, custom ones included.
+pub async fn autocomplete_game(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<String>> {
+    game_choices(&ctx.data().catalogs(), partial)
+        .into_iter()
+        .map(|(name, value)| poise::AutocompleteChoice { name, value })
+        .collect()
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct Difficulty {
     pub bas: String,
     pub adv: String,
@@ -220,10 +317,22 @@ pub struct MainAliases<V> {
     pub nicknames_alphanumeric_and_ascii: HashMap<String, V>,
 }
 
+/// The path of a `file://` URL, relative to the working directory unless it starts with `/`.
+/// This is how tests point the loaders at recorded payloads under `tests/fixtures`.
+fn file_url_path(url: &str) -> Option<&str> {
+    url.strip_prefix("file://")
+}
+
+/// Fetches `url` and returns the body. `file://` URLs are read from disk.
 pub fn get_curl(url: &str) -> String {
+    let url = url.trim();
+    if let Some(path) = file_url_path(url) {
+        return fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", url, e));
+    }
+    assert!(url.contains("://"), "{:?} is not a URL", url);
     let mut data = Vec::new();
     let mut handle = curl::easy::Easy::new();
-    handle.url(url.trim()).unwrap();
+    handle.url(url).unwrap();
     {
         let mut transfer = handle.transfer();
         transfer
@@ -241,6 +350,30 @@ pub fn get_curl(url: &str) -> String {
     s.to_string()
 }
 
+/// Like `get_curl`, but for binary bodies, and failing instead of panicking.
+pub fn get_curl_bytes(url: &str) -> eyre::Result<Vec<u8>> {
+    let url = url.trim();
+    if let Some(path) = file_url_path(url) {
+        return Ok(fs::read(path)?);
+    }
+    eyre::ensure!(url.contains("://"), "{:?} is not a URL", url);
+    let mut data = Vec::new();
+    let mut handle = curl::easy::Easy::new();
+    handle.url(url)?;
+    handle.follow_location(true)?;
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|new_data| {
+            data.extend_from_slice(new_data);
+            Ok(new_data.len())
+        })?;
+        transfer.perform()?;
+    }
+    let code = handle.response_code()?;
+    eyre::ensure!(code == 200, "{} returned HTTP {}", url, code);
+    Ok(data)
+}
+
 // TODO: NEEDS REFACTOR
 pub fn get_title(title: &str, all_aliases: &Aliases, server_id: GuildId) -> Option<String> {
     let aliases = &all_aliases.main;
@@ -371,7 +504,7 @@ where
     }
 
     // Set aliases
-    let files = WalkDir::new("./data/aliases")
+    let files = WalkDir::new(data_path("aliases"))
         .into_iter()
         .filter_map(|file| file.ok())
         // filter files with correct filename
@@ -455,7 +588,7 @@ where
 
     // Set community aliases
     let mut community_aliases = HashMap::<GuildId, MainAliases<(String, String)>>::new();
-    let file = File::open(format!("./data/aliases/manual/{}.tsv", game)).unwrap();
+    let file = File::open(data_path(format!("aliases/manual/{}.tsv", game))).unwrap();
     let lines = BufReader::new(file).lines();
     for line in lines.map_while(Result::ok) {
         let split = line.split('\t');
@@ -735,49 +868,88 @@ pub fn constant_to_string(c: Option<OrderedFloat<f32>>) -> String {
     }
 }
 
-/// Returns true if guild id is registered in `data/cooldown-server-ids.txt`
-/// and user cooldown has not yet passed.
-pub async fn check_cooldown(_ctx: &Context<'_>) -> Cooldown {
-    Cooldown::None
-
-    // let guild_id = match ctx.guild_id() {
-    //     Some(id) => id,
-    //     None => return Cooldown::None,
-    // };
-    // let channel_id = ctx.channel_id();
+/// A YouTube search for play videos of a chart, e.g. `youtube_search("maimai", "Selector", "MASTER")`.
+pub fn youtube_search(game: &str, title: &str, chart: &str) -> String {
+    // A leading '-' would exclude the title from the results.
+    let title = title.replace(" -", " ");
+    let title = title.strip_prefix('-').unwrap_or(&title);
+    format!(
+        "https://www.youtube.com/results?search_query={}+{}+{}",
+        game,
+        urlencoding::encode(title),
+        chart
+    )
+}
 
-    // if !ctx.data().cooldown_server_ids.contains(&guild_id) {
-    //     return Cooldown::None;
-    // }
-    // if ctx
-    //     .data()
-    //     .cooldown_channel_exception_ids
-    //     .contains(&channel_id)
-    // {
-    //     return Cooldown::None;
-    // }
+/// "B **13** (13.7) / A ..." for the charts of `lv`, with the labels linking to YouTube
+/// searches for `game` and `title` unless `options.links` is off. `extra` is the name of the
+/// fifth difficulty (Re:MASTER, ULTIMA, LUNATIC); charts without a level are left out.
+pub fn difficulty_line(
+    lv: &Difficulty,
+    game: &str,
+    title: &str,
+    extra: &str,
+    options: &RenderOptions,
+) -> String {
+    let charts = [
+        ("BASIC", Some(&lv.bas), lv.bas_c),
+        ("ADVANCED", Some(&lv.adv), lv.adv_c),
+        ("EXPERT", Some(&lv.exp), lv.exp_c),
+        ("MASTER", Some(&lv.mas), lv.mas_c),
+        (extra, lv.extra.as_ref(), lv.extra_c),
+    ];
+    charts
+        .into_iter()
+        .filter_map(|(name, level, constant)| {
+            let level = level.filter(|level| !level.is_empty())?;
+            let label = &name[..1];
+            let label = if options.links {
+                format!("[{}]({})", label, youtube_search(game, title, name))
+            } else {
+                label.to_string()
+            };
+            let constant = if options.constants {
+                constant_to_string(constant)
+            } else {
+                "".to_string()
+            };
+            Some(format!("{} **{}**{}", label, level, constant))
+        })
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
 
-    // let mut map = ctx.data().timestamps.lock().await;
-    // let (user_map, channel_map) = map.get_mut(&guild_id).unwrap();
+/// Discord wants required options before optional ones, but `#[rest]` has to come last for
+/// prefix commands, so optional flags are declared first and moved behind here.
+/// Slash arguments are matched by name, so the order is free to change.
+pub fn required_options_first(command: &mut poise::Command<Arc<Data>, Error>) {
+    command.parameters.sort_by_key(|param| !param.required);
+    for subcommand in &mut command.subcommands {
+        required_options_first(subcommand);
+    }
+}
 
-    // let now = Timestamp::now().unix_timestamp();
-    // let user_id = ctx.author().id;
-    // let channel_id = ctx.channel_id();
-    // let then = user_map.get(&user_id);
-    // if let Some(then) = then {
-    //     if now - then < USER_COOLDOWN {
-    //         return Cooldown::User(USER_COOLDOWN - (now - then));
-    //     }
-    // }
-    // let then = channel_map.get(&channel_id);
-    // if let Some(then) = then {
-    //     if now - then < CHANNEL_COOLDOWN {
-    //         return Cooldown::Channel(CHANNEL_COOLDOWN - (now - then));
-    //     }
-    // }
-    // user_map.insert(user_id, now);
-    // channel_map.insert(channel_id, now);
-    // Cooldown::None
+/// Sends `text` as a message, or as a file named `filename` if it's over Discord's 2000 character limit.
+pub async fn say_or_attach(
+    ctx: Context<'_>,
+    text: String,
+    filename: &str,
+    ephemeral: bool,
+) -> Result<(), Error> {
+    if text.chars().count() <= 2000 {
+        ctx.send(|f| f.ephemeral(ephemeral).content(text)).await?;
+    } else {
+        ctx.send(|f| {
+            f.ephemeral(ephemeral)
+                .content("The list is too long for a message; see the attached file.")
+                .attachment(AttachmentType::Bytes {
+                    data: Cow::Owned(text.into_bytes()),
+                    filename: filename.to_string(),
+                })
+        })
+        .await?;
+    }
+    Ok(())
 }
 
 /// Return corresponding index to difficulty - BASIC = 0, ADVANCED = 1, ...
@@ -799,7 +971,7 @@ pub fn diff_to_idx(diff: &str) -> usize {
 
 /////////////////////// maimai utils ///////////////////////
 
-#[derive(Debug, Eq, PartialEq, Default, Clone)]
+#[derive(Debug, Eq, PartialEq, Default, Clone, Serialize)]
 pub struct MaiDifficulty {
     pub st: Option<Difficulty>,
     pub dx: Option<Difficulty>,
@@ -812,7 +984,7 @@ impl MaiDifficulty {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Serialize)]
 pub enum MaiCategory {
     PopAnime,
     NicoVoca,
@@ -825,7 +997,7 @@ pub enum MaiCategory {
     Error,
 }
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Serialize)]
 pub struct MaiInfo {
     pub jp_lv: Option<MaiDifficulty>,
     pub intl_lv: Option<MaiDifficulty>,
@@ -846,14 +1018,14 @@ pub struct MaiInfo {
     pub additional_dx_version: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct Utage {
     pub level: String,
     pub kanji: String,
     pub comment: String,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct MaiSheet {
     pub designer: Option<String>,
     pub brk: usize,
@@ -893,7 +1065,7 @@ pub fn mai_get_category(s: &str) -> MaiCategory {
 
 /////////////////////// chuni utils ///////////////////////
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Serialize)]
 pub struct ChuniInfo {
     pub jp_lv: Option<Difficulty>,
     pub intl_lv: Option<Difficulty>,
@@ -908,7 +1080,7 @@ pub struct ChuniInfo {
     pub sdvxin: Option<(String, String)>,
 }
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Serialize)]
 pub enum ChuniCategory {
     PopsAnime,
     Niconico,
@@ -954,7 +1126,7 @@ pub fn float_to_chuni_level(f: &str) -> String {
 
 /////////////////////// ongeki utils ///////////////////////
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Serialize)]
 pub struct OngekiInfo {
     pub lv: Option<Difficulty>,
     pub jp_jacket: Option<String>,
@@ -968,7 +1140,7 @@ pub struct OngekiInfo {
     pub deleted: bool,
 }
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Serialize)]
 pub enum OngekiCategory {
     Ongeki,
     PopsAnime,
@@ -1000,44 +1172,56 @@ pub fn ongeki_get_category(s: &str) -> OngekiCategory {
     }
 }
 
-fn get_jp_jacket(ctx: Context<'_>, game: Game, title: &str) -> Option<String> {
-    match game {
-        Game::Maimai => ctx.data().mai_charts[title].jp_jacket.clone(),
-        Game::Chunithm => ctx.data().chuni_charts[title].jp_jacket.clone(),
-        Game::Ongeki => ctx.data().ongeki_charts[title].jp_jacket.clone(),
-    }
-}
-
-fn get_url_prefix(ctx: Context<'_>, game: Game) -> String {
-    match game {
-        Game::Maimai => ctx.data().mai_jacket_prefix.clone(),
-        Game::Chunithm => ctx.data().chuni_jacket_prefix.clone(),
-        Game::Ongeki => ctx.data().ongeki_jacket_prefix.clone(),
-    }
-}
-
-fn get_aliases(ctx: Context<'_>, game: Game) -> &Aliases {
-    match game {
-        Game::Maimai => &ctx.data().mai_aliases,
-        Game::Chunithm => &ctx.data().chuni_aliases,
-        Game::Ongeki => &ctx.data().ongeki_aliases,
-    }
+/// Replies to a title that wasn't found with its closest match, and a button that does
+/// `action` for that song.
+pub async fn suggest(
+    ctx: Context<'_>,
+    catalog: &dyn GameCatalog,
+    title: &str,
+    closest: &(String, String),
+    action: Action,
+) -> eyre::Result<()> {
+    let reply = format!(
+        "I couldn't find the results for **{}**;
+Did you mean **{}** (for **{}**)?
+(P.S. You can also use the `/add-alias` command to add this alias to the bot.)",
+        title, closest.0, closest.1
+    );
+    let id = ComponentId::new(action, catalog.id(), &closest.1).to_string();
+    ctx.send(|f| {
+        f.ephemeral(true).content(reply).components(|c| {
+            let mut button = CreateButton::default();
+            button.custom_id(id);
+            button.label("Yes");
+            let mut ar = CreateActionRow::default();
+            ar.add_button(button);
+            c.set_action_row(ar)
+        })
+    })
+    .await?;
+    Ok(())
 }
 
-pub async fn jacket_template(ctx: Context<'_>, title: String, game: Game) -> eyre::Result<()> {
+pub async fn jacket_template(
+    ctx: Context<'_>,
+    catalog: &dyn GameCatalog,
+    title: String,
+) -> eyre::Result<()> {
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
     // Get alias corresponding to game.
-    let aliases_template = get_aliases(ctx, game);
+    let aliases_template = catalog.aliases();
 
     // Check if title is in alias list.
-    let actual_title = get_title(
+    let actual_title = catalog.lookup(
         &title,
-        aliases_template,
         ctx.guild_id()
             .unwrap_or(poise::serenity_prelude::GuildId(0)),
     );
 
     // If title is not in alias list, get closest alias to title and show button.
-    if actual_title.is_none() {
+    let Some(title) = actual_title else {
         let mut log = ctx.data().alias_log.lock().await;
         let closest = get_closest_title(
             &title,
@@ -1045,159 +1229,106 @@ pub async fn jacket_template(ctx: Context<'_>, title: String, game: Game) -> eyr
             ctx.guild_id()
                 .unwrap_or(poise::serenity_prelude::GuildId(0)),
         );
-        writeln!(log, "{}\t{:?}\t{}\t{}", title, game, closest.0, closest.1)?;
+        writeln!(
+            log,
+            "{}\t{}\t{}\t{}",
+            title,
+            catalog.name(),
+            closest.0,
+            closest.1
+        )?;
         log.sync_all()?;
         drop(log);
-        let reply = format!(
-            "I couldn't find the results for **{}**;
-Did you mean **{}** (for **{}**)?
-(P.S. You can also use the `/add-alias` command to add this alias to the bot.)",
-            title, closest.0, closest.1
-        );
-        let sent = ctx
-            .send(|f| {
-                let mut f = f.ephemeral(true).content(reply);
-                if let Context::Application(_) = ctx {
-                    f = f.components(|c| {
-                        let mut button = CreateButton::default();
-                        button.custom_id(closest.0);
-                        button.label(format!("Yes (times out after {} seconds)", 10));
-                        let mut ar = CreateActionRow::default();
-                        ar.add_button(button);
-                        c.set_action_row(ar)
-                    })
-                }
-                f
-            })
-            .await?;
-        let reply = sent.into_message().await?;
-        if let Context::Application(poise_ctx) = ctx {
-            let serenity_ctx = poise_ctx.serenity_context();
-            let mci = match reply
-                .await_component_interaction(serenity_ctx)
-                .timeout(Duration::from_secs(10))
-                .await
-            {
-                Some(ci) => ci,
-                None => {
-                    // ctx.send(|f| f.ephemeral(true).content("Timed out"))
-                    //     .await
-                    //     .unwrap();
-                    return Ok(());
-                }
-            };
-            let actual_title = get_title(
-                &mci.data.custom_id,
-                aliases_template,
-                ctx.guild_id()
-                    .unwrap_or(poise::serenity_prelude::GuildId(0)),
-            )
-            .unwrap();
-            mci.create_interaction_response(&serenity_ctx.http, |r| {
-                r.kind(InteractionResponseType::ChannelMessageWithSource)
-                    .interaction_response_data(|d| {
-                        let jacket = get_jp_jacket(ctx, game, &actual_title);
-                        if let Some(jacket) = jacket {
-                            d.content(format!("Query by <@{}>", ctx.author().id))
-                                .add_file(AttachmentType::Image(
-                                    url::Url::parse(&format!(
-                                        "{}{}",
-                                        get_url_prefix(ctx, game),
-                                        jacket
-                                    ))
-                                    .unwrap(),
-                                ));
-                        }
-                        d
-                    })
-            })
-            .await?;
-        }
-        if let Context::Application(poise_ctx) = ctx {
-            let serenity_ctx = poise_ctx.serenity_context();
-            let mci = match reply
-                .await_component_interaction(serenity_ctx)
-                .timeout(Duration::from_secs(10))
-                .await
-            {
-                Some(ci) => ci,
-                None => {
-                    // ctx.send(|f| f.ephemeral(true).content("Timed out"))
-                    //     .await
-                    //     .unwrap();
-                    return Ok(());
-                }
-            };
-            let actual_title = get_title(
-                &mci.data.custom_id,
-                aliases_template,
-                ctx.guild_id()
-                    .unwrap_or(poise::serenity_prelude::GuildId(0)),
-            )
-            .unwrap();
-            mci.create_interaction_response(&serenity_ctx.http, |r| {
-                r.kind(InteractionResponseType::ChannelMessageWithSource)
-                    .interaction_response_data(|d| {
-                        let jacket = get_jp_jacket(ctx, game, &actual_title);
-                        if let Some(jacket) = jacket {
-                            d.content(format!("Query by <@{}>", ctx.author().id))
-                                .add_file(AttachmentType::Image(
-                                    url::Url::parse(&format!(
-                                        "{}{}",
-                                        get_url_prefix(ctx, game),
-                                        jacket
-                                    ))
-                                    .unwrap(),
-                                ));
-                        }
-                        d
-                    })
-            })
+        return suggest(ctx, catalog, &title, &closest, Action::Jacket).await;
+    };
+    if let Some(attachment) = jacket_attachment(ctx.data(), catalog, &title).await {
+        ctx.send(|f| f.ephemeral(settings.ephemeral).attachment(attachment))
             .await?;
-        }
-        return Ok(());
-    }
-    let title = actual_title.unwrap();
-    let jacket = get_jp_jacket(ctx, game, &title);
-    if let Some(jacket) = jacket {
-        ctx.send(|f| {
-            f.attachment(AttachmentType::Image(
-                url::Url::parse(&format!("{}{}", get_url_prefix(ctx, game), jacket)).unwrap(),
-            ))
-        })
-        .await?;
     }
     Ok(())
 }
 
-/// (description, jacket)
-type GetEmbed =
-    Arc<dyn Fn(String, &Context<'_>) -> eyre::Result<(String, Option<String>)> + Sync + Send>;
+/// The jacket of `title` as an attachment: the cached image, or its URL if caching fails.
+pub async fn jacket_attachment(
+    data: &Data,
+    catalog: &dyn GameCatalog,
+    title: &str,
+) -> Option<AttachmentType<'static>> {
+    let jacket = catalog.song(title)?.jp_jacket()?.to_string();
+    let url = catalog.jacket_url(title)?;
+    let game = catalog.id().to_string();
+    let jackets = data.jackets.clone();
+    let fetch = {
+        let url = url.clone();
+        move || jackets.get_named(&game, &jacket, &url)
+    };
+    match tokio::task::spawn_blocking(fetch).await {
+        Ok(Ok((filename, bytes))) => Some(AttachmentType::Bytes {
+            data: Cow::Owned(bytes),
+            filename,
+        }),
+        result => {
+            if let Ok(Err(e)) = result {
+                eprintln!("Jacket cache: {:?}", e);
+            }
+            Some(AttachmentType::Image(url::Url::parse(&url).ok()?))
+        }
+    }
+}
+
+/// The chart card of `title` as a PNG attachment, drawn with the cached jacket if there is one.
+pub async fn card_attachment(
+    data: &Data,
+    catalog: &dyn GameCatalog,
+    title: &str,
+    options: &RenderOptions,
+) -> eyre::Result<AttachmentType<'static>> {
+    let mut card = ChartCard::from_catalog(catalog, title)
+        .ok_or_else(|| eyre::eyre!("No data for {title}"))?;
+    card.apply(options);
+    let jacket = catalog
+        .song(title)
+        .and_then(|song| song.jp_jacket())
+        .zip(catalog.jacket_url(title))
+        .map(|(jacket, url)| (jacket.to_string(), url));
+    let game = catalog.id().to_string();
+    let jackets = data.jackets.clone();
+    let fonts = data.fonts.clone();
+    let draw = move || {
+        let jacket = jacket.and_then(|(jacket, url)| {
+            jackets
+                .get(&game, &jacket, &url)
+                .map_err(|e| eprintln!("Jacket cache: {:?}", e))
+                .ok()
+        });
+        card.render(&fonts, jacket.as_deref())
+    };
+    let png = tokio::task::spawn_blocking(draw).await??;
+    Ok(AttachmentType::Bytes {
+        data: Cow::Owned(png),
+        filename: "card.png".to_string(),
+    })
+}
 
 pub async fn info_template(
     ctx: Context<'_>,
+    catalog: &dyn GameCatalog,
     title: String,
-    game: Game,
-    get_embed: GetEmbed,
-    color: (u8, u8, u8),
-    duplicate_alias_to_title: Arc<dyn Fn(&String) -> String + Sync + Send>,
+    card: bool,
 ) -> eyre::Result<()> {
-    let aliases = get_aliases(ctx, game);
-    // let actual_title = get_title(
-    //     &title,
-    //     aliases_template,
-    //     ctx.guild_id()
-    //         .unwrap_or(poise::serenity_prelude::GuildId(0)),
-    // );
-    let actual_title = get_title(
+    let Some(settings) = query_settings(ctx).await? else {
+        return Ok(());
+    };
+    let options = render_options(ctx, &settings).await;
+    let aliases = catalog.aliases();
+    let actual_title = catalog.lookup(
         &title,
-        aliases,
         ctx.guild_id()
             .unwrap_or(poise::serenity_prelude::GuildId(0)),
     );
-    if actual_title.is_none() {
+    let Some(title) = actual_title else {
         let mut log = ctx.data().alias_log.lock().await;
-        writeln!(log, "{}\t{:?}", title, game)?;
+        writeln!(log, "{}\t{}", title, catalog.name())?;
         log.sync_all()?;
         drop(log);
         let closest = get_closest_title(
@@ -1206,124 +1337,19 @@ pub async fn info_template(
             ctx.guild_id()
                 .unwrap_or(poise::serenity_prelude::GuildId(0)),
         );
-        let reply = format!(
-            "I couldn't find the results for **{}**;
-Did you mean **{}** (for **{}**)?
-(P.S. You can also use the `/add-alias` command to add this alias to the bot.)",
-            title, closest.0, closest.1
-        );
-        let sent = ctx
-            .send(|f| {
-                let mut f = f.ephemeral(true).content(reply);
-                if let Context::Application(_) = ctx {
-                    f = f.components(|c| {
-                        let mut button = CreateButton::default();
-                        button.custom_id(closest.0);
-                        button.label(format!("Yes (times out after {} seconds)", 10));
-                        let mut ar = CreateActionRow::default();
-                        ar.add_button(button);
-                        c.set_action_row(ar)
-                    })
-                }
-                f
-            })
-            .await?;
-        let reply = sent.into_message().await?;
-        if let Context::Application(poise_ctx) = ctx {
-            let serenity_ctx = poise_ctx.serenity_context();
-            let mci = match reply
-                .await_component_interaction(serenity_ctx)
-                .timeout(Duration::from_secs(10))
-                .await
-            {
-                Some(ci) => ci,
-                None => {
-                    // ctx.send(|f| f.ephemeral(true).content("Timed out"))
-                    //     .await
-                    //     .unwrap();
-                    return Ok(());
-                }
-            };
-            let actual_title = get_title(
-                &mci.data.custom_id,
-                aliases,
-                ctx.guild_id()
-                    .unwrap_or(poise::serenity_prelude::GuildId(0)),
-            )
-            .unwrap();
-            mci.create_interaction_response(&serenity_ctx.http, |r| {
-                r.kind(InteractionResponseType::ChannelMessageWithSource)
-                    .interaction_response_data(|d| {
-                        // Make the message hidden for other users by setting `ephemeral(true)`.
-                        d.ephemeral(false)
-                            .content(format!("Query by <@{}>", ctx.author().id))
-                            .embed(|f| {
-                                let (description, jacket) =
-                                    get_embed(actual_title.to_string(), &ctx).unwrap();
-
-                                let mut f = f
-                                    .title(duplicate_alias_to_title(&actual_title))
-                                    .description(description)
-                                    .color(Color::from_rgb(color.0, color.1, color.2));
-                                if let Some(jacket) = jacket {
-                                    f = f.thumbnail(format!(
-                                        "{}{}",
-                                        get_url_prefix(ctx, game),
-                                        jacket
-                                    ));
-                                }
-
-                                f
-                            })
-                    })
-            })
-            .await?;
-        }
-        return Ok(());
-    }
+        let action = if card { Action::Card } else { Action::Info };
+        return suggest(ctx, catalog, &title, &closest, action).await;
+    };
 
-    match check_cooldown(&ctx).await {
-        Cooldown::Channel(t) => {
-            let is_slash_command = matches!(&ctx, poise::Context::Application(_));
-            ctx.send(|f| {
-                f.ephemeral(is_slash_command).content(format!(
-                    "Channel cooldown: please wait {} seconds and try again, or try the #bot-commands channel for no cooldown.",
-                    t
-                ))
-            })
+    if card {
+        let attachment = card_attachment(ctx.data(), catalog, &title, &options).await?;
+        ctx.send(|f| f.ephemeral(settings.ephemeral).attachment(attachment))
             .await?;
-            return Ok(());
-        }
-        Cooldown::User(t) => {
-            if let poise::Context::Application(_) = &ctx {
-                ctx.send(|f| {
-                    f.ephemeral(true).content(format!(
-                        "Channel cooldown: please wait {} seconds and try again, or try the #bot-commands channel for no cooldown.",
-                        t
-                    ))
-                })
-                .await?;
-            }
-            return Ok(());
-        }
-        Cooldown::None => (),
+        return Ok(());
     }
-    let title = actual_title.unwrap();
-    let (description, jacket) = get_embed(title.clone(), &ctx)?;
-
-    ctx.send(|f| {
-        f.embed(|f| {
-            let mut f = f
-                .title(duplicate_alias_to_title(&title).replace('*', "\\*"))
-                .description(description)
-                .color(Color::from_rgb(color.0, color.1, color.2));
-            if let Some(jacket) = jacket {
-                f = f.thumbnail(format!("{}{}", get_url_prefix(ctx, game), jacket));
-            }
+    let document = catalog.document_with(&title, &options)?;
 
-            f
-        })
-    })
-    .await?;
+    ctx.send(|f| f.ephemeral(settings.ephemeral).embed(|f| document.embed(f)))
+        .await?;
     Ok(())
 }
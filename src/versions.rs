@@ -0,0 +1,251 @@
+//! Game versions in release order, read from `data/versions.toml`.
+//!
+//! Every chart gets a typed added-in `Version` out of this, so searches can ask for charts
+//! new in the current version of a region. A new version is one more line in the data file.
+//! The current JP version is the latest one released, unless the config names another.
+
+use std::{collections::BTreeMap, fs, path::Path, sync::OnceLock};
+
+use chrono::NaiveDate;
+use eyre::{ensure, eyre, WrapErr};
+use serde::Deserialize;
+
+use crate::{
+    catalog::Region,
+    config::{config, data_path},
+};
+
+/// Relative to `data_dir`.
+pub const VERSIONS_FILE: &str = "versions.toml";
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Version {
+    pub name: String,
+    /// Other spellings the song data uses for this version.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// JP release date.
+    pub jp: NaiveDate,
+    /// International release date; `None` if it never came out there.
+    pub intl: Option<NaiveDate>,
+    /// For acts (e.g. "bright MEMORY Act.2"), the version they are part of.
+    pub act_of: Option<String>,
+    /// Game id of the list this version is in.
+    #[serde(skip)]
+    pub game: String,
+    /// Position in the game's release order.
+    #[serde(skip)]
+    pub index: usize,
+}
+
+impl Version {
+    pub fn release(&self, region: Region) -> Option<NaiveDate> {
+        match region {
+            Region::Jp => Some(self.jp),
+            Region::Intl => self.intl,
+        }
+    }
+
+    /// The version this is an act of, or its own name.
+    pub fn series(&self) -> &str {
+        self.act_of.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Whether this is the latest version released in `region` as of today.
+    pub fn is_current(&self, region: Region) -> bool {
+        versions()
+            .game(&self.game)
+            .and_then(|list| list.current(region))
+            .is_some_and(|current| current == self)
+    }
+
+    /// Whether charts of this version count as new songs in `region` for rating,
+    /// i.e. it is the current version or an earlier act of it.
+    pub fn is_new(&self, region: Region) -> bool {
+        versions()
+            .game(&self.game)
+            .is_some_and(|list| list.new_frame(region).contains(&self))
+    }
+}
+
+/// Versions of one game, oldest first.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionList {
+    /// Put in front of version names by some sources, e.g. "CHUNITHM" in "CHUNITHM AIR".
+    #[serde(default)]
+    pub prefix: Option<String>,
+    pub versions: Vec<Version>,
+    /// Index of the configured current JP version.
+    #[serde(skip)]
+    pinned: Option<usize>,
+}
+
+/// Case and whitespace don't matter when matching names.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl VersionList {
+    /// Matching key of a name: normalized, without the prefix unless that's all there is.
+    fn key(&self, name: &str) -> String {
+        let name = normalize(name);
+        let prefix = self.prefix.as_deref().map(normalize).unwrap_or_default();
+        match name.strip_prefix(&prefix) {
+            Some(rest) if !rest.is_empty() => rest.to_string(),
+            _ => name,
+        }
+    }
+
+    /// The version called `name`, with or without the game's prefix.
+    pub fn find(&self, name: &str) -> Option<&Version> {
+        let key = self.key(name);
+        self.versions
+            .iter()
+            .find(|v| self.key(&v.name) == key || v.aliases.iter().any(|a| self.key(a) == key))
+    }
+
+    /// The latest version released in `region` on or before `date`.
+    pub fn at(&self, date: NaiveDate, region: Region) -> Option<&Version> {
+        self.versions
+            .iter()
+            .rev()
+            .find(|v| v.release(region).is_some_and(|release| release <= date))
+    }
+
+    /// The current version in `region` as of `date`: the pinned one in JP, or the latest released.
+    pub fn current_at(&self, date: NaiveDate, region: Region) -> Option<&Version> {
+        match (region, self.pinned) {
+            (Region::Jp, Some(index)) => self.versions.get(index),
+            _ => self.at(date, region),
+        }
+    }
+
+    pub fn current(&self, region: Region) -> Option<&Version> {
+        self.current_at(chrono::Local::now().date_naive(), region)
+    }
+
+    /// Versions in the new-songs rating frame as of `date`: the current one and its released acts.
+    pub fn new_frame_at(&self, date: NaiveDate, region: Region) -> Vec<&Version> {
+        let Some(current) = self.current_at(date, region) else {
+            return vec![];
+        };
+        self.versions
+            .iter()
+            .filter(|v| v.series() == current.series() && v.index <= current.index)
+            .filter(|v| v.release(region).is_some())
+            .collect()
+    }
+
+    pub fn new_frame(&self, region: Region) -> Vec<&Version> {
+        self.new_frame_at(chrono::Local::now().date_naive(), region)
+    }
+}
+
+/// Version lists keyed by game id.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Versions {
+    games: BTreeMap<String, VersionList>,
+}
+
+impl Versions {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let text = fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {:?}", path))?;
+        Self::parse(&text).wrap_err_with(|| format!("Invalid version list {:?}", path))
+    }
+
+    /// Parses and checks that every list is in release order.
+    pub fn parse(text: &str) -> eyre::Result<Self> {
+        let mut versions: Versions = toml::from_str(text)?;
+        for (game, list) in &mut versions.games {
+            for (index, version) in list.versions.iter_mut().enumerate() {
+                version.game = game.clone();
+                version.index = index;
+            }
+            for region in [Region::Jp, Region::Intl] {
+                let dates = list
+                    .versions
+                    .iter()
+                    .filter_map(|v| Some((&v.name, v.release(region)?)))
+                    .collect::<Vec<_>>();
+                for pair in dates.windows(2) {
+                    ensure!(
+                        pair[0].1 <= pair[1].1,
+                        "{}: {} is listed after {} but released before it ({:?})",
+                        game,
+                        pair[1].0,
+                        pair[0].0,
+                        region
+                    );
+                }
+            }
+        }
+        Ok(versions)
+    }
+
+    pub fn game(&self, game: &str) -> Option<&VersionList> {
+        self.games.get(game)
+    }
+
+    /// Makes the version called `name` the current JP version of `game`, whatever the release
+    /// dates say.
+    pub fn pin_current(&mut self, game: &str, name: &str) -> eyre::Result<()> {
+        let list = self
+            .games
+            .get_mut(game)
+            .ok_or_else(|| eyre!("There are no versions of {}", game))?;
+        let index = list
+            .find(name)
+            .ok_or_else(|| eyre!("{} has no version called {:?}", game, name))?
+            .index;
+        list.pinned = Some(index);
+        Ok(())
+    }
+
+    /// Shorthand for `game(game)?.find(name)`.
+    pub fn find(&self, game: &str, name: &str) -> Option<&Version> {
+        self.game(game)?.find(name)
+    }
+}
+
+static VERSIONS: OnceLock<Versions> = OnceLock::new();
+
+/// The lists in `data_dir` with each game's configured `current_version` pinned.
+fn load_configured() -> eyre::Result<Versions> {
+    let mut versions = Versions::load(&data_path(VERSIONS_FILE))?;
+    for game in ["maimai", "chuni", "ongeki"] {
+        if let Some(name) = config().current_version(game) {
+            versions
+                .pin_current(game, name)
+                .wrap_err_with(|| format!("Invalid {}.current_version", game))?;
+        }
+    }
+    Ok(versions)
+}
+
+/// Loads the version lists; call at startup to report errors before anything else happens.
+pub fn init() -> eyre::Result<&'static Versions> {
+    if let Some(versions) = VERSIONS.get() {
+        return Ok(versions);
+    }
+    let versions = load_configured()?;
+    Ok(VERSIONS.get_or_init(|| versions))
+}
+
+/// Uses `versions` instead of the file in `data_dir`, e.g. so tests on fixture data check the
+/// real lists. Fails if the lists are already loaded.
+pub fn set(versions: Versions) -> eyre::Result<()> {
+    VERSIONS
+        .set(versions)
+        .map_err(|_| eyre::eyre!("The version lists are already loaded"))
+}
+
+/// The version lists in `data_dir`, loaded on first use.
+pub fn versions() -> &'static Versions {
+    VERSIONS.get_or_init(|| load_configured().unwrap_or_else(|e| panic!("{:?}", e)))
+}
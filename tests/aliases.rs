@@ -0,0 +1,88 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use gcm_bot::{
+    chuni::set_chuni_charts, maimai::set_mai_charts, ongeki::set_ongeki_charts, utils::*,
+};
+use poise::serenity_prelude::GuildId;
+
+/// The server with a community alias in `data/aliases/manual/maimai.tsv`.
+const ALIAS_SERVER: GuildId = GuildId(42);
+
+fn resolution_table<T>(charts: &HashMap<String, T>, game: &str, queries: &[&str]) -> String {
+    let aliases = set_aliases(charts.keys(), game).unwrap();
+    let mut table = String::new();
+    for query in queries {
+        for server in [GuildId(0), ALIAS_SERVER] {
+            table.push_str(&format!(
+                "{}\t{}\t{:?}\n",
+                query,
+                server,
+                get_title(query, &aliases, server)
+            ));
+        }
+    }
+    table
+}
+
+#[test]
+fn maimai_aliases() {
+    enter_fixtures();
+    let queries = [
+        "Selector",
+        "selector",
+        "S E L E C T O R",
+        "Bad Apple!! feat.nomico",
+        "badapplefeatnomico",
+        "bad apple",
+        "BAD-APPLE",
+        "sel",
+        "셀렉터",
+        "Link",
+        "Link (maimai)",
+        "old link",
+        "Forgotten Song",
+        "no such song",
+    ];
+    assert_golden(
+        "maimai-aliases.txt",
+        &resolution_table(&set_mai_charts().unwrap(), "maimai", &queries),
+    );
+}
+
+#[test]
+fn chuni_aliases() {
+    enter_fixtures();
+    let queries = [
+        "Elemental Creation",
+        "ec",
+        "E.C.",
+        "zebel",
+        "reach for the stars",
+        "Gone Away",
+        "狂",
+    ];
+    assert_golden(
+        "chuni-aliases.txt",
+        &resolution_table(&set_chuni_charts().unwrap(), "chuni", &queries),
+    );
+}
+
+#[test]
+fn ongeki_aliases() {
+    enter_fixtures();
+    let queries = [
+        "Singularity",
+        "singularity (arcaea)",
+        "Arcaea Singularity",
+        "opfer",
+        "Lunatic Only",
+        "Vanished Lunatic",
+    ];
+    assert_golden(
+        "ongeki-aliases.txt",
+        &resolution_table(&set_ongeki_charts().unwrap(), "ongeki", &queries),
+    );
+}
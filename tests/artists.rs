@@ -0,0 +1,84 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    artists::*, catalog::GameCatalog, chuni::set_chuni_charts, crossover::Charts,
+    maimai::set_mai_charts, ongeki::set_ongeki_charts, utils::Game,
+};
+
+#[test]
+fn credits_split_into_artists() {
+    let aliases = ArtistAliases::parse("Sta & Tatsh\tSta＆Tatsh\nt+pazolite\ttpz\n");
+    let split = |credit| split_artists(credit, &aliases);
+
+    assert_eq!(split("t+pazolite"), ["t+pazolite"]);
+    assert_eq!(split("Camellia feat. Nanahira"), ["Camellia", "Nanahira"]);
+    assert_eq!(split("HiTECH NINJA vs Cranky"), ["HiTECH NINJA", "Cranky"]);
+    assert_eq!(split("t+pazolite × Getty"), ["t+pazolite", "Getty"]);
+    assert_eq!(split("A, B & C"), ["A", "B", "C"]);
+    assert_eq!(
+        split("星咲 あかり(CV：赤﨑 千夏)"),
+        ["星咲 あかり", "赤﨑 千夏"]
+    );
+    assert_eq!(split("Ice (Drums)"), ["Ice (Drums)"]);
+    // Names in the alias file are never split
+    assert_eq!(split("Sta & Tatsh"), ["Sta & Tatsh"]);
+    assert_eq!(artist_key("ｔ＋Pazolite "), "t+pazolite");
+}
+
+#[test]
+fn artists_across_games() {
+    enter_fixtures();
+    let mut maimai = set_mai_charts().unwrap();
+    maimai.get_mut("Selector").unwrap().artist = "t+pazolite feat. Nanahira".to_string();
+    let mut chuni = set_chuni_charts().unwrap();
+    chuni.get_mut("Gone Away").unwrap().artist = "T+PAZOLITE".to_string();
+    chuni.get_mut("Xevel").unwrap().artist = "tpz vs. Camellia".to_string();
+    let mut ongeki = set_ongeki_charts().unwrap();
+    ongeki.get_mut("Opfer").unwrap().artist = "t+pazolite".to_string();
+    let aliases = ArtistAliases::parse("t+pazolite\ttpz\n");
+    let index = ArtistIndex::build(
+        Charts {
+            maimai: &maimai,
+            chuni: &chuni,
+            ongeki: &ongeki,
+        },
+        aliases,
+    );
+
+    let artist = index.find("TPZ").unwrap();
+    assert_eq!(artist.name, "t+pazolite");
+    assert_eq!(artist.songs(Game::Maimai), ["Selector"]);
+    assert_eq!(artist.songs(Game::Chunithm), ["Gone Away", "Xevel"]);
+    assert_eq!(artist.songs(Game::Ongeki), ["Opfer"]);
+    assert_eq!(
+        index.find("nanahira").unwrap().songs(Game::Maimai),
+        ["Selector"]
+    );
+    assert!(index.find("t+pazolit").is_none());
+    assert_eq!(index.closest("t+pazolit").unwrap().name, "t+pazolite");
+    assert!(index.closest("zzzzzz").is_none());
+
+    let maimai = catalog(maimai, "maimai");
+    let chuni = catalog(chuni, "chuni");
+    let ongeki = catalog(ongeki, "ongeki");
+    let catalogs: [(Game, &dyn GameCatalog); 3] = [
+        (Game::Maimai, &maimai),
+        (Game::Chunithm, &chuni),
+        (Game::Ongeki, &ongeki),
+    ];
+    let first = artist_page(catalogs, artist, 0);
+    assert_eq!(first.pages, 3);
+    assert!(first
+        .document
+        .description
+        .starts_with("**maimai** (1–1 of 1)\n**Selector** DX "));
+    assert!(first.document.description.ends_with("Page 1/3"));
+    let second = artist_page(catalogs, artist, 1);
+    assert!(second
+        .document
+        .description
+        .contains("(1–2 of 2)\n**Gone Away** "));
+    // Pages past the end show the last one
+    assert_eq!(artist_page(catalogs, artist, 9).page, 2);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use gcm_bot::{
+    card::ChartCard,
+    catalog::*,
+    chuni::set_chuni_charts,
+    images::*,
+    maimai::{mai_info, set_mai_charts},
+    utils::required_options_first,
+};
+
+fn fonts() -> Fonts {
+    Fonts::load(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join(FONT_DIR),
+    )
+    .unwrap()
+}
+
+fn decode(png: &[u8]) -> image::RgbaImage {
+    image::load_from_memory_with_format(png, image::ImageFormat::Png)
+        .unwrap()
+        .to_rgba8()
+}
+
+#[test]
+fn maimai_card() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let card = ChartCard::from_catalog(&maimai, "Link (maimai)").unwrap();
+    assert_eq!(card.title, "Link");
+    assert!(card.regional);
+
+    let jacket = image::RgbaImage::from_pixel(50, 50, image::Rgba([0, 0, 255, 255]));
+    let jacket = encode_png(&jacket).unwrap();
+    let image = decode(&card.render(&fonts(), Some(&jacket)).unwrap());
+    // Bar, jacket and two rows: JP ST and INTL ST
+    assert_eq!(image.dimensions(), (900, 8 + 48 + 200 + 2 * 80 + 24 - 8));
+    assert_eq!(image.get_pixel(0, 0).0, [0, 255, 255, 255]);
+    assert_eq!(image.get_pixel(124, 132).0, [0, 0, 255, 255]);
+
+    // Only the JP row for JP-only render options
+    let mut jp = card.clone();
+    jp.apply(&RenderOptions {
+        region: Some(Region::Jp),
+        ..Default::default()
+    });
+    let image = decode(&jp.render(&fonts(), Some(&jacket)).unwrap());
+    assert_eq!(image.height(), 8 + 48 + 200 + 80 + 24 - 8);
+
+    // Unreadable jackets get a placeholder instead of failing the card
+    let image = decode(&card.render(&fonts(), Some(b"not an image")).unwrap());
+    assert_eq!(image.get_pixel(30, 40).0, [200, 200, 200, 255]);
+    assert!(ChartCard::from_catalog(&maimai, "No such song").is_none());
+}
+
+#[test]
+fn chuni_card() {
+    enter_fixtures();
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    for (title, song) in chuni.songs() {
+        let card = ChartCard::from_catalog(&chuni, title).unwrap();
+        let rows = song
+            .chart_entries()
+            .iter()
+            .map(|chart| chart.region)
+            .collect::<std::collections::HashSet<_>>()
+            .len() as u32;
+        let image = decode(&card.render(&fonts(), None).unwrap());
+        assert_eq!(
+            image.height(),
+            8 + 48 + 200 + rows * 80 + 24 - 8,
+            "{}",
+            title
+        );
+    }
+}
+
+#[test]
+fn japanese_and_korean_titles() {
+    let fonts = fonts();
+    for title in ["封焔の135秒", "セレクター", "강남스타일"] {
+        assert!(title.chars().all(|c| fonts.has_glyph(c)), "{}", title);
+    }
+    assert!(!fonts.has_glyph('\u{e000}'));
+
+    let card = |title: &str| ChartCard {
+        title: title.to_string(),
+        artist: "아티스트 / アーティスト".to_string(),
+        version: None,
+        deleted: false,
+        color: (0, 255, 255),
+        difficulty_names: vec!["BASIC".to_string()],
+        regional: false,
+        charts: vec![],
+    };
+    // The first line of text next to the jacket
+    let title_area = |title: &str| {
+        let image = decode(&card(title).render(&fonts, None).unwrap());
+        image::imageops::crop_imm(&image, 248, 32, 200, 44)
+            .to_image()
+            .into_raw()
+    };
+    // Missing glyphs would all be the same box
+    let japanese = title_area("封焔");
+    let korean = title_area("강남");
+    let missing = title_area("\u{e000}\u{e001}");
+    assert_ne!(japanese, missing);
+    assert_ne!(korean, missing);
+    assert_ne!(japanese, korean);
+}
+
+#[test]
+fn many_difficulties_wrap() {
+    let names = (1..=10).map(|n| format!("LEVEL {}", n)).collect::<Vec<_>>();
+    let card = ChartCard {
+        title: "Song".to_string(),
+        artist: "Artist".to_string(),
+        version: None,
+        deleted: false,
+        color: (0, 255, 255),
+        difficulty_names: names,
+        regional: false,
+        charts: (0..10)
+            .map(|difficulty| ChartEntry {
+                region: Region::Jp,
+                chart_type: None,
+                difficulty,
+                level: (difficulty + 1).to_string(),
+                constant: None,
+                version: None,
+            })
+            .collect(),
+    };
+    // Six boxes to a line, so one row takes two lines
+    let image = decode(&card.render(&fonts(), None).unwrap());
+    assert_eq!(image.dimensions(), (900, 8 + 48 + 200 + 2 * 80 + 24 - 8));
+    assert_eq!(image.get_pixel(150, 300).0, [0, 255, 255, 255]);
+    assert_eq!(image.get_pixel(150, 380).0, [0, 255, 255, 255]);
+}
+
+#[test]
+fn font_loading() {
+    let dir = std::env::temp_dir().join(format!("gcm-no-fonts-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    assert!(Fonts::load(&dir).is_err());
+    std::fs::write(dir.join("broken.ttf"), "not a font").unwrap();
+    assert!(Fonts::load(&dir).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let fonts = fonts();
+    assert!(fonts.text_width("Link", 20.) > 0.);
+    let fitted = fonts.fit("A very long song title indeed", 20., 100.);
+    assert!(fitted.ends_with('…') && fonts.text_width(&fitted, 20.) <= 100.);
+}
+
+#[test]
+fn card_flag_after_title() {
+    let mut command = mai_info();
+    required_options_first(&mut command);
+    let names = command
+        .parameters
+        .iter()
+        .map(|param| param.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["title", "card"]);
+}
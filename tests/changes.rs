@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+
+use common::*;
+use gcm_bot::{catalog::*, changes::*, chuni::set_chuni_charts, maimai::set_mai_charts};
+
+fn live() -> Snapshot {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    Snapshot::capture(&[("maimai", &maimai as &dyn GameCatalog), ("chuni", &chuni)])
+}
+
+#[test]
+fn report() {
+    let new = live();
+    let mut old = new.clone();
+
+    let chuni = &mut old.games.get_mut("chuni").unwrap().songs;
+    // Shows up as a new song
+    chuni.remove("Xevel").unwrap();
+    // Shows up as newly deleted
+    let mut gone = chuni["Gone Away"].clone();
+    gone.deleted = false;
+    chuni.insert("Removed Song".to_string(), gone);
+    // Constant and level changes, and a new ULTIMA
+    let song = chuni.get_mut("Elemental Creation").unwrap();
+    song.charts.retain(|c| c.difficulty != "ULTIMA");
+    for chart in &mut song.charts {
+        if chart.difficulty == "MASTER" && chart.region == "JP" {
+            chart.constant = Some(13.5);
+        }
+        if chart.difficulty == "EXPERT" && chart.region == "INTL" {
+            chart.level = "11+".to_string();
+            chart.constant = Some(11.9);
+        }
+    }
+    // Removed from INTL
+    let song = chuni.get_mut("Reach For The Stars").unwrap();
+    let intl = song
+        .charts
+        .iter()
+        .map(|chart| ChartSnapshot {
+            region: "INTL".to_string(),
+            ..chart.clone()
+        })
+        .collect::<Vec<_>>();
+    song.charts.extend(intl);
+    // New in INTL
+    let maimai = &mut old.games.get_mut("maimai").unwrap().songs;
+    maimai
+        .get_mut("Selector")
+        .unwrap()
+        .charts
+        .retain(|c| c.region == "JP");
+    // A removed Re:MASTER
+    let link = maimai.get_mut("Link").unwrap();
+    let mut remas = link.charts[0].clone();
+    remas.difficulty = "Re:MASTER".to_string();
+    remas.level = "13".to_string();
+    remas.constant = Some(13.2);
+    link.charts.push(remas);
+
+    let changes = diff(&old, &new);
+    assert_golden("changes.txt", &changes_markdown(&changes));
+}
+
+#[test]
+fn rotation() {
+    let dir = std::env::temp_dir().join(format!("gcm-snapshots-{}", std::process::id()));
+    let live = live();
+    let mut older = live.clone();
+    older.games.get_mut("chuni").unwrap().songs.clear();
+
+    // First run: nothing to compare with yet
+    assert_eq!(rotate_snapshots(&dir, &older).unwrap(), None);
+    // Data changed: the old data becomes the comparison base
+    assert_eq!(rotate_snapshots(&dir, &live).unwrap(), Some(older.clone()));
+    // Restart without changes keeps the same base
+    assert_eq!(rotate_snapshots(&dir, &live).unwrap(), Some(older));
+    assert_eq!(Snapshot::load(&dir.join("latest.json")).unwrap(), live);
+    fs::remove_dir_all(dir).unwrap();
+}
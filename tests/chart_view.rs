@@ -0,0 +1,158 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    catalog::*,
+    chart_view::*,
+    chuni::set_chuni_charts,
+    components::{Action, ComponentId},
+    maimai::set_mai_charts,
+};
+
+#[test]
+fn one_chart_at_a_time() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let view = |choice, options| chart_view(&maimai, "Selector", choice, &options).unwrap();
+
+    // MASTER of the first chart type by default
+    let master = view(ChartChoice::default(), RenderOptions::default());
+    assert!(master.document.description.starts_with(
+        "**DX MASTER**\n:flag_jp: Lv.**14** (14.1)\n:globe_with_meridians: **Unreleased**\n"
+    ));
+    assert!(master
+        .document
+        .description
+        .contains("**Designer:** designer3"));
+    assert!(master
+        .document
+        .description
+        .contains("**Notes:** **488** (Tap 400 / Hold 40 / Slide 20 / Touch 20 / Break 8)"));
+    assert!(master
+        .document
+        .description
+        .contains("search_query=maimai+Selector+DX+MASTER"));
+
+    let st_basic = view(
+        ChartChoice {
+            chart_type: Some("ST"),
+            difficulty: Some(0),
+        },
+        RenderOptions {
+            region: Some(Region::Jp),
+            constants: false,
+            links: false,
+            ..Default::default()
+        },
+    );
+    let description = &st_basic.document.description;
+    assert!(description.starts_with("**ST BASIC**\n:flag_jp: Lv.**"));
+    assert!(!description.contains(":globe_with_meridians:"));
+    assert!(!description.lines().nth(1).unwrap().contains('('));
+    assert!(!description.contains("youtube"));
+    // ST charts have no touch notes
+    assert!(!description.contains("Touch"));
+
+    // A difficulty the chart type doesn't have falls back to MASTER
+    let fallback = view(
+        ChartChoice {
+            chart_type: Some("ST"),
+            difficulty: Some(4),
+        },
+        RenderOptions::default(),
+    );
+    assert!(fallback.document.description.starts_with("**ST MASTER**"));
+}
+
+#[test]
+fn switcher_buttons() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let view = chart_view(
+        &maimai,
+        "Selector",
+        ChartChoice::default(),
+        &RenderOptions::default(),
+    )
+    .unwrap();
+    let labels = |row: &[ViewButton]| row.iter().map(|b| b.label.clone()).collect::<Vec<_>>();
+    assert_eq!(view.rows.len(), 2);
+    assert_eq!(labels(&view.rows[0]), ["DX", "ST"]);
+    assert_eq!(
+        labels(&view.rows[1]),
+        ["BASIC", "ADVANCED", "EXPERT", "MASTER"]
+    );
+    let current = view
+        .rows
+        .iter()
+        .flatten()
+        .filter(|b| b.current)
+        .map(|b| b.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(current, ["DX", "MASTER"]);
+
+    // Every button leads to a chart, and ids in a message are unique
+    let mut ids = view
+        .rows
+        .iter()
+        .flatten()
+        .map(|b| b.custom_id.clone())
+        .collect::<Vec<_>>();
+    for id in &ids {
+        let id = ComponentId::parse(id).unwrap();
+        assert_eq!(id.title(&maimai).as_deref(), Some("Selector"));
+        assert!(matches!(id.action, Action::Chart(_)));
+    }
+    let count = ids.len();
+    ids.dedup();
+    assert_eq!(ids.len(), count);
+    // Switching type keeps the difficulty
+    assert_eq!(view.rows[0][1].custom_id, "gcm:chart.ST.3:maimai:Selector");
+
+    // Games without chart types get a single row with ULTIMA
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    let view = chart_view(
+        &chuni,
+        "Elemental Creation",
+        ChartChoice {
+            chart_type: None,
+            difficulty: Some(4),
+        },
+        &RenderOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(view.rows.len(), 1);
+    assert_eq!(view.rows[0][4].label, "ULTIMA");
+    assert!(view.rows[0][4].current);
+    assert!(view.document.description.starts_with("**ULTIMA**\n"));
+}
+
+#[test]
+fn choices_in_custom_ids() {
+    for choice in [
+        ChartChoice::default(),
+        ChartChoice {
+            chart_type: Some("DX"),
+            difficulty: Some(3),
+        },
+        ChartChoice {
+            chart_type: Some("ST"),
+            difficulty: None,
+        },
+        ChartChoice {
+            chart_type: None,
+            difficulty: Some(4),
+        },
+    ] {
+        let id = ComponentId::new(Action::Chart(choice), "maimai", "Selector");
+        assert_eq!(ComponentId::parse(&id.to_string()), Some(id));
+    }
+    // Ids from before the switcher still open the default chart
+    assert_eq!(
+        ComponentId::parse("gcm:chart:maimai:Selector")
+            .unwrap()
+            .action,
+        Action::Chart(ChartChoice::default())
+    );
+    assert_eq!(ComponentId::parse("gcm:chart.XX.3:maimai:Selector"), None);
+}
@@ -0,0 +1,64 @@
+//! Shared harness for the fixture-based tests.
+//!
+//! The loaders read `data/...` relative to the working directory, except for the version lists,
+//! which are the real `data/versions.toml`. The URL files under `tests/fixtures/data` hold
+//! `file://` URLs into `tests/fixtures/upstream` instead of real ones, which `get_curl` reads
+//! from disk.
+//! Set `UPDATE_GOLDEN=1` to rewrite the golden files after an intended change.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    fs,
+    path::PathBuf,
+    sync::Once,
+};
+
+use gcm_bot::{catalog::*, versions};
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Makes `tests/fixtures` the working directory of this test binary.
+pub fn enter_fixtures() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let real = manifest_dir().join("data").join(versions::VERSIONS_FILE);
+        versions::set(versions::Versions::load(&real).unwrap()).unwrap();
+        std::env::set_current_dir(manifest_dir().join("tests/fixtures")).unwrap();
+    });
+}
+
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = manifest_dir().join("tests/golden").join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{:?}: {} (run with UPDATE_GOLDEN=1 to create)", path, e));
+    if expected != actual {
+        panic!(
+            "{} does not match the golden file; rerun with UPDATE_GOLDEN=1 if this is intended\n\n{}",
+            name,
+            actual
+        );
+    }
+}
+
+/// One line per song, sorted by key.
+pub fn dump_charts<T: Debug>(charts: &HashMap<String, T>) -> String {
+    charts
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(title, info)| format!("{}\t{:?}\n", title, info))
+        .collect()
+}
+
+pub fn catalog<T>(charts: HashMap<String, T>, game: &str) -> Catalog<T> {
+    Catalog::load(charts, game).unwrap()
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    catalog::GameCatalog,
+    components::{Action, ComponentId},
+    maimai::set_mai_charts,
+};
+
+#[test]
+fn custom_ids() {
+    let id = ComponentId::new(Action::Info, "maimai", "Bad Apple!! feat.nomico");
+    assert_eq!(id.to_string(), "gcm:info:maimai:Bad Apple!! feat.nomico");
+    assert_eq!(ComponentId::parse(&id.to_string()), Some(id));
+
+    // Colons in titles survive
+    let id = ComponentId::new(Action::Jacket, "chuni", "Re:End of a Dream");
+    assert_eq!(ComponentId::parse(&id.to_string()).unwrap(), id);
+
+    // Ids of other bots or older versions are ignored
+    assert_eq!(ComponentId::parse("Selector"), None);
+    assert_eq!(ComponentId::parse("gcm:dance:maimai:Selector"), None);
+    assert_eq!(ComponentId::parse("gcm:info"), None);
+}
+
+#[test]
+fn song_lookup() {
+    enter_fixtures();
+    let mut charts = set_mai_charts().unwrap();
+    let long = "Bad Apple!! feat.nomico".repeat(5);
+    let song = charts.remove("Selector").unwrap();
+    charts.insert(long.clone(), song);
+    let maimai = catalog(charts, "maimai");
+    for (title, _) in maimai.songs() {
+        let id = ComponentId::new(Action::Card, "maimai", title);
+        assert_eq!(id.title(&maimai).as_deref(), Some(title.as_str()));
+    }
+
+    // Titles too long for a custom id are stored as a hash
+    let id = ComponentId::new(Action::Info, "maimai", &long).to_string();
+    assert!(id.len() <= 100);
+    assert!(id.starts_with("gcm:info:maimai:#"));
+    assert_eq!(ComponentId::parse(&id).unwrap().title(&maimai), Some(long));
+
+    let gone = ComponentId::new(Action::Info, "maimai", "Deleted long ago");
+    assert_eq!(gone.title(&maimai), None);
+}
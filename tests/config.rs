@@ -0,0 +1,132 @@
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use gcm_bot::config::{env_names, Config};
+
+fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn defaults() {
+    let config = Config::parse("", env(&[])).unwrap();
+    assert_eq!(config.data_dir, Path::new("data"));
+    assert!(config.owners.is_empty());
+    assert!(!config.notify_owners);
+    assert_eq!(config.timeouts.update_check_hours, 6);
+    assert_eq!(config.maimai.jp_url, None);
+    assert!(config.chuni.jp_and_intl_version_is_different);
+}
+
+#[test]
+fn file_and_environment() {
+    let text = r#"
+owners = [1, 2]
+
+[timeouts]
+update_check_hours = 3
+
+[maimai]
+jp_url = "https://example.com/maimai.json"
+"#;
+    let config = Config::parse(
+        text,
+        env(&[
+            ("GCM_TIMEOUTS_UPDATE_CHECK_HOURS", "12"),
+            ("GCM_DATA_DIR", "/srv/gcm data"),
+            ("GCM_OWNERS", "[3]"),
+            ("GCM_CHUNI_JACKET_PREFIX", "https://example.com/jackets/"),
+            ("GCM_CONFIG", "ignored.toml"),
+            ("PATH", "/usr/bin"),
+        ]),
+    )
+    .unwrap();
+    assert_eq!(config.timeouts.update_check_hours, 12);
+    assert_eq!(config.data_dir, Path::new("/srv/gcm data"));
+    assert_eq!(config.owners, vec![3]);
+    assert_eq!(
+        config.maimai.jp_url.as_deref(),
+        Some("https://example.com/maimai.json")
+    );
+    assert_eq!(
+        config.chuni.jacket_prefix.as_deref(),
+        Some("https://example.com/jackets/")
+    );
+}
+
+#[test]
+fn invalid() {
+    assert!(Config::parse("[maimai]\njp_ulr = \"typo\"", env(&[])).is_err());
+    assert!(Config::parse("", env(&[("GCM_TIMEOUTS_UPDATE_CHECK_HOURS", "soon")])).is_err());
+    assert!(Config::parse("[timeouts]\nupdate_check_hours = 0", env(&[])).is_err());
+}
+
+#[test]
+fn environment_types() {
+    let config = Config::parse(
+        "",
+        env(&[
+            // Text settings stay text whatever they look like
+            ("GCM_DATA_DIR", "123"),
+            ("GCM_MAIMAI_CHART_VIEWER", "true"),
+            ("GCM_OWNERS", "1, 2"),
+            ("GCM_NOTIFY_OWNERS", "true"),
+            ("GCM_CHUNI_JP_AND_INTL_VERSION_IS_DIFFERENT", "false"),
+            ("GCM_ONGEKI_CURRENT_VERSION", "bright MEMORY Act.3"),
+            // Not settings
+            ("GCM_NOT_A_SETTING", "1"),
+            ("GCM_TIMEOUTS", "soon"),
+        ]),
+    )
+    .unwrap();
+    assert_eq!(config.data_dir, Path::new("123"));
+    assert_eq!(config.maimai.chart_viewer.as_deref(), Some("true"));
+    assert_eq!(config.owners, vec![1, 2]);
+    assert!(config.notify_owners);
+    assert!(!config.chuni.jp_and_intl_version_is_different);
+    assert_eq!(
+        config.current_version("ongeki"),
+        Some("bright MEMORY Act.3")
+    );
+    assert_eq!(config.current_version("maimai"), None);
+
+    assert!(Config::parse("", env(&[("GCM_NOTIFY_OWNERS", "yes")])).is_err());
+    assert!(Config::parse("", env(&[("GCM_OWNERS", "[1, two]")])).is_err());
+}
+
+#[test]
+fn every_setting_from_environment() {
+    let names = env_names();
+    let table = toml::Table::try_from(Config::default()).unwrap();
+    for (key, value) in table {
+        let keys = match value {
+            toml::Value::Table(inner) => inner
+                .keys()
+                .map(|inner| format!("{}_{}", key, inner))
+                .collect(),
+            _ => vec![key],
+        };
+        for key in keys {
+            let name = format!("GCM_{}", key.to_uppercase());
+            assert!(names.contains(&name), "{} is missing", name);
+        }
+    }
+}
+
+#[test]
+fn sources() {
+    enter_fixtures();
+    let config = Config::parse("", env(&[])).unwrap();
+    config.check_sources().unwrap();
+    assert!(config
+        .source(&None, "maimai/maimai-jp.txt")
+        .unwrap()
+        .starts_with("file://upstream/"));
+
+    let missing = Config::parse("data_dir = \"missing\"", env(&[])).unwrap();
+    assert!(missing.check_sources().is_err());
+}
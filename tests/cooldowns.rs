@@ -0,0 +1,74 @@
+use gcm_bot::cooldowns::*;
+use poise::serenity_prelude::{ChannelId, GuildId, UserId};
+
+const GUILD: GuildId = GuildId(1);
+const GENERAL: ChannelId = ChannelId(10);
+const BOT_COMMANDS: ChannelId = ChannelId(11);
+const ALICE: UserId = UserId(100);
+const BOB: UserId = UserId(101);
+
+#[test]
+fn user_and_channel_cooldowns() {
+    let mut cooldowns = Cooldowns::default();
+    assert_eq!(cooldowns.check(GUILD, GENERAL, ALICE, 0), Cooldown::None);
+    assert_eq!(cooldowns.check(GUILD, GENERAL, ALICE, 0), Cooldown::None);
+
+    cooldowns.set(GUILD, 60, 10);
+    assert_eq!(cooldowns.check(GUILD, GENERAL, ALICE, 0), Cooldown::None);
+    assert_eq!(
+        cooldowns.check(GUILD, GENERAL, ALICE, 5),
+        Cooldown::User(55)
+    );
+    assert_eq!(
+        cooldowns.check(GUILD, GENERAL, BOB, 5),
+        Cooldown::Channel(5)
+    );
+    assert_eq!(cooldowns.check(GUILD, GENERAL, BOB, 10), Cooldown::None);
+    assert_eq!(cooldowns.check(GUILD, GENERAL, ALICE, 60), Cooldown::None);
+    // Other guilds are not limited
+    assert_eq!(
+        cooldowns.check(GuildId(2), GENERAL, ALICE, 60),
+        Cooldown::None
+    );
+
+    assert!(cooldowns.set_exempt(GUILD, BOT_COMMANDS, true));
+    assert!(!cooldowns.set_exempt(GUILD, BOT_COMMANDS, true));
+    assert_eq!(
+        cooldowns.check(GUILD, BOT_COMMANDS, ALICE, 61),
+        Cooldown::None
+    );
+    assert_eq!(
+        cooldowns.check(GUILD, BOT_COMMANDS, ALICE, 61),
+        Cooldown::None
+    );
+    assert!(cooldowns.set_exempt(GUILD, BOT_COMMANDS, false));
+    assert_eq!(
+        cooldowns.check(GUILD, BOT_COMMANDS, ALICE, 61),
+        Cooldown::User(59)
+    );
+
+    cooldowns.set(GUILD, 0, 0);
+    assert!(cooldowns.guild(GUILD).is_none());
+    assert_eq!(cooldowns.check(GUILD, GENERAL, ALICE, 62), Cooldown::None);
+}
+
+#[test]
+fn persisted() {
+    let path = std::env::temp_dir().join(format!("gcm-cooldowns-{}.json", std::process::id()));
+    assert!(Cooldowns::load(&path).unwrap().guild(GUILD).is_none());
+
+    let mut cooldowns = Cooldowns::default();
+    cooldowns.set(GUILD, 60, 0);
+    cooldowns.set_exempt(GUILD, BOT_COMMANDS, true);
+    assert_eq!(cooldowns.check(GUILD, GENERAL, ALICE, 0), Cooldown::None);
+    cooldowns.save(&path).unwrap();
+
+    let mut loaded = Cooldowns::load(&path).unwrap();
+    let settings = loaded.guild(GUILD).unwrap();
+    assert_eq!((settings.user_secs, settings.channel_secs), (60, 0));
+    assert!(settings.exempt_channels.contains(&BOT_COMMANDS.0));
+    // Query times are not saved
+    assert_eq!(loaded.check(GUILD, GENERAL, ALICE, 30), Cooldown::None);
+    assert_eq!(loaded.check(GUILD, GENERAL, ALICE, 40), Cooldown::User(50));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    catalog::*, chuni::set_chuni_charts, cross_game::*, maimai::set_mai_charts,
+    ongeki::set_ongeki_charts,
+};
+use poise::serenity_prelude::GuildId;
+
+#[test]
+fn every_game() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let mut charts = set_chuni_charts().unwrap();
+    // The fixtures share no songs, so put one in CHUNITHM too
+    let song = charts.remove("Gone Away").unwrap();
+    charts.insert("Selector".to_string(), song);
+    let chuni = catalog(charts, "chuni");
+    let ongeki = catalog(set_ongeki_charts().unwrap(), "ongeki");
+    let catalogs: [&dyn GameCatalog; 3] = [&maimai, &chuni, &ongeki];
+    let find = |query| {
+        find_song(&catalogs, query, GuildId(0))
+            .into_iter()
+            .map(|game| (game.catalog.id().to_string(), game.title, game.closest))
+            .collect::<Vec<_>>()
+    };
+
+    // "sel" is only a maimai alias, but the title finds the CHUNITHM song too
+    assert_eq!(
+        find("sel"),
+        [
+            ("maimai".to_string(), "Selector".to_string(), false),
+            ("chuni".to_string(), "Selector".to_string(), false),
+        ]
+    );
+    assert_eq!(
+        find("zebel"),
+        [("chuni".to_string(), "Xevel".to_string(), false)]
+    );
+
+    // Nothing known: the closest title of every game
+    let closest = find("Singularlty");
+    assert_eq!(closest.len(), 3);
+    assert!(closest.iter().all(|(_, _, closest)| *closest));
+    assert_eq!(closest[2].1, "Singularity");
+
+    let matches = find_song(&catalogs, "sel", GuildId(0));
+    let document = cross_game_document(&matches, "sel", &RenderOptions::default()).unwrap();
+    assert_eq!(document.title, "Selector");
+    assert_eq!(document.description, "");
+    let names = document
+        .fields
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["maimai", "CHUNITHM"]);
+    assert!(document.fields[0]
+        .value
+        .starts_with("**Level(DX)**\n:flag_jp: [B]"));
+    assert!(document
+        .fields
+        .iter()
+        .all(|field| field.value.len() <= 1024));
+    assert_eq!(document.thumbnail, maimai.jacket_url("Selector"));
+
+    let matches = find_song(&catalogs, "Singularlty", GuildId(0));
+    let document = cross_game_document(&matches, "Singularlty", &RenderOptions::default()).unwrap();
+    assert!(document.description.contains("**Singularlty**"));
+    assert_eq!(
+        document.fields[2].name,
+        "O.N.G.E.K.I.: Singularity (closest match)"
+    );
+}
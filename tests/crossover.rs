@@ -0,0 +1,78 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    chuni::set_chuni_charts,
+    crossover::*,
+    maimai::set_mai_charts,
+    ongeki::set_ongeki_charts,
+    utils::{ChuniCategory, Game, MaiCategory},
+};
+
+#[test]
+fn shared_songs() {
+    enter_fixtures();
+    let mut maimai = set_mai_charts().unwrap();
+    maimai.get_mut("Selector").unwrap().category = MaiCategory::PopAnime;
+    // The fixtures share no songs, so copy some over under maimai titles
+    let mut chuni = set_chuni_charts().unwrap();
+    let mut song = chuni.remove("Gone Away").unwrap();
+    song.category = ChuniCategory::Original;
+    chuni.insert("Selector".to_string(), song);
+    let song = chuni.remove("Xevel").unwrap();
+    chuni.insert("Link".to_string(), song);
+    let mut ongeki = set_ongeki_charts().unwrap();
+    let song = ongeki.remove("Opfer").unwrap();
+    ongeki.insert("Selector".to_string(), song);
+    let charts = Charts {
+        maimai: &maimai,
+        chuni: &chuni,
+        ongeki: &ongeki,
+    };
+    let mut data = CrossoverData::default();
+    let find = |data: &CrossoverData, games: GameSet, origin| {
+        crossover_songs(
+            charts,
+            data,
+            &CrossoverFilter {
+                games: games.games(),
+                origin,
+            },
+        )
+    };
+
+    assert_eq!(find(&data, GameSet::All, None), ["Selector"]);
+    // In kana order: セレクター, リンク
+    assert_eq!(
+        find(&data, GameSet::MaimaiChunithm, None),
+        ["Selector", "Link"]
+    );
+    assert!(find(&data, GameSet::MaimaiOngeki, Some(Game::Maimai)).is_empty());
+    assert_eq!(
+        find(&data, GameSet::All, Some(Game::Chunithm)),
+        ["Selector"]
+    );
+
+    data.exclude.insert("Selector".to_string());
+    assert!(find(&data, GameSet::All, None).is_empty());
+    data.extra
+        .insert("chuni".to_string(), vec!["TiamaT:F minor".to_string()]);
+    assert_eq!(
+        find(&data, GameSet::All, Some(Game::Chunithm)),
+        ["TiamaT:F minor"]
+    );
+    assert!(find(&data, GameSet::All, Some(Game::Ongeki)).is_empty());
+    assert!(find(&data, GameSet::MaimaiOngeki, None).is_empty());
+}
+
+#[test]
+fn wiki_tables() {
+    let mut data = CrossoverData::default();
+    data.wiki_pages
+        .insert("Ring".to_string(), "Ring(CHUNITHM)".to_string());
+    let titles = ["A", "Ring", "C", "D", "E"].map(String::from);
+    assert_eq!(
+        wiki_table(&titles, &data),
+        "||<width=25%> [[A]] ||<width=25%> [[Ring(CHUNITHM)|Ring]] ||<width=25%> [[C]] ||<width=25%> [[D]] ||\n|| [[E]] ||\n"
+    );
+}
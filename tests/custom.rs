@@ -0,0 +1,158 @@
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use gcm_bot::{
+    catalog::*,
+    custom::*,
+    maimai::set_mai_charts,
+    utils::{find_game, game_choices},
+};
+use poise::serenity_prelude::GuildId;
+
+fn configs() -> Vec<CustomGameConfig> {
+    enter_fixtures();
+    load_custom_configs(Path::new("data").join(CUSTOM_GAME_DIR).as_path()).unwrap()
+}
+
+fn config(id: &str) -> CustomGameConfig {
+    configs()
+        .into_iter()
+        .find(|config| config.id == id)
+        .unwrap()
+}
+
+#[test]
+fn configs_by_file_name() {
+    let ids = configs()
+        .into_iter()
+        .map(|config| config.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["popn", "taiko"]);
+    assert!(load_custom_configs(Path::new("no such dir"))
+        .unwrap()
+        .is_empty());
+
+    let dir = std::env::temp_dir().join(format!("gcm-custom-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let game = |id: &str, levels: &str| {
+        format!(
+            r#"{{"id": "{id}", "name": "Game", "difficulty_names": ["A", "B"],
+                "source": {{"format": "csv", "path": "songs.csv"}},
+                "fields": {{"title": "title", "levels": [{levels}]}}}}"#
+        )
+    };
+    std::fs::write(dir.join("a.json"), game("game", r#""a""#)).unwrap();
+    assert!(
+        load_custom_configs(&dir).is_err(),
+        "level fields must match"
+    );
+    std::fs::write(dir.join("a.json"), game("game", r#""a", "b""#)).unwrap();
+    assert_eq!(load_custom_configs(&dir).unwrap().len(), 1);
+    std::fs::write(dir.join("b.json"), game("game", r#""a", "b""#)).unwrap();
+    assert!(load_custom_configs(&dir).is_err(), "ids must be unique");
+    std::fs::remove_file(dir.join("b.json")).unwrap();
+
+    for id in [
+        "chuni",
+        "a:b",
+        "../../x",
+        "a/b",
+        "Taiko",
+        "taiko no tatsujin",
+        "",
+        "a-very-long-custom-game-id",
+    ] {
+        std::fs::write(dir.join("a.json"), game(id, r#""a", "b""#)).unwrap();
+        assert!(load_custom_configs(&dir).is_err(), "bad id {id:?}");
+    }
+    std::fs::write(
+        dir.join("a.json"),
+        game("a-long-custom-game-id-25", r#""a", "b""#),
+    )
+    .unwrap();
+    assert_eq!(load_custom_configs(&dir).unwrap().len(), 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn json_source() {
+    let config = config("taiko");
+    let base = Path::new("data").join(CUSTOM_GAME_DIR);
+    let records = config.source.data_source(&base).unwrap().records().unwrap();
+    assert_eq!(records.len(), 2);
+    // Numbers become their text, null becomes empty
+    assert_eq!(records[0]["oni"], "7");
+    assert_eq!(records[1]["artist"], "");
+
+    let charts = set_custom_charts(&config, &base).unwrap();
+    let song = &charts["夏祭り"];
+    assert_eq!(song.artist, "Whiteberry");
+    assert_eq!(song.version.as_deref(), Some("AC1"));
+    assert!(!song.deleted);
+    assert_eq!(song.extra, [("BPM".to_string(), "195".to_string())]);
+    assert_eq!(song.levels[3], Some(("7".to_string(), Some(7.4.into()))));
+    let old = &charts["Old Song"];
+    assert!(old.deleted);
+    assert_eq!(old.levels[1], None);
+}
+
+#[test]
+fn csv_source() {
+    let config = config("popn");
+    let base = Path::new("data").join(CUSTOM_GAME_DIR);
+    let charts = set_custom_charts(&config, &base).unwrap();
+    assert_eq!(charts.len(), 2);
+    let song = &charts["Popperz Chronicle"];
+    assert_eq!(song.artist, "Sota Fujimori");
+    assert_eq!(song.extra, [("Genre".to_string(), "ELECTRIC".to_string())]);
+    assert_eq!(song.levels[3], Some(("42".to_string(), None)));
+    let song = &charts["Mermaid girl"];
+    assert!(song.extra.is_empty());
+    assert_eq!(song.levels[3], None);
+}
+
+#[test]
+fn custom_catalog() {
+    let catalog = set_custom_catalog(config("taiko")).unwrap();
+    assert_eq!(catalog.id(), "taiko");
+    assert_eq!(catalog.name(), "Taiko no Tatsujin");
+    assert_eq!(
+        catalog.lookup("natsu", GuildId(0)).as_deref(),
+        Some("夏祭り")
+    );
+    assert_eq!(
+        catalog.jacket_url("夏祭り").as_deref(),
+        Some("https://jackets.example/taiko/natsumatsuri.png")
+    );
+    let (description, _) = catalog.render("夏祭り", &RenderOptions::default()).unwrap();
+    assert_eq!(
+        description,
+        "**Artist:** Whiteberry\n**Version:** AC1\n**BPM:** 195\n\n\
+         **Level**\nEasy **2** / Normal **4** / Hard **5** / Oni **7** (7.4)"
+    );
+    let (description, _) = catalog
+        .render("Old Song", &RenderOptions::default())
+        .unwrap();
+    assert!(description.starts_with("**THIS SONG IS DELETED**"));
+    assert!(description.ends_with("**Level**\nEasy **1** / Oni **3**"));
+}
+
+#[test]
+fn games_by_id_or_name() {
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let taiko = set_custom_catalog(config("taiko")).unwrap();
+    let catalogs: [(&str, &dyn GameCatalog); 2] = [("maimai", &maimai), ("taiko", &taiko)];
+
+    let id = |query| find_game(&catalogs, query).map(|(id, _)| id);
+    assert_eq!(id("taiko"), Some("taiko"));
+    assert_eq!(id("taiko no tatsujin"), Some("taiko"));
+    assert_eq!(id("MAIMAI"), Some("maimai"));
+    assert_eq!(id("popn"), None);
+    assert_eq!(
+        game_choices(&catalogs, "TAT"),
+        [("Taiko no Tatsujin".to_string(), "taiko".to_string())]
+    );
+    assert_eq!(game_choices(&catalogs, "").len(), 2);
+}
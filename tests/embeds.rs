@@ -0,0 +1,165 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    catalog::*, chart_view::*, chuni::set_chuni_charts, document::Document, maimai::set_mai_charts,
+    ongeki::set_ongeki_charts,
+};
+
+fn render_all(catalog: &dyn GameCatalog, titles: &[&str]) -> String {
+    let mut out = String::new();
+    for title in titles {
+        let (description, _) = catalog.render(title, &RenderOptions::default()).unwrap();
+        out.push_str(&format!(
+            "===== {} ({})\njacket: {:?}\n{}\n\n",
+            catalog.display_title(title),
+            title,
+            catalog.jacket_url(title),
+            description
+        ));
+    }
+    out
+}
+
+#[test]
+fn maimai_embeds() {
+    enter_fixtures();
+    let catalog = catalog(set_mai_charts().unwrap(), "maimai");
+    let titles = [
+        // Deleted
+        "Forgotten Song",
+        // Duplicate titles
+        "Link",
+        "Link (maimai)",
+        // Re:MASTER
+        "Bad Apple!! feat.nomico",
+        // DX not in intl, plus a U･TA･GE chart
+        "Selector",
+    ];
+    assert_golden("maimai-embeds.txt", &render_all(&catalog, &titles));
+}
+
+#[test]
+fn chuni_embeds() {
+    enter_fixtures();
+    let catalog = catalog(set_chuni_charts().unwrap(), "chuni");
+    let titles = [
+        // ULTIMA in JP only
+        "Elemental Creation",
+        // Deleted
+        "Gone Away",
+        // JP only
+        "Reach For The Stars",
+        "Xevel",
+    ];
+    assert_golden("chuni-embeds.txt", &render_all(&catalog, &titles));
+}
+
+#[test]
+fn ongeki_embeds() {
+    enter_fixtures();
+    let catalog = catalog(set_ongeki_charts().unwrap(), "ongeki");
+    let titles = [
+        // LUNATIC added to a normal chart
+        "Opfer",
+        // LUNATIC only
+        "Lunatic Only",
+        // Deleted LUNATIC
+        "Vanished Lunatic",
+        // Deleted
+        "Vanished",
+        // Duplicate titles
+        "Singularity",
+        "Singularity (Arcaea)",
+    ];
+    assert_golden("ongeki-embeds.txt", &render_all(&catalog, &titles));
+}
+
+#[test]
+fn missing_levels() {
+    enter_fixtures();
+    let mut maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let song = maimai.charts.get_mut("Link").unwrap();
+    song.jp_lv = None;
+    song.intl_lv = None;
+    // Missing in one region only
+    maimai
+        .charts
+        .get_mut("Bad Apple!! feat.nomico")
+        .unwrap()
+        .jp_lv = None;
+    // Only the JP levels of deleted songs are shown
+    maimai.charts.get_mut("Forgotten Song").unwrap().jp_lv = None;
+    let mut chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    chuni.charts.get_mut("Gone Away").unwrap().jp_lv = None;
+    let song = chuni.charts.get_mut("Xevel").unwrap();
+    song.jp_lv = None;
+    song.intl_lv = None;
+    let mut ongeki = catalog(set_ongeki_charts().unwrap(), "ongeki");
+    ongeki.charts.get_mut("Opfer").unwrap().lv = None;
+
+    let cases: [(&dyn GameCatalog, &str); 6] = [
+        (&maimai, "Bad Apple!! feat.nomico"),
+        (&maimai, "Link"),
+        (&maimai, "Forgotten Song"),
+        (&chuni, "Gone Away"),
+        (&chuni, "Xevel"),
+        (&ongeki, "Opfer"),
+    ];
+    let mut out = String::new();
+    for (catalog, title) in cases {
+        let result = catalog.render(title, &RenderOptions::default());
+        out.push_str(&format!(
+            "===== {}\n{:?}\n\n",
+            title,
+            result.map(|r| r.0).map_err(|e| e.to_string())
+        ));
+    }
+    assert_golden("missing-levels.txt", &out);
+}
+
+#[test]
+fn documents() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let ongeki = catalog(set_ongeki_charts().unwrap(), "ongeki");
+    let out = [
+        chart_view(
+            &maimai,
+            "Selector",
+            ChartChoice::default(),
+            &Default::default(),
+        )
+        .unwrap()
+        .document
+        .to_text(),
+        maimai.document("Link (maimai)").unwrap().to_text(),
+        ongeki.document("Opfer").unwrap().to_markdown(),
+    ]
+    .join("\n-----\n\n");
+    assert_golden("documents.txt", &out);
+}
+
+#[test]
+fn document_fields() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    let names = |document: Document| {
+        assert!(!document.description.contains("Level"));
+        document
+            .fields
+            .into_iter()
+            .map(|field| field.name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(maimai.document("Selector").unwrap()),
+        ["Level(DX)", "Level(ST)", "U･TA･GE"]
+    );
+    assert_eq!(names(chuni.document("Xevel").unwrap()), ["Level"]);
+
+    let levels = &chuni.document("Xevel").unwrap().fields[0].value;
+    assert!(levels.starts_with(":flag_jp: [B]"));
+    assert!(levels.contains("\n:globe_with_meridians: "));
+}
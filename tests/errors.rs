@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use gcm_bot::errors::Failure;
+use poise::serenity_prelude::Permissions;
+
+#[test]
+fn messages() {
+    let internal = Failure::Internal.message(false);
+    assert!(internal.starts_with("Something went wrong"));
+    assert_ne!(Failure::Internal.message(true), internal);
+
+    let input = Failure::BadArguments(Some("maimia".to_string()));
+    assert!(input.message(false).contains("`maimia`"));
+    assert!(input.message(true).contains("`maimia`"));
+
+    let permissions = Failure::MissingUserPermissions(Some(Permissions::MANAGE_GUILD));
+    assert_eq!(
+        permissions.message(false),
+        "You need the Manage Guilds permission to use this command."
+    );
+
+    // Never "0 seconds"
+    assert_eq!(
+        Failure::Cooldown(Duration::from_millis(300)).message(false),
+        "Please wait 1 seconds and try again."
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::fs;
+
+use common::*;
+use gcm_bot::{
+    catalog::*, chuni::set_chuni_charts, export::*, maimai::set_mai_charts,
+    ongeki::set_ongeki_charts,
+};
+
+#[test]
+fn chart_csv() {
+    enter_fixtures();
+    let dir = std::env::temp_dir().join(format!("gcm-export-csv-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut out = String::new();
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    let ongeki = catalog(set_ongeki_charts().unwrap(), "ongeki");
+    for (game, catalog) in [("chuni", &chuni as &dyn GameCatalog), ("ongeki", &ongeki)] {
+        let path = dir.join(format!("{game}-charts.csv"));
+        write_csv(catalog, &path).unwrap();
+        out.push_str(&format!(
+            "===== {game}\n{}\n",
+            fs::read_to_string(path).unwrap()
+        ));
+    }
+    fs::remove_dir_all(dir).unwrap();
+    assert_golden("export-charts.txt", &out);
+}
+
+#[test]
+fn sqlite_matches_rows() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    let catalogs: [(&str, &dyn GameCatalog); 2] = [("maimai", &maimai), ("chuni", &chuni)];
+    let path = std::env::temp_dir().join(format!("gcm-export-{}.sqlite", std::process::id()));
+    write_sqlite(&catalogs, &path).unwrap();
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    for (game, catalog) in catalogs {
+        let count = |table: &str| -> i64 {
+            conn.query_row(
+                &format!("SELECT COUNT(*) FROM {table} WHERE game = ?1"),
+                [game],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(count("songs") as usize, catalog.songs().count());
+        assert_eq!(count("charts") as usize, chart_rows(catalog).len());
+    }
+    let constant: f64 = conn
+        .query_row(
+            "SELECT constant FROM charts WHERE game = 'chuni' AND title = 'Elemental Creation'
+            AND difficulty = 'EXPERT' AND region = 'JP'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(constant, 12.3);
+    drop(conn);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use common::*;
+use gcm_bot::{
+    catalog::*, chuni::set_chuni_charts, export::songs_json, http::router, images::Fonts,
+    jackets::JacketCache, maimai::set_mai_charts, ongeki::set_ongeki_charts, utils::*,
+};
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+fn app() -> Router {
+    enter_fixtures();
+    let tmp = std::env::temp_dir().join(format!("gcm-http-{}", std::process::id()));
+    std::fs::create_dir_all(&tmp).unwrap();
+    let fonts = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fonts");
+    let data = Data {
+        maimai: catalog(set_mai_charts().unwrap(), "maimai"),
+        chuni: catalog(set_chuni_charts().unwrap(), "chuni"),
+        ongeki: catalog(set_ongeki_charts().unwrap(), "ongeki"),
+        custom: HashMap::new(),
+        previous_snapshot: None,
+        subscriptions: Default::default(),
+        cooldowns: Default::default(),
+        settings: Default::default(),
+        preferences: Default::default(),
+        crossover: Default::default(),
+        artists: Default::default(),
+        jackets: JacketCache::new(tmp.join("jackets")),
+        fonts: Arc::new(Fonts::load(&fonts).unwrap()),
+        manual_alias_files: HashMap::new(),
+        alias_log: Arc::new(Mutex::new(
+            std::fs::File::create(tmp.join("alias-log.tsv")).unwrap(),
+        )),
+    };
+    router(Arc::new(data))
+}
+
+async fn get(uri: &str) -> (StatusCode, Value) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn maimai() -> impl GameCatalog {
+    catalog(set_mai_charts().unwrap(), "maimai")
+}
+
+#[tokio::test]
+async fn song_list() {
+    let (status, body) = get("/songs/maimai").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, serde_json::to_value(songs_json(&maimai())).unwrap());
+    assert!(body.get("Selector").is_some());
+}
+
+#[tokio::test]
+async fn song_by_title_and_alias() {
+    let (status, by_title) = get("/songs/maimai/Selector").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(Some(&by_title), maimai().song_json("Selector").as_ref());
+
+    let (status, by_alias) = get("/songs/maimai/sel").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(by_alias, by_title);
+}
+
+#[tokio::test]
+async fn resolve() {
+    let (status, body) = get("/resolve/maimai?q=bad%20apple").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["title"], "Bad Apple!! feat.nomico");
+    assert!(body["closest"].is_null());
+
+    let (status, body) = get("/resolve/maimai?q=old%20link&server=42").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["title"], "Link (maimai)");
+
+    let (status, body) = get("/resolve/maimai?q=selectorr").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["title"].is_null());
+    assert_eq!(body["closest"]["title"], "Selector");
+}
+
+#[tokio::test]
+async fn not_found() {
+    for uri in [
+        "/songs/nogame",
+        "/songs/maimai/no%20such%20song",
+        "/resolve/nogame?q=sel",
+    ] {
+        let (status, body) = get(uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        assert!(body["error"].is_string(), "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn bad_request() {
+    for uri in ["/search?region=xx", "/search?game=maimai&difficulty=ULTIMA"] {
+        let (status, body) = get(uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert!(body["error"].is_string(), "{uri}");
+    }
+}
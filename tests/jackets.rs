@@ -0,0 +1,92 @@
+mod common;
+
+use std::fs;
+
+use common::*;
+use gcm_bot::{jackets::*, maimai::set_mai_charts};
+
+fn png() -> Vec<u8> {
+    let mut bytes = std::io::Cursor::new(vec![]);
+    image::RgbImage::new(2, 2)
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .unwrap();
+    bytes.into_inner()
+}
+
+#[test]
+fn download_once() {
+    let dir = std::env::temp_dir().join(format!("gcm-jackets-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let remote = dir.join("remote.png");
+    fs::write(&remote, png()).unwrap();
+    let cache = JacketCache::new(dir.join("cache"));
+
+    assert!(!cache.is_cached("maimai", "abc.png"));
+    let url = format!("file://{}", remote.display());
+    assert_eq!(cache.get("maimai", "abc.png", &url).unwrap(), png());
+    // Local files need an explicit file:// URL
+    assert!(cache
+        .get("maimai", "bare.png", remote.to_str().unwrap())
+        .is_err());
+    assert!(cache.is_cached("maimai", "abc.png"));
+    assert_eq!(
+        cache.path("maimai", "sub/abc.png"),
+        dir.join("cache/maimai/sub/abc.png")
+    );
+    assert_ne!(
+        cache.path("maimai", "a/abc.png"),
+        cache.path("maimai", "b/abc.png")
+    );
+    assert_eq!(
+        cache.path("maimai", "../../abc.png"),
+        dir.join("cache/maimai/abc.png")
+    );
+
+    // Served from the cache once the remote is gone
+    fs::remove_file(&remote).unwrap();
+    assert_eq!(cache.get("maimai", "abc.png", &url).unwrap(), png());
+
+    // Anything that isn't an image is rejected and not cached
+    let broken = dir.join("broken.png");
+    fs::write(&broken, "<html>Not Found</html>").unwrap();
+    assert!(cache
+        .get(
+            "maimai",
+            "broken.png",
+            &format!("file://{}", broken.display())
+        )
+        .is_err());
+    assert!(!cache.is_cached("maimai", "broken.png"));
+
+    // Same stem, different extension: both stored, neither left as a temporary file
+    let jpg = dir.join("remote.jpg");
+    fs::write(&jpg, png()).unwrap();
+    cache
+        .get("maimai", "abc.jpg", &format!("file://{}", jpg.display()))
+        .unwrap();
+    let mut names: Vec<_> = fs::read_dir(dir.join("cache/maimai"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["abc.jpg", "abc.png"]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn catalog_jacket() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let dir = std::env::temp_dir().join(format!("gcm-jacket-catalog-{}", std::process::id()));
+    let cache = JacketCache::new(&dir);
+    let path = cache.path("maimai", "link-maimai.png");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, png()).unwrap();
+
+    let (name, bytes) = cache.jacket(&maimai, "Link (maimai)").unwrap().unwrap();
+    assert_eq!(name, "link-maimai.png");
+    assert_eq!(bytes, png());
+    assert!(cache.jacket(&maimai, "No such song").is_none());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::*;
+use gcm_bot::{chuni::set_chuni_charts, maimai::set_mai_charts, ongeki::set_ongeki_charts};
+
+#[test]
+fn maimai_charts() {
+    enter_fixtures();
+    assert_golden(
+        "maimai-charts.txt",
+        &dump_charts(&set_mai_charts().unwrap()),
+    );
+}
+
+#[test]
+fn chuni_charts() {
+    enter_fixtures();
+    assert_golden(
+        "chuni-charts.txt",
+        &dump_charts(&set_chuni_charts().unwrap()),
+    );
+}
+
+#[test]
+fn ongeki_charts() {
+    enter_fixtures();
+    assert_golden(
+        "ongeki-charts.txt",
+        &dump_charts(&set_ongeki_charts().unwrap()),
+    );
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use gcm_bot::{catalog::*, maimai::set_mai_charts, preferences::*, settings::*};
+use poise::serenity_prelude::UserId;
+
+const ALICE: UserId = UserId(100);
+
+#[test]
+fn over_guild_settings() {
+    let guild = GuildSettings {
+        region: RegionSetting::Jp,
+        ..Default::default()
+    };
+    let defaults = UserPreferences::default();
+    assert_eq!(
+        defaults.render_options(&guild),
+        RenderOptions {
+            region: Some(Region::Jp),
+            ..Default::default()
+        }
+    );
+
+    let preferences = UserPreferences {
+        region: Some(RegionSetting::Both),
+        constants: false,
+        ..Default::default()
+    };
+    let options = preferences.render_options(&guild);
+    assert_eq!(options.region, None);
+    assert!(!options.constants);
+    assert!(options.links);
+}
+
+#[test]
+fn persisted() {
+    let path = std::env::temp_dir().join(format!("gcm-preferences-{}.json", std::process::id()));
+    assert_eq!(
+        Preferences::load(&path).unwrap().get(ALICE),
+        UserPreferences::default()
+    );
+
+    let mut preferences = Preferences::default();
+    preferences.update(ALICE, |p| {
+        p.region = Some(RegionSetting::Intl);
+        p.compact = true;
+    });
+    preferences.save(&path).unwrap();
+    assert_eq!(
+        Preferences::load(&path).unwrap().get(ALICE),
+        preferences.get(ALICE)
+    );
+
+    // Users back at the defaults are dropped
+    preferences.update(ALICE, |p| *p = UserPreferences::default());
+    preferences.save(&path).unwrap();
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("\"100\""));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn display_options() {
+    enter_fixtures();
+    let catalog = catalog(set_mai_charts().unwrap(), "maimai");
+    let render = |title, options| catalog.render(title, &options).unwrap().0;
+
+    let plain = render(
+        "Selector",
+        RenderOptions {
+            links: false,
+            ..Default::default()
+        },
+    );
+    assert!(!plain.contains("youtube"));
+    assert!(plain.contains(":flag_jp: B **6** (6.0) / A **8** (8.5)"));
+
+    let no_constants = render(
+        "Selector",
+        RenderOptions {
+            constants: false,
+            ..Default::default()
+        },
+    );
+    assert!(no_constants.contains("BASIC) **6** / "));
+    assert!(!no_constants.contains("(14.1)"));
+
+    let compact = RenderOptions {
+        compact: true,
+        ..Default::default()
+    };
+    assert!(render("Selector", compact).starts_with("**Level(DX)**\n:flag_jp: [B]"));
+    assert!(render("Forgotten Song", compact)
+        .starts_with("**THIS SONG IS DELETED**\n\n**Level(ST)**\n[B]"));
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use gcm_bot::{
+    catalog::*, chuni::set_chuni_charts, images::*, maimai::set_mai_charts,
+    ongeki::set_ongeki_charts, rating::*,
+};
+use poise::serenity_prelude::GuildId;
+
+#[test]
+fn chart_rating() {
+    let mai = |constant, score: &str| {
+        Rules::Maimai.chart_rating(constant, Rules::Maimai.parse_score(score).unwrap())
+    };
+    assert_eq!(mai(12.4, "100.5"), 279);
+    assert_eq!(mai(12.4, "100.7%"), 279);
+    assert_eq!(mai(14.0, "100.0000"), 302);
+    assert_eq!(mai(13.7, "99.4999"), 283);
+    assert_eq!(mai(13.7, "96.9999"), 223);
+    assert_eq!(mai(13.7, "5"), 0);
+    assert!(Rules::Maimai.parse_score("101.5").is_err());
+
+    let chuni = |constant, score: &str| {
+        let rating =
+            Rules::Chunithm.chart_rating(constant, Rules::Chunithm.parse_score(score).unwrap());
+        Rules::Chunithm.format_rating(rating)
+    };
+    assert_eq!(chuni(14.8, "1,010,000"), "16.95");
+    assert_eq!(chuni(14.8, "1009000"), "16.95");
+    assert_eq!(chuni(14.8, "1008000"), "16.85");
+    assert_eq!(chuni(14.8, "1007500"), "16.80");
+    assert_eq!(chuni(14.8, "1005000"), "16.30");
+    assert_eq!(chuni(14.8, "1000000"), "15.80");
+    assert_eq!(chuni(14.8, "975000"), "14.80");
+    assert_eq!(chuni(14.8, "950000"), "13.30");
+    assert_eq!(chuni(14.8, "925000"), "11.80");
+    assert_eq!(chuni(14.8, "900000"), "9.80");
+    assert_eq!(chuni(14.8, "400000"), "0.00");
+    assert!(Rules::Chunithm.parse_score("1010001").is_err());
+
+    let ongeki = |constant, score: &str| {
+        let rating =
+            Rules::Ongeki.chart_rating(constant, Rules::Ongeki.parse_score(score).unwrap());
+        Rules::Ongeki.format_rating(rating)
+    };
+    assert_eq!(ongeki(14.2, "1,010,000"), "16.20");
+    assert_eq!(ongeki(14.2, "1007500"), "16.20");
+    assert_eq!(ongeki(14.2, "1003750"), "15.95");
+    assert_eq!(ongeki(14.2, "1000000"), "15.70");
+    assert_eq!(ongeki(14.2, "990000"), "15.20");
+    assert_eq!(ongeki(14.2, "970000"), "14.20");
+    assert_eq!(ongeki(14.2, "900000"), "10.20");
+    assert_eq!(ongeki(14.2, "800000"), "8.20");
+    assert_eq!(ongeki(14.2, "700000"), "0.00");
+
+    assert_eq!(Rules::Maimai.format_score(1005000), "100.5000%");
+    assert_eq!(Rules::Chunithm.format_score(1009000), "1,009,000");
+}
+
+#[test]
+fn maimai_sheet() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let scores = "# title, difficulty, score, chart type
+Link (maimai)\tMASTER\t100.5
+Link (maimai)\tMAS\t99.0
+VIIIbit Explorer\tMaster\t100
+Selector\tMASTER\t100.5
+Selector\tExpert\t100.5\tDX
+No such song\tMASTER\t100
+Link\tEXPERT\t101.5
+Link\tRe:MASTER\t100
+not a score line
+";
+    let date = "2022-10-01".parse().unwrap();
+    let sheet = RatingSheet::build_at(&maimai, Region::Jp, scores, GuildId(0), date).unwrap();
+    assert_eq!(sheet.rules, Rules::Maimai);
+    assert_eq!(sheet.skipped.len(), 5, "{:#?}", sheet.skipped);
+    assert!(sheet.skipped[0].starts_with("line 5: Selector has both DX and ST charts"));
+
+    let [old, new] = &sheet.frames;
+    let titles = |frame: &[RatedChart]| frame.iter().map(|r| r.title.clone()).collect::<Vec<_>>();
+    // VIIIbit Explorer's ST charts were added in FESTiVAL, the JP version on that date
+    assert_eq!(titles(new), ["VIIIbit Explorer"]);
+    assert_eq!(titles(old), ["Link", "Selector"]);
+    assert!(new[0].estimated);
+    assert_eq!((new[0].constant, new[0].rating), (7.0, 151));
+    assert_eq!((old[0].score, old[0].rating), (1_005_000, 279));
+    assert_eq!(old[1].chart.chart_type, Some("DX"));
+    assert_eq!(old[1].rating, Rules::Maimai.chart_rating(11.6, 1_005_000));
+    assert_eq!(sheet.total(), 279 + old[1].rating + 151);
+
+    let fonts = Fonts::load(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join(FONT_DIR),
+    )
+    .unwrap();
+    let png = sheet.render(&fonts, |_| None).unwrap();
+    let image = image::load_from_memory(&png).unwrap();
+    assert_eq!(
+        (image.width(), image.height()),
+        (24 * 2 + 5 * 270 + 4 * 8, 96 + 2 * (44 + 112) + 24)
+    );
+}
+
+#[test]
+fn chuni_sheet() {
+    enter_fixtures();
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    let scores = "Elemental Creation\tULTIMA\t1009000\nElemental Creation\tULT\t1007500\tDX\n";
+    let sheet = RatingSheet::build(&chuni, Region::Jp, scores, GuildId(0)).unwrap();
+    assert_eq!(sheet.frames[0].len(), 1);
+    assert_eq!(sheet.frames[0][0].rating, 1695);
+    // Averaged over all 50 slots
+    assert_eq!(sheet.rules.format_rating(sheet.total()), "0.33");
+    assert_eq!(sheet.skipped.len(), 1);
+
+    let intl = RatingSheet::build(&chuni, Region::Intl, scores, GuildId(0)).unwrap();
+    assert!(intl.frames.iter().all(|frame| frame.is_empty()));
+}
+
+#[test]
+fn ongeki_sheet() {
+    enter_fixtures();
+    let ongeki = catalog(set_ongeki_charts().unwrap(), "ongeki");
+    let scores = "Lunatic Only\tLUNATIC\t1007500
+Opfer\tMASTER\t1000000
+Singularity (Arcaea)\tMASTER\t970000
+";
+    // bright is the current version, so its songs are new
+    let date = "2022-01-01".parse().unwrap();
+    let sheet = RatingSheet::build_at(&ongeki, Region::Jp, scores, GuildId(0), date).unwrap();
+    assert_eq!(sheet.rules, Rules::Ongeki);
+    assert!(sheet.skipped.is_empty(), "{:#?}", sheet.skipped);
+    let [best, new] = &sheet.frames;
+    assert_eq!(new.len(), 1);
+    assert_eq!(
+        (new[0].title.as_str(), new[0].rating),
+        ("Lunatic Only", 1620)
+    );
+    assert_eq!(best.len(), 2);
+    assert_eq!((best[0].title.as_str(), best[0].rating), ("Opfer", 1520));
+    assert!(best[1].estimated);
+    assert_eq!(best[1].rating, 1400);
+    // Averaged over all 45 slots
+    assert_eq!(sheet.total(), (1620 + 1520 + 1400) / 45);
+
+    // Once bright MEMORY is out, bright songs are old
+    let date = "2022-03-03".parse().unwrap();
+    let sheet = RatingSheet::build_at(&ongeki, Region::Jp, scores, GuildId(0), date).unwrap();
+    assert!(sheet.frames[1].is_empty());
+}
+
+#[test]
+fn scores_attachment() {
+    assert!(check_scores_attachment(1000, Some("text/plain; charset=utf-8")).is_ok());
+    assert!(check_scores_attachment(1000, Some("text/tab-separated-values")).is_ok());
+    assert!(check_scores_attachment(1000, None).is_ok());
+    assert!(check_scores_attachment(MAX_SCORES_SIZE + 1, Some("text/plain")).is_err());
+    assert!(check_scores_attachment(1000, Some("image/png")).is_err());
+}
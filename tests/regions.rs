@@ -0,0 +1,25 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    chuni::set_chuni_charts, maimai::set_mai_charts, ongeki::set_ongeki_charts,
+    regions::region_diff,
+};
+
+#[test]
+fn region_diffs() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    let ongeki = catalog(set_ongeki_charts().unwrap(), "ongeki");
+    let out = [
+        region_diff(&maimai),
+        region_diff(&chuni),
+        region_diff(&ongeki),
+    ]
+    .iter()
+    .map(|diff| diff.to_markdown())
+    .collect::<Vec<_>>()
+    .join("\n");
+    assert_golden("region-diffs.txt", &out);
+}
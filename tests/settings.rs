@@ -0,0 +1,96 @@
+mod common;
+
+use common::*;
+use gcm_bot::{catalog::*, maimai::set_mai_charts, settings::*};
+use poise::serenity_prelude::{ChannelId, GuildId, Permissions};
+
+const GUILD: GuildId = GuildId(1);
+const GENERAL: ChannelId = ChannelId(10);
+const BOT_COMMANDS: ChannelId = ChannelId(11);
+
+#[test]
+fn channel_lists() {
+    let mut settings = Settings::default();
+    assert!(settings.get(GUILD).channel_allowed(GENERAL));
+
+    settings.update(GUILD, |s| {
+        s.blocked_channels.insert(GENERAL.0);
+    });
+    assert!(!settings.get(GUILD).channel_allowed(GENERAL));
+    assert!(settings.get(GUILD).channel_allowed(BOT_COMMANDS));
+    // Other guilds keep the defaults
+    assert!(settings.get(GuildId(2)).channel_allowed(GENERAL));
+
+    settings.update(GUILD, |s| {
+        s.blocked_channels.clear();
+        s.allowed_channels.insert(BOT_COMMANDS.0);
+    });
+    assert!(!settings.get(GUILD).channel_allowed(GENERAL));
+    assert!(settings.get(GUILD).channel_allowed(BOT_COMMANDS));
+}
+
+#[test]
+fn alias_permission() {
+    let moderator = Permissions::MANAGE_MESSAGES;
+    assert!(AliasPermission::Everyone.allows(Permissions::empty()));
+    assert!(!AliasPermission::Moderators.allows(Permissions::empty()));
+    assert!(AliasPermission::Moderators.allows(moderator));
+    assert!(AliasPermission::Moderators.allows(Permissions::ADMINISTRATOR));
+    assert!(!AliasPermission::Nobody.allows(Permissions::ADMINISTRATOR));
+}
+
+#[test]
+fn persisted() {
+    let path = std::env::temp_dir().join(format!("gcm-settings-{}.json", std::process::id()));
+    assert_eq!(
+        Settings::load(&path).unwrap().get(GUILD),
+        GuildSettings::default()
+    );
+
+    let mut settings = Settings::default();
+    settings.update(GUILD, |s| {
+        s.region = RegionSetting::Intl;
+        s.default_game = Some("chuni".to_string());
+        s.ephemeral = true;
+    });
+    settings.save(&path).unwrap();
+    let loaded = Settings::load(&path).unwrap().get(GUILD);
+    assert_eq!(loaded, settings.get(GUILD));
+    assert_eq!(loaded.render_options().region, Some(Region::Intl));
+
+    // Guilds back at the defaults are dropped
+    settings.update(GUILD, |s| *s = GuildSettings::default());
+    settings.save(&path).unwrap();
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("\"1\""));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn region_option() {
+    enter_fixtures();
+    let catalog = catalog(set_mai_charts().unwrap(), "maimai");
+    let render = |region| {
+        catalog
+            .render(
+                "Selector",
+                &RenderOptions {
+                    region,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .0
+    };
+
+    let both = render(None);
+    assert!(both.contains(":globe_with_meridians: **Unreleased**"));
+
+    let jp = render(Some(Region::Jp));
+    assert!(!jp.contains(":globe_with_meridians:"));
+    assert!(jp.contains("**Level(DX)**\n:flag_jp: [B]"));
+    assert!(jp.contains("**Level(ST)**\n:flag_jp: [B]"));
+
+    let intl = render(Some(Region::Intl));
+    assert!(!intl.contains(":flag_jp:"));
+    assert!(intl.contains("**Level(DX)**\n:globe_with_meridians: **Unreleased**"));
+}
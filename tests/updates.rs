@@ -0,0 +1,86 @@
+mod common;
+
+use common::*;
+use gcm_bot::{
+    catalog::*, changes::*, chuni::set_chuni_charts, maimai::set_mai_charts, updates::*,
+};
+use poise::serenity_prelude::ChannelId;
+
+#[test]
+fn subscriptions_persist() {
+    let path = std::env::temp_dir().join(format!("gcm-subscriptions-{}.json", std::process::id()));
+    let mut subscriptions = Subscriptions::load(&path).unwrap();
+    assert!(subscriptions.channels("maimai").is_empty());
+
+    assert!(subscriptions.subscribe("maimai", ChannelId(1)));
+    assert!(!subscriptions.subscribe("maimai", ChannelId(1)));
+    assert!(subscriptions.subscribe("maimai", ChannelId(2)));
+    assert!(subscriptions.subscribe("chuni", ChannelId(1)));
+    assert!(subscriptions.unsubscribe("maimai", ChannelId(2)));
+    assert!(!subscriptions.unsubscribe("ongeki", ChannelId(2)));
+    subscriptions.save(&path).unwrap();
+
+    let subscriptions = Subscriptions::load(&path).unwrap();
+    assert_eq!(subscriptions.channels("maimai"), vec![ChannelId(1)]);
+    assert_eq!(subscriptions.channels("chuni"), vec![ChannelId(1)]);
+    assert!(subscriptions.channels("ongeki").is_empty());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn embed_limits() {
+    enter_fixtures();
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let mut new_songs = vec!["Selector".to_string()];
+    new_songs.extend((0..200).map(|i| format!("New Song {i}")));
+    let changes = GameChanges {
+        game: "maimai".to_string(),
+        name: "maimai".to_string(),
+        new_songs,
+        deleted_songs: vec![],
+        new_in_region: vec![],
+        removed_from_region: vec![],
+        new_charts: vec![],
+        removed_charts: vec![],
+        changed_charts: vec![],
+    };
+    assert!(changes.to_markdown().chars().count() > 2000);
+
+    let embeds = announcement_embeds(&changes, &maimai);
+    // The summary and 9 of the new songs
+    assert_eq!(embeds.len(), 10);
+    let summary = embeds[0].0["description"].as_str().unwrap();
+    assert!(summary.chars().count() <= 2000);
+    assert!(summary.ends_with("\n…"));
+    assert_eq!(embeds[1].0["title"], "Selector");
+    assert_eq!(embeds[9].0["title"], "New Song 7");
+}
+
+#[test]
+fn announce_once() {
+    enter_fixtures();
+    let path = std::env::temp_dir().join(format!("gcm-announced-{}.json", std::process::id()));
+    let maimai = catalog(set_mai_charts().unwrap(), "maimai");
+    let chuni = catalog(set_chuni_charts().unwrap(), "chuni");
+    let fetched = || Snapshot::capture(&[("chuni", &chuni as &dyn GameCatalog)]);
+
+    let mut announced = Snapshot::capture(&[("maimai", &maimai as &dyn GameCatalog)]);
+    // A game that wasn't known yet is only recorded
+    assert_eq!(unannounced_changes(&mut announced, fetched(), &path), None);
+    announced
+        .games
+        .get_mut("chuni")
+        .unwrap()
+        .songs
+        .remove("Xevel");
+
+    let changes = unannounced_changes(&mut announced, fetched(), &path).unwrap();
+    assert_eq!(changes.new_songs, ["Xevel"]);
+    assert_eq!(unannounced_changes(&mut announced, fetched(), &path), None);
+
+    // After a restart, the saved snapshot keeps the changes from being announced again
+    let mut restarted = Snapshot::load(&path).unwrap();
+    assert_eq!(unannounced_changes(&mut restarted, fetched(), &path), None);
+    assert!(restarted.games.contains_key("maimai"));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::*;
+use gcm_bot::{
+    catalog::*, chuni::set_chuni_charts, maimai::set_mai_charts, ongeki::set_ongeki_charts,
+    versions::*,
+};
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+#[test]
+fn every_chart_has_a_version() {
+    enter_fixtures();
+    let catalogs: [Box<dyn GameCatalog>; 3] = [
+        Box::new(catalog(set_mai_charts().unwrap(), "maimai")),
+        Box::new(catalog(set_chuni_charts().unwrap(), "chuni")),
+        Box::new(catalog(set_ongeki_charts().unwrap(), "ongeki")),
+    ];
+    for catalog in &catalogs {
+        for (title, song) in catalog.songs() {
+            if song.version().is_none() {
+                continue;
+            }
+            for chart in song.chart_entries() {
+                assert!(
+                    chart.version.is_some(),
+                    "{}: {} {:?} has version {:?}, which is not in {}",
+                    catalog.name(),
+                    title,
+                    chart,
+                    song.version(),
+                    VERSIONS_FILE
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn every_ongeki_song_has_a_version() {
+    enter_fixtures();
+    for (title, song) in set_ongeki_charts().unwrap() {
+        assert!(
+            song.version().is_some(),
+            "{} ({}) is older than every version in {}",
+            title,
+            song.date,
+            VERSIONS_FILE
+        );
+    }
+}
+
+#[test]
+fn ongeki_acts() {
+    enter_fixtures();
+    let ongeki = versions().game("ongeki").unwrap();
+    let at = |d| ongeki.at(date(d), Region::Jp).unwrap().name.as_str();
+    assert_eq!(at("2022-03-03"), "bright MEMORY Act.1");
+    assert_eq!(at("2022-10-01"), "bright MEMORY Act.2");
+    assert_eq!(at("2024-01-01"), "bright MEMORY Act.3");
+    assert_eq!(
+        ongeki.find("bright MEMORY").unwrap().name,
+        "bright MEMORY Act.1"
+    );
+
+    let frame = |d| {
+        ongeki
+            .new_frame_at(date(d), Region::Jp)
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        frame("2024-01-01"),
+        [
+            "bright MEMORY Act.1",
+            "bright MEMORY Act.2",
+            "bright MEMORY Act.3"
+        ]
+    );
+    assert_eq!(frame("2022-03-03"), ["bright MEMORY Act.1"]);
+    assert_eq!(frame("2021-12-01"), ["bright"]);
+    assert!(frame("2018-01-01").is_empty());
+}
+
+#[test]
+fn lookup() {
+    enter_fixtures();
+    let versions = versions();
+    let chuni = versions.game("chuni").unwrap();
+    assert_eq!(chuni.find("CHUNITHM AIR").unwrap().name, "CHUNITHM AIR");
+    assert_eq!(chuni.find("air").unwrap().name, "CHUNITHM AIR");
+    assert_eq!(chuni.find("CHUNITHM").unwrap().index, 0);
+    assert_eq!(chuni.find("NEW!!").unwrap().name, "CHUNITHM NEW");
+    assert!(chuni.find("CHUNITHM X").is_none());
+
+    let maimai = versions.game("maimai").unwrap();
+    assert_eq!(maimai.find("maimai").unwrap().index, 0);
+    assert_eq!(maimai.find("GreeN").unwrap().name, "maimai GreeN");
+    assert_eq!(
+        maimai.find("maimai DX PLUS").unwrap().name,
+        "maimaiでらっくす PLUS"
+    );
+
+    let ongeki = versions.game("ongeki").unwrap();
+    assert_eq!(
+        ongeki.at(date("2018-07-26"), Region::Jp).unwrap().name,
+        "オンゲキ"
+    );
+    assert_eq!(
+        ongeki.at(date("2021-10-20"), Region::Jp).unwrap().name,
+        "R.E.D. PLUS"
+    );
+    assert!(ongeki.at(date("2018-07-25"), Region::Jp).is_none());
+    assert!(ongeki.at(date("2022-03-03"), Region::Intl).is_none());
+
+    let current = chuni.current(Region::Jp).unwrap();
+    assert!(current.is_current(Region::Jp));
+    assert!(!chuni.find("CHUNITHM AIR").unwrap().is_current(Region::Jp));
+}
+
+#[test]
+fn release_order() {
+    let ok = r#"
+[game]
+versions = [
+    { name = "A", jp = "2020-01-01", intl = "2020-06-01" },
+    { name = "B", jp = "2020-03-01" },
+    { name = "C", jp = "2020-05-01", intl = "2020-09-01" },
+]
+"#;
+    let versions = Versions::parse(ok).unwrap();
+    let list = versions.game("game").unwrap();
+    assert_eq!(list.at(date("2020-08-01"), Region::Intl).unwrap().name, "A");
+    assert_eq!(list.at(date("2020-08-01"), Region::Jp).unwrap().name, "C");
+
+    assert!(Versions::parse(&ok.replace("2020-09-01", "2020-05-01")).is_err());
+    assert!(Versions::parse(&ok.replace("2020-03-01", "2019-03-01")).is_err());
+    assert!(Versions::parse(&ok.replace("intl =", "global =")).is_err());
+}
+
+#[test]
+fn pinned_current_version() {
+    let mut versions = Versions::parse(
+        r#"
+[game]
+versions = [
+    { name = "A", jp = "2020-01-01", intl = "2020-06-01" },
+    { name = "B", jp = "2020-03-01" },
+    { name = "C", jp = "2020-05-01", intl = "2020-09-01" },
+]
+"#,
+    )
+    .unwrap();
+    assert!(versions.pin_current("game", "D").is_err());
+    assert!(versions.pin_current("other", "A").is_err());
+
+    versions.pin_current("game", "b").unwrap();
+    let list = versions.game("game").unwrap();
+    let now = date("2020-08-01");
+    assert_eq!(list.current_at(now, Region::Jp).unwrap().name, "B");
+    assert_eq!(list.current(Region::Jp).unwrap().name, "B");
+    assert_eq!(list.new_frame_at(now, Region::Jp)[0].name, "B");
+    // International versions still go by release date
+    assert_eq!(list.current_at(now, Region::Intl).unwrap().name, "A");
+    assert_eq!(list.at(now, Region::Jp).unwrap().name, "C");
+}